@@ -0,0 +1,7 @@
+public class BrokenInit {
+    static int value = Missing.compute();
+
+    public static int main() {
+        return value;
+    }
+}
@@ -0,0 +1,5 @@
+public class Calculator {
+    public static int increment(int i) {
+        return i + 1;
+    }
+}
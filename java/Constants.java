@@ -0,0 +1,36 @@
+import java.util.function.IntSupplier;
+
+public class Constants {
+    static long big() {
+        return 1234567890123L;
+    }
+
+    static double pi() {
+        return 3.14159;
+    }
+
+    static float quarter() {
+        return 0.25f;
+    }
+
+    static int large() {
+        return 100000;
+    }
+
+    static String greeting() {
+        return "hello";
+    }
+
+    static Class<?> type() {
+        return Calculator.class;
+    }
+
+    static int viaInterface() {
+        IntSupplier supplier = Constants::large;
+        return supplier.getAsInt();
+    }
+
+    public static int main() {
+        return large();
+    }
+}
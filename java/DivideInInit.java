@@ -0,0 +1,8 @@
+public class DivideInInit {
+    static int zero = 0;
+    static int value = 1 / zero;
+
+    public static int main() {
+        return value;
+    }
+}
@@ -0,0 +1,7 @@
+public class FinalStatic {
+    static final int LIMIT = limit();
+
+    static int limit() {
+        return 100;
+    }
+}
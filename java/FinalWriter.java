@@ -0,0 +1,6 @@
+// compiled against a FinalStatic whose LIMIT is not final, which javac would refuse otherwise
+public class FinalWriter {
+    static {
+        FinalStatic.LIMIT = 5;
+    }
+}
@@ -0,0 +1,41 @@
+public class FloatArithmetic {
+    static float floatSum, floatDifference, floatQuotient, floatRemainder, floatNegated, floatNaN, floatScaled;
+    static double sum, difference, product, quotient, remainder, negated;
+    static double negativeZero, infinity, nan, scaled;
+
+    public static int main() {
+        float x = 7.5f;
+        float y = -2.0f;
+        float zero = 0.0f;
+        floatSum = x + y;
+        floatDifference = y - x;
+        floatQuotient = x / y;
+        floatRemainder = x % y;
+        floatNegated = -x;
+        floatNaN = zero / zero;
+        floatScaled = scale(x);
+
+        double a = -7.5;
+        double b = 2.0;
+        double dzero = 0.0;
+        sum = a + b;
+        difference = a - b;
+        product = a * b;
+        quotient = a / b;
+        remainder = a % b;
+        negated = -a;
+        negativeZero = -dzero;
+        infinity = b / dzero;
+        nan = dzero % dzero;
+        scaled = scale(a, b);
+        return 0;
+    }
+
+    static float scale(float value) {
+        return value * 2.0f + 1.0f;
+    }
+
+    static double scale(double value, double factor) {
+        return value * factor - 1.0;
+    }
+}
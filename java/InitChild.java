@@ -0,0 +1,9 @@
+public class InitChild extends InitParent implements InitDefault {
+    static {
+        InitLog.parentBeforeChild = InitLog.parentInitialized;
+    }
+
+    public static int main() {
+        return InitLog.parentBeforeChild;
+    }
+}
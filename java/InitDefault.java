@@ -0,0 +1,7 @@
+public interface InitDefault {
+    int VALUE = InitLog.markInterface();
+
+    default int value() {
+        return VALUE;
+    }
+}
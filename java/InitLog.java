@@ -0,0 +1,10 @@
+public class InitLog {
+    static int parentInitialized;
+    static int interfaceInitialized;
+    static int parentBeforeChild;
+
+    static int markInterface() {
+        interfaceInitialized = 1;
+        return 1;
+    }
+}
@@ -0,0 +1,5 @@
+public class InitParent {
+    static {
+        InitLog.parentInitialized = 1;
+    }
+}
@@ -0,0 +1,32 @@
+public class IntArithmetic {
+    static int sum, difference, product, quotient, remainder, negated;
+    static int shiftedLeft, shiftedRight, unsignedShifted, and, or, xor;
+    static int overflow, minQuotient, minRemainder, counter;
+
+    public static int main() {
+        int a = -7;
+        int b = 3;
+        int one = 1;
+        int min = one << 31;
+        int counter = 5;
+
+        sum = a + b;
+        difference = b - a;
+        product = a * -300;
+        quotient = a / b;
+        remainder = a % b;
+        negated = -a;
+        shiftedLeft = a << 33;
+        shiftedRight = a >> 1;
+        unsignedShifted = a >>> 28;
+        and = a & b;
+        or = a | b;
+        xor = a ^ b;
+        overflow = (one << 30) + (one << 30);
+        minQuotient = min / -1;
+        minRemainder = min % -1;
+        counter -= 2;
+        IntArithmetic.counter = counter;
+        return sum;
+    }
+}
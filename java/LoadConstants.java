@@ -0,0 +1,6 @@
+public class LoadConstants {
+    static int large = 100000;
+    static long big = 1234567890123L;
+    static float quarter = 0.25f;
+    static double rate = 1.75;
+}
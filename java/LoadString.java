@@ -0,0 +1,3 @@
+public class LoadString {
+    static String name = "hello";
+}
@@ -0,0 +1,34 @@
+public class LongArithmetic {
+    static long sum, difference, product, quotient, remainder, negated;
+    static long shiftedLeft, shiftedRight, unsignedShifted, and, or, xor;
+    static long overflow, minQuotient, minRemainder, mixed;
+
+    public static int main() {
+        long a = -7000000000L;
+        long b = 3;
+        long one = 1;
+        long min = one << 63;
+
+        sum = a + b;
+        difference = b - a;
+        product = a * b;
+        quotient = a / b;
+        remainder = a % b;
+        negated = -a;
+        shiftedLeft = a << 65;
+        shiftedRight = a >> 1;
+        unsignedShifted = a >>> 60;
+        and = a & b;
+        or = a | b;
+        xor = a ^ b;
+        overflow = (one << 62) + (one << 62);
+        minQuotient = min / -1L;
+        minRemainder = min % -1L;
+        mixed = mix(a, b);
+        return 0;
+    }
+
+    static long mix(long x, long y) {
+        return (x ^ y) + 1L;
+    }
+}
@@ -0,0 +1,8 @@
+public class MultiClass {
+    public static void main(String[] args) {
+        System.out.println(main());
+    }
+    public static int main() {
+        return Calculator.increment(2);
+    }
+}
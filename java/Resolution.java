@@ -0,0 +1,37 @@
+// classes for the field and method resolution, JVMS 5.4.3.2 - 5.4.3.4
+public class Resolution {
+}
+
+interface Named {
+    default int name() {
+        return 1;
+    }
+}
+
+interface Renamed extends Named {
+    default int name() {
+        return 2;
+    }
+}
+
+interface Counted {
+    int LIMIT = 1;
+
+    int count();
+}
+
+abstract class Base implements Counted {
+    static int total;
+
+    static int base() {
+        return 1;
+    }
+}
+
+abstract class Derived extends Base implements Renamed {
+    int size;
+
+    int size() {
+        return size;
+    }
+}
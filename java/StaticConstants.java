@@ -0,0 +1,13 @@
+public class StaticConstants {
+    static final int MAX = 100;
+    static final long BIG = 1234567890123L;
+    static final float HALF = 0.5f;
+    static final double RATE = 1.25;
+    static final boolean ENABLED = true;
+    static final String NAME = "constants";
+    static int counter = MAX + 1;
+
+    public static int main() {
+        return counter;
+    }
+}
@@ -0,0 +1,8 @@
+public class StaticCounter {
+    static int count = 1;
+
+    public static int main() {
+        count = count + 1;
+        return count;
+    }
+}
@@ -0,0 +1,5 @@
+public class Unshared {
+    static int value() {
+        return 1;
+    }
+}
@@ -0,0 +1,25 @@
+import pkg.Hidden;
+import pkg.Members;
+
+// compiled while every member of pkg is public, then pkg is compiled again as it is
+public class Accessor {
+    static int open() {
+        return Members.open;
+    }
+
+    static int hidden() {
+        return Members.hidden;
+    }
+
+    static int secret() {
+        return Members.secret;
+    }
+
+    static int shared() {
+        return Members.shared;
+    }
+
+    static int hiddenClass() {
+        return Hidden.value();
+    }
+}
@@ -0,0 +1,10 @@
+// Outer$Inner reads the private field of its nest host directly, JEP 181
+public class Outer {
+    private static int secret = 1;
+
+    static class Inner {
+        static int peek() {
+            return secret;
+        }
+    }
+}
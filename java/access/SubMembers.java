@@ -0,0 +1,7 @@
+import pkg.Members;
+
+public class SubMembers extends Members {
+    static int shared() {
+        return Members.shared;
+    }
+}
@@ -0,0 +1,7 @@
+package pkg;
+
+class Hidden {
+    public static int value() {
+        return 1;
+    }
+}
@@ -0,0 +1,8 @@
+package pkg;
+
+public class Members {
+    public static int open = 1;
+    static int hidden = 1;
+    private static int secret = 1;
+    protected static int shared = 1;
+}
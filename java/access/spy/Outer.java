@@ -0,0 +1,11 @@
+// compiled apart, only Outer$Spy is taken. it claims Outer as its nest host,
+// but the NestMembers of the real Outer doesn't list it.
+public class Outer {
+    private static int secret = 1;
+
+    static class Spy {
+        static int peek() {
+            return secret;
+        }
+    }
+}
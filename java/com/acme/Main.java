@@ -0,0 +1,12 @@
+package com.acme;
+
+public class Main {
+    public static void main(String[] args) {
+        System.out.println(main());
+    }
+    public static int main() {
+        int a = 1;
+        int b = 2;
+        return a + b;
+    }
+}
@@ -0,0 +1,15 @@
+public class Api {
+    public static Shared LOG = new Shared();
+
+    public static int run(Shared shared) {
+        return shared.id();
+    }
+
+    public static int getLength() {
+        return 1;
+    }
+
+    public static Shared toLowerCase() {
+        return LOG;
+    }
+}
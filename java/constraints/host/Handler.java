@@ -0,0 +1,5 @@
+public class Handler {
+    public int handle(Shared shared) {
+        return shared.id();
+    }
+}
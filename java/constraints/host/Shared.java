@@ -0,0 +1,5 @@
+public class Shared {
+    public int id() {
+        return 1;
+    }
+}
@@ -0,0 +1,10 @@
+public class Client {
+    public static int call() {
+        return Api.run(new Shared());
+    }
+
+    // members with an 'L' in their names
+    public static int log() {
+        return Api.LOG.id() + Api.getLength() + Api.toLowerCase().id();
+    }
+}
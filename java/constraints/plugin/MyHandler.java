@@ -0,0 +1,6 @@
+public class MyHandler extends Handler {
+    @Override
+    public int handle(Shared shared) {
+        return shared.id() * 10;
+    }
+}
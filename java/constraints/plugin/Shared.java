@@ -0,0 +1,6 @@
+// the same name as host/Shared, loaded by the plugin loader first
+public class Shared {
+    public int id() {
+        return 2;
+    }
+}
@@ -0,0 +1,5 @@
+public class Missing {
+    static int compute() {
+        return 1;
+    }
+}
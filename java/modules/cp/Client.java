@@ -0,0 +1,9 @@
+import com.acme.greeting.api.Greeter;
+import com.acme.greeting.internal.Secret;
+
+// on the class path, in the unnamed module
+public class Client {
+    public static int call() {
+        return Greeter.greet() + Secret.value;
+    }
+}
@@ -0,0 +1,7 @@
+package com.legacy;
+
+public class Util {
+    public static int answer() {
+        return 1;
+    }
+}
@@ -0,0 +1,7 @@
+package com.acme.greeting.api;
+
+public class Greeter {
+    public static int greet() {
+        return 42;
+    }
+}
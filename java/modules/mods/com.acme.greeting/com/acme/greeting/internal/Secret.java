@@ -0,0 +1,6 @@
+package com.acme.greeting.internal;
+
+public class Secret {
+    public static int value = 7;
+    private int hidden;
+}
@@ -0,0 +1,4 @@
+module com.acme.greeting {
+    exports com.acme.greeting.api;
+    opens com.acme.greeting.internal to com.acme.app;
+}
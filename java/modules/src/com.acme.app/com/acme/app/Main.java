@@ -0,0 +1,12 @@
+package com.acme.app;
+
+import com.acme.greeting.api.Greeter;
+
+public class Main {
+    static int greeting;
+
+    public static int main() {
+        greeting = Greeter.greet();
+        return greeting;
+    }
+}
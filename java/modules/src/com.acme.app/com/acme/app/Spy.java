@@ -0,0 +1,10 @@
+package com.acme.app;
+
+import com.acme.greeting.internal.Secret;
+
+// compiled with --add-exports com.acme.greeting/com.acme.greeting.internal=com.acme.app
+public class Spy {
+    public static int peek() {
+        return Secret.value;
+    }
+}
@@ -0,0 +1,7 @@
+public class Plugin {
+    static int VERSION = 2;
+
+    public static int version() {
+        return VERSION;
+    }
+}
@@ -0,0 +1,6 @@
+package java.lang.invoke;
+
+// signature polymorphic method only, to resolve it without the JDK
+public abstract class MethodHandle {
+    public final native Object invoke(Object... args);
+}
@@ -0,0 +1,7 @@
+public class Caller {
+    static int result;
+
+    static void run() {
+        result = Greeting.value();
+    }
+}
@@ -0,0 +1,5 @@
+public class Greeting {
+    static int value() {
+        return 1;
+    }
+}
@@ -0,0 +1,10 @@
+// a method is added, which can't be redefined
+public class Greeting {
+    static int value() {
+        return 1;
+    }
+
+    static int other() {
+        return 3;
+    }
+}
@@ -0,0 +1,6 @@
+// the body of value() is changed, which can be redefined
+public class Greeting {
+    static int value() {
+        return 2;
+    }
+}
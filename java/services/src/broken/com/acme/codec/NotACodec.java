@@ -0,0 +1,5 @@
+package com.acme.codec;
+
+// listed as a provider, but doesn't implement Codec
+public class NotACodec {
+}
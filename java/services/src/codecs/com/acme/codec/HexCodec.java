@@ -0,0 +1,9 @@
+package com.acme.codec;
+
+import com.acme.spi.Codec;
+
+public class HexCodec implements Codec {
+    public int id() {
+        return 2;
+    }
+}
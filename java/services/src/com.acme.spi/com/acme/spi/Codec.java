@@ -0,0 +1,5 @@
+package com.acme.spi;
+
+public interface Codec {
+    int id();
+}
@@ -0,0 +1,3 @@
+module com.acme.spi {
+    exports com.acme.spi;
+}
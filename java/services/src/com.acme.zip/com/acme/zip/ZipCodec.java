@@ -0,0 +1,9 @@
+package com.acme.zip;
+
+import com.acme.spi.Codec;
+
+public class ZipCodec implements Codec {
+    public int id() {
+        return 1;
+    }
+}
@@ -0,0 +1,4 @@
+module com.acme.zip {
+    requires com.acme.spi;
+    provides com.acme.spi.Codec with com.acme.zip.ZipCodec;
+}
@@ -0,0 +1,312 @@
+use std::rc::{Rc, Weak};
+
+use crate::class::Class;
+use crate::class_file::{ACC_PRIVATE, ACC_PROTECTED, ACC_PUBLIC};
+use crate::jvm::JVM;
+use crate::module::dotted_name_of;
+use crate::module_graph::module_display_name;
+#[cfg(test)]
+use crate::throwable::INACCESSIBLE_OBJECT_EXCEPTION;
+use crate::throwable::{Throwable, ILLEGAL_ACCESS_ERROR};
+
+/// Access control of a class referenced from `accessor`, JVMS 5.4.4.
+/// a class is accessible if it is in the same run-time package, or if it is public and
+/// the module of `accessor` reads its module, which exports its package to the one of `accessor`.
+/// https://docs.oracle.com/javase/specs/jvms/se17/html/jvms-5.html#jvms-5.4.4
+pub fn check_class_access(vm: &JVM, accessor: &Class, class: &Class) -> Result<(), Throwable> {
+    if class.is_same_runtime_package(accessor) {
+        return Ok(());
+    }
+    if !class.is_public() {
+        return Err(Throwable::new(
+            ILLEGAL_ACCESS_ERROR,
+            format!(
+                "failed to access class {} from class {}",
+                class.descriptor, accessor.descriptor
+            ),
+        ));
+    }
+
+    let module_graph = vm.module_graph();
+    let (from, to) = (accessor.module.as_deref(), class.module.as_deref());
+    let reason = if !module_graph.reads(from, to) {
+        format!(
+            "{} does not read {}",
+            module_display_name(from),
+            module_display_name(to)
+        )
+    } else if !module_graph.is_exported(to, class.package_name(), from) {
+        format!(
+            "{} does not export {} to {}",
+            module_display_name(to),
+            dotted_name_of(class.package_name()),
+            module_display_name(from)
+        )
+    } else {
+        return Ok(());
+    };
+    Err(Throwable::new(
+        ILLEGAL_ACCESS_ERROR,
+        format!(
+            "class {} (in {}) cannot access class {} (in {}) because {}",
+            accessor.descriptor,
+            module_display_name(from),
+            class.descriptor,
+            module_display_name(to),
+            reason
+        ),
+    ))
+}
+
+/// Access check of deep reflection, as `AccessibleObject.setAccessible(true)` by `caller`
+/// on a member of `declaring_class`. a member is accessible if the package is open to
+/// the module of `caller`, or if both the member and its class are public in an exported package.
+/// https://docs.oracle.com/en/java/javase/17/docs/api/java.base/java/lang/reflect/AccessibleObject.html#setAccessible(boolean)
+#[cfg(test)]
+pub fn check_reflective_access(
+    vm: &JVM,
+    caller: &Class,
+    declaring_class: &Class,
+    access_flags: u16,
+    kind: &str,
+    name_and_descriptor: &str,
+) -> Result<(), Throwable> {
+    let module_graph = vm.module_graph();
+    let (from, to) = (caller.module.as_deref(), declaring_class.module.as_deref());
+    let package = declaring_class.package_name();
+    let public = declaring_class.is_public() && access_flags & ACC_PUBLIC != 0;
+    if (public && module_graph.is_exported(to, package, from))
+        || module_graph.is_opened(to, package, from)
+    {
+        return Ok(());
+    }
+    Err(Throwable::new(
+        INACCESSIBLE_OBJECT_EXCEPTION,
+        format!(
+            "Unable to make {} {}.{} accessible: {} does not \"opens {}\" to {}",
+            kind,
+            declaring_class.descriptor,
+            name_and_descriptor,
+            module_display_name(to),
+            dotted_name_of(package),
+            module_display_name(from)
+        ),
+    ))
+}
+
+/// Access control of a field or method declared in `declaring_class`, JVMS 5.4.4.
+/// `kind` is either `field` or `method`, to tell the member in the error message.
+pub fn check_member_access(
+    vm: &JVM,
+    accessor: &Rc<Class>,
+    declaring_class: &Rc<Class>,
+    access_flags: u16,
+    kind: &str,
+    name_and_descriptor: &str,
+) -> Result<(), Throwable> {
+    let accessible = if access_flags & ACC_PUBLIC != 0 {
+        true
+    } else if access_flags & ACC_PRIVATE != 0 {
+        is_nestmate_of(vm, accessor, declaring_class)
+    } else {
+        // protected members are also accessible from the same run-time package
+        declaring_class.is_same_runtime_package(accessor)
+            || (access_flags & ACC_PROTECTED != 0
+                && (Rc::ptr_eq(accessor, declaring_class)
+                    || accessor.is_subclass_of(declaring_class)))
+    };
+    if accessible {
+        return Ok(());
+    }
+
+    let modifier = match access_flags & (ACC_PRIVATE | ACC_PROTECTED) {
+        ACC_PRIVATE => "private",
+        ACC_PROTECTED => "protected",
+        _ => "package-private",
+    };
+    Err(Throwable::new(
+        ILLEGAL_ACCESS_ERROR,
+        format!(
+            "class {} tried to access {} {} {}.{}",
+            accessor.descriptor, modifier, kind, declaring_class.descriptor, name_and_descriptor
+        ),
+    ))
+}
+
+/// classes are nestmates if they have the same nest host, JVMS 5.4.4
+pub fn is_nestmate_of(vm: &JVM, class: &Rc<Class>, other: &Rc<Class>) -> bool {
+    Rc::ptr_eq(class, other) || Rc::ptr_eq(&nest_host_of(vm, class), &nest_host_of(vm, other))
+}
+
+/// The nest host claimed by `NestHost`, if it is loaded by the same loader, in the same
+/// run-time package, and lists the class in its `NestMembers`.
+/// Otherwise the class is the host of its own nest, and no error is raised for it.
+/// The nest host is determined once, and then kept by the class.
+pub fn nest_host_of(vm: &JVM, class: &Rc<Class>) -> Rc<Class> {
+    let cached = class.nest_host.borrow().as_ref().and_then(Weak::upgrade);
+    if let Some(host) = cached {
+        return host;
+    }
+    let host = determine_nest_host(vm, class);
+    *class.nest_host.borrow_mut() = Some(Rc::downgrade(&host));
+    host
+}
+
+fn determine_nest_host(vm: &JVM, class: &Rc<Class>) -> Rc<Class> {
+    let Some(host_name) = class.data().nest_host.clone() else {
+        return Rc::clone(class);
+    };
+    match vm.resolve_class_from(class, &host_name) {
+        Ok(host)
+            if host.is_same_runtime_package(class)
+                && host.data().nest_members.contains(&class.descriptor) =>
+        {
+            host
+        }
+        _ => {
+            println!(
+                "[DEBUG] -- nest host {} of {} is invalid",
+                host_name, class.descriptor
+            );
+            Rc::clone(class)
+        }
+    }
+}
+
+#[cfg(test)]
+pub fn resolve_constant_of(
+    vm: &JVM,
+    class: &Rc<Class>,
+    value: &str,
+) -> Result<crate::runtime_constant_pool::ResolvedConstant, Throwable> {
+    let index = (1..class.data().constant_pool.len() as u16)
+        .find(|&index| class.constant_pool_value_at(index) == value)
+        .unwrap_or_else(|| panic!("{} is not in {}", value, class.descriptor));
+    crate::runtime_constant_pool::resolve_constant(vm, class, index)
+}
+
+/// java/access/Accessor is compiled while every member of pkg/Members and pkg/Hidden are public,
+/// then pkg is compiled again with the actual access
+#[test]
+fn test_member_access() {
+    let vm = JVM::create_with_class_path("java/access");
+    let accessor = vm.resolve_class("Accessor").unwrap();
+
+    assert!(resolve_constant_of(&vm, &accessor, "pkg/Members.open:I").is_ok());
+    for member in ["hidden", "secret", "shared"] {
+        let result = resolve_constant_of(&vm, &accessor, &format!("pkg/Members.{}:I", member));
+        assert!(result.unwrap_err().is(ILLEGAL_ACCESS_ERROR), "{}", member);
+    }
+    let result = resolve_constant_of(&vm, &accessor, "pkg/Members.secret:I");
+    assert_eq!(
+        result.unwrap_err().message,
+        "class Accessor tried to access private field pkg/Members.secret:I"
+    );
+
+    // protected access from a subclass
+    let sub_members = vm.resolve_class("SubMembers").unwrap();
+    assert!(resolve_constant_of(&vm, &sub_members, "pkg/Members.shared:I").is_ok());
+}
+
+#[test]
+fn test_class_access() {
+    let vm = JVM::create_with_class_path("java/access");
+    let accessor = vm.resolve_class("Accessor").unwrap();
+
+    let result = resolve_constant_of(&vm, &accessor, "pkg/Hidden.value:()I");
+
+    assert_eq!(
+        result.unwrap_err().message,
+        "failed to access class pkg/Hidden from class Accessor"
+    );
+}
+
+#[test]
+fn test_private_access_between_nestmates() {
+    // Outer$Spy claims Outer as its nest host, but the NestMembers of Outer only has Outer$Inner.
+    // see java/access/spy/Outer.java
+    let vm = JVM::create_with_class_path("java/access");
+    let outer = vm.resolve_class("Outer").unwrap();
+    let inner = vm.resolve_class("Outer$Inner").unwrap();
+    let spy = vm.resolve_class("Outer$Spy").unwrap();
+
+    assert!(Rc::ptr_eq(&nest_host_of(&vm, &inner), &outer));
+    assert!(Rc::ptr_eq(&nest_host_of(&vm, &spy), &spy));
+    // kept by the classes once determined
+    let cached = |class: &Rc<Class>| class.nest_host.borrow().as_ref().and_then(Weak::upgrade);
+    assert!(Rc::ptr_eq(&cached(&inner).unwrap(), &outer));
+    assert!(Rc::ptr_eq(&cached(&spy).unwrap(), &spy));
+    assert!(resolve_constant_of(&vm, &inner, "Outer.secret:I").is_ok());
+    assert!(resolve_constant_of(&vm, &spy, "Outer.secret:I")
+        .unwrap_err()
+        .is(ILLEGAL_ACCESS_ERROR));
+}
+
+#[cfg(test)]
+fn module_vm(add_exports: &[&str]) -> JVM {
+    use crate::class_path::ClassPath;
+    use crate::module_graph::{ModuleGraph, ModuleOptions};
+
+    let options = ModuleOptions {
+        module_path: vec![std::path::PathBuf::from("java/modules/mods")],
+        add_exports: add_exports.iter().map(|value| value.to_string()).collect(),
+        ..ModuleOptions::default()
+    };
+    let mut vm = JVM::create();
+    let module_graph = ModuleGraph::resolve(&options, Some("com.acme.app")).unwrap();
+    vm.set_module_graph(ClassPath::parse("java/modules/cp"), module_graph);
+    vm
+}
+
+#[test]
+fn test_module_access() {
+    // see java/modules, Client is on the class path
+    let vm = module_vm(&[]);
+    let spy = vm.resolve_class("com/acme/app/Spy").unwrap();
+    let client = vm.resolve_class("Client").unwrap();
+    assert_eq!(spy.module.as_deref(), Some("com.acme.app"));
+    assert_eq!(client.module, None);
+
+    assert!(resolve_constant_of(&vm, &client, "com/acme/greeting/api/Greeter.greet:()I").is_ok());
+    let result = resolve_constant_of(&vm, &client, "com/acme/greeting/internal/Secret.value:I");
+    assert_eq!(
+        result.unwrap_err().message,
+        "class Client (in unnamed module) cannot access class com/acme/greeting/internal/Secret \
+         (in module com.acme.greeting) because module com.acme.greeting does not export \
+         com.acme.greeting.internal to unnamed module"
+    );
+    // opened to com.acme.app, which exports it to the module at run time
+    assert!(resolve_constant_of(&vm, &spy, "com/acme/greeting/internal/Secret.value:I").is_ok());
+}
+
+#[test]
+fn test_module_access_with_added_exports() {
+    let vm = module_vm(&["com.acme.greeting/com.acme.greeting.internal=ALL-UNNAMED"]);
+    let client = vm.resolve_class("Client").unwrap();
+
+    assert!(resolve_constant_of(&vm, &client, "com/acme/greeting/internal/Secret.value:I").is_ok());
+}
+
+#[test]
+fn test_reflective_access() {
+    let vm = module_vm(&["com.acme.greeting/com.acme.greeting.internal=ALL-UNNAMED"]);
+    let spy = vm.resolve_class("com/acme/app/Spy").unwrap();
+    let client = vm.resolve_class("Client").unwrap();
+    let secret = vm
+        .resolve_class("com/acme/greeting/internal/Secret")
+        .unwrap();
+    let hidden = secret.field("hidden:I").unwrap().access_flags;
+
+    assert!(check_reflective_access(&vm, &spy, &secret, hidden, "field", "hidden:I").is_ok());
+    // exported, but not opened to the unnamed module
+    let result = check_reflective_access(&vm, &client, &secret, hidden, "field", "hidden:I");
+    let error = result.unwrap_err();
+    assert!(error.is(INACCESSIBLE_OBJECT_EXCEPTION));
+    assert_eq!(
+        error.message,
+        "Unable to make field com/acme/greeting/internal/Secret.hidden:I accessible: \
+         module com.acme.greeting does not \"opens com.acme.greeting.internal\" to unnamed module"
+    );
+    let value = secret.field("value:I").unwrap().access_flags;
+    assert!(check_reflective_access(&vm, &client, &secret, value, "field", "value:I").is_ok());
+}
@@ -3,12 +3,12 @@ use std::io::{Cursor, Read};
 use std::{fs, io};
 
 pub fn read_binary_file(filename: &String) -> Result<Vec<u8>, io::Error> {
-    let mut file = File::open(&filename)?;
-    let metadata = fs::metadata(&filename)?;
+    let mut file = File::open(filename)?;
+    let metadata = fs::metadata(filename)?;
     let mut buffer = vec![0; metadata.len() as usize];
-    file.read(&mut buffer)?;
+    file.read_exact(&mut buffer)?;
 
-    return Ok(buffer);
+    Ok(buffer)
 }
 
 pub fn read_i8(cursor: &mut Cursor<&[u8]>) -> i8 {
@@ -33,13 +33,14 @@ pub fn read_u16(cursor: &mut Cursor<&[u8]>) -> u16 {
     (left << 8) + right
 }
 
+#[cfg(test)]
 pub fn read_u32(cursor: &mut Cursor<&[u8]>) -> u32 {
     let buf: &mut [u8] = &mut [0; 4];
     cursor.read_exact(buf).unwrap();
     let mut result: u32 = buf[0] as u32;
     let mut itr = 0;
     loop {
-        result = result << 8;
+        result <<= 8;
         result += buf[itr] as u32;
         itr += 1;
         if itr >= 4 {
@@ -49,10 +50,25 @@ pub fn read_u32(cursor: &mut Cursor<&[u8]>) -> u32 {
     result
 }
 
-pub fn read_string_to(cursor: &mut Cursor<&[u8]>, length: usize) -> String {
-    let buf: &mut [u8] = &mut vec![0; length];
+/// little endian readers, for the ZIP format
+pub fn read_u16_le(cursor: &mut Cursor<&[u8]>) -> u16 {
+    let buf: &mut [u8] = &mut [0; 2];
+    cursor.read_exact(buf).unwrap();
+    u16::from_le_bytes([buf[0], buf[1]])
+}
+
+pub fn read_u32_le(cursor: &mut Cursor<&[u8]>) -> u32 {
+    let buf: &mut [u8] = &mut [0; 4];
+    cursor.read_exact(buf).unwrap();
+    u32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]])
+}
+
+pub fn read_u64_le(cursor: &mut Cursor<&[u8]>) -> u64 {
+    let buf: &mut [u8] = &mut [0; 8];
     cursor.read_exact(buf).unwrap();
-    std::str::from_utf8(buf).unwrap().to_string()
+    u64::from_le_bytes([
+        buf[0], buf[1], buf[2], buf[3], buf[4], buf[5], buf[6], buf[7],
+    ])
 }
 
 pub fn read_to(cursor: &mut Cursor<&[u8]>, length: usize) -> Vec<u8> {
@@ -61,12 +77,36 @@ pub fn read_to(cursor: &mut Cursor<&[u8]>, length: usize) -> Vec<u8> {
     buf.to_owned()
 }
 
-pub fn debug_bytes(bytes: &Vec<u8>) {
-    let s: String = bytes
-        .iter()
-        .map(|byte| format!("0x{:02x}, ", byte))
-        .collect();
-    println!("[DEBUG] -- [{}]", s);
+/// the readers for the bytes which may be malformed, such as a class file given by a user
+/// loader. they fail at the end of the bytes rather than panicking.
+pub fn try_read_u8(cursor: &mut Cursor<&[u8]>) -> Result<u8, String> {
+    Ok(try_read_to(cursor, 1)?[0])
+}
+
+pub fn try_read_u16(cursor: &mut Cursor<&[u8]>) -> Result<u16, String> {
+    let buf = try_read_to(cursor, 2)?;
+    Ok(u16::from_be_bytes([buf[0], buf[1]]))
+}
+
+pub fn try_read_u32(cursor: &mut Cursor<&[u8]>) -> Result<u32, String> {
+    let buf = try_read_to(cursor, 4)?;
+    Ok(u32::from_be_bytes([buf[0], buf[1], buf[2], buf[3]]))
+}
+
+pub fn try_read_to(cursor: &mut Cursor<&[u8]>, length: usize) -> Result<Vec<u8>, String> {
+    let start = cursor.position() as usize;
+    let data = *cursor.get_ref();
+    match start.checked_add(length) {
+        Some(end) if end <= data.len() => {
+            cursor.set_position(end as u64);
+            Ok(data[start..end].to_vec())
+        }
+        _ => Err(format!(
+            "truncated at {}, {} more bytes are expected",
+            data.len().min(start),
+            length
+        )),
+    }
 }
 
 #[test]
@@ -118,7 +158,7 @@ fn test_read_u32() {
 }
 
 #[test]
-fn test_read_string_to() {
+fn test_read_to() {
     let bytes: &[u8] = &[
         0x6A, 0x61, 0x76, 0x61, 0x2F, 0x6C, 0x61, 0x6E, 0x67, 0x2F, 0x4F, 0x62, 0x6A, 0x65, 0x63,
         0x74,
@@ -126,21 +166,37 @@ fn test_read_string_to() {
 
     let mut cursor = Cursor::new(bytes);
 
-    let result = read_string_to(&mut cursor, bytes.len());
+    let result = read_to(&mut cursor, bytes.len());
 
-    assert_eq!(result, "java/lang/Object");
+    assert_eq!(result, bytes);
 }
 
 #[test]
-fn test_read_to() {
+fn test_try_read_truncated() {
+    let bytes: &[u8] = &[0xCA, 0xFE, 0xBA];
+    let mut cursor = Cursor::new(bytes);
+
+    assert_eq!(try_read_u16(&mut cursor), Ok(0xCAFE));
+    assert_eq!(
+        try_read_u32(&mut cursor),
+        Err(String::from("truncated at 2, 4 more bytes are expected"))
+    );
+    // nothing is consumed by the failed read
+    assert_eq!(try_read_u8(&mut cursor), Ok(0xBA));
+}
+
+#[test]
+fn test_read_le() {
     let bytes: &[u8] = &[
-        0x6A, 0x61, 0x76, 0x61, 0x2F, 0x6C, 0x61, 0x6E, 0x67, 0x2F, 0x4F, 0x62, 0x6A, 0x65, 0x63,
-        0x74,
+        0x50, 0x4b, 0x03, 0x04, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x80,
     ];
-
     let mut cursor = Cursor::new(bytes);
 
-    let result = read_to(&mut cursor, bytes.len());
+    assert_eq!(read_u16_le(&mut cursor), 0x4b50);
+    assert_eq!(read_u16_le(&mut cursor), 0x0403);
+    assert_eq!(read_u64_le(&mut cursor), 0x8000_0000_0000_0001);
+    assert_eq!(cursor.position(), 12);
 
-    assert_eq!(result, bytes);
+    cursor.set_position(0);
+    assert_eq!(read_u32_le(&mut cursor), 0x04034b50);
 }
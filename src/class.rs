@@ -1,8 +1,14 @@
+use std::cell::RefCell;
 use std::collections::HashMap;
-use std::rc::Rc;
+use std::rc::{Rc, Weak};
+use std::sync::Arc;
 
 use crate::class_attributes::{FieldInfo, MethodInfo};
-use crate::cp_info::constant_pool_value_at;
+use crate::class_data::ClassData;
+use crate::class_file::{ACC_ABSTRACT, ACC_INTERFACE, ACC_PUBLIC, ACC_STATIC};
+use crate::class_loader::LoaderId;
+use crate::runtime_constant_pool::{Constant, RuntimeConstantPool};
+use crate::value::Value;
 
 #[derive(Debug)]
 pub struct MethodRef {
@@ -13,35 +19,256 @@ pub struct MethodRef {
 }
 
 impl MethodRef {
+    /// parse `<class>.<name>:<descriptor>`, such as `com/acme/Main.main:()I`
     pub fn parse_from(full_descriptor: String) -> MethodRef {
-        let class_and_method: Vec<&str> = full_descriptor.split(".").collect();
-        let name_and_descriptor: Vec<&str> = full_descriptor.split(":").collect();
+        let (class, name, descriptor) = split_member_ref(&full_descriptor);
 
         MethodRef {
-            class: String::from(class_and_method[0]),
-            name: String::from(name_and_descriptor[0]),
-            descriptor: String::from(name_and_descriptor[1]),
-            name_and_descriptor: String::from(class_and_method[1]),
+            class: String::from(class),
+            name: String::from(name),
+            descriptor: String::from(descriptor),
+            name_and_descriptor: format!("{}:{}", name, descriptor),
         }
     }
 }
 
+#[derive(Debug)]
+pub struct FieldRef {
+    pub class: String,
+    pub name: String,
+    pub descriptor: String,
+    pub name_and_descriptor: String,
+}
+
+impl FieldRef {
+    /// parse `<class>.<name>:<descriptor>`, such as `InitLog.count:I`
+    pub fn parse_from(full_descriptor: String) -> FieldRef {
+        let (class, name, descriptor) = split_member_ref(&full_descriptor);
+
+        FieldRef {
+            class: String::from(class),
+            name: String::from(name),
+            descriptor: String::from(descriptor),
+            name_and_descriptor: format!("{}:{}", name, descriptor),
+        }
+    }
+}
+
+fn split_member_ref(full_descriptor: &str) -> (&str, &str, &str) {
+    let (class, name_and_descriptor) = full_descriptor
+        .split_once('.')
+        .unwrap_or_else(|| panic!("Invalid member ref: {}", full_descriptor));
+    let (name, descriptor) = name_and_descriptor
+        .split_once(':')
+        .unwrap_or_else(|| panic!("Invalid member ref: {}", full_descriptor));
+    (class, name, descriptor)
+}
+
+pub fn package_name_of(class_name: &str) -> &str {
+    class_name
+        .rsplit_once('/')
+        .map_or("", |(package_name, _)| package_name)
+}
+
+/// the value of a `ConstantValue` attribute.
+/// `None` for a String constant, which needs an interned String object on the heap,
+/// and then the field is left out of the static values.
+fn constant_value_of(constant: &Constant) -> Option<Value> {
+    match *constant {
+        Constant::Integer(value) => Some(Value::Int(value)),
+        Constant::Long(value) => Some(Value::Long(value)),
+        Constant::Float(value) => Some(Value::Float(value)),
+        Constant::Double(value) => Some(Value::Double(value)),
+        _ => None,
+    }
+}
+
+/// Initialization state of a class, JVMS 5.5
+/// https://docs.oracle.com/javase/specs/jvms/se17/html/jvms-5.html#jvms-5.5
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InitState {
+    Uninitialized,
+    /// `<clinit>` is running. there is only one thread, so this is always the current one.
+    InProgress,
+    Initialized,
+    /// `<clinit>` or the initialization of a superclass failed
+    Erroneous,
+}
+
 #[derive(Debug)]
 pub struct Class {
     pub descriptor: String,
-    pub constant_pool: Vec<String>,
-    pub methods: HashMap<String, Rc<MethodInfo>>,
-    pub fields: HashMap<String, Rc<FieldInfo>>,
+    pub access_flags: u16,
+    /// `None` only for java/lang/Object
+    pub super_class: Option<Rc<Class>>,
+    /// direct superinterfaces, in the order of the class file
+    pub interfaces: Vec<Rc<Class>>,
+    /// the parsed class file, shared with other JVMs. replaced by a redefinition.
+    pub data: RefCell<Arc<ClassData>>,
+    /// replaced along with the data by a redefinition, while running frames keep the old one
+    pub runtime_constant_pool: RefCell<Rc<RuntimeConstantPool>>,
+    /// symbolic references of the class are resolved through this loader
+    pub defining_loader: LoaderId,
+    /// the named module of the class, `None` for the unnamed module of its defining loader
+    pub module: Option<String>,
+    /// values of the static fields, by `name:descriptor`. prepared with the default values.
+    pub static_values: RefCell<HashMap<String, Value>>,
+    pub init_state: RefCell<InitState>,
+    /// the nest host once determined, JVMS 5.4.4. weak as it may be the class itself.
+    pub nest_host: RefCell<Option<Weak<Class>>>,
 }
 
 impl Class {
+    /// a class of this JVM over the parsed `data`, prepared but not initialized yet
+    pub fn create(
+        data: Arc<ClassData>,
+        super_class: Option<Rc<Class>>,
+        interfaces: Vec<Rc<Class>>,
+        defining_loader: LoaderId,
+    ) -> Class {
+        // preparation, static fields are created with the default value, and then the ones
+        // with a ConstantValue attribute are set before `<clinit>` runs, JVMS 5.5 step 6
+        let static_values = data
+            .fields
+            .iter()
+            .filter(|(_, field)| field.access_flags & ACC_STATIC != 0)
+            .filter_map(|(field_id, field)| {
+                let (_, descriptor) = field_id.split_once(':').unwrap_or_default();
+                let value = match field.constant_value_index() {
+                    Some(index) => constant_value_of(&data.constants[index as usize])?,
+                    None => Value::default_of(descriptor),
+                };
+                Some((field_id.clone(), value))
+            })
+            .collect();
+
+        Class {
+            descriptor: data.descriptor.clone(),
+            access_flags: data.access_flags,
+            super_class,
+            interfaces,
+            runtime_constant_pool: RefCell::new(Rc::new(RuntimeConstantPool::create(
+                &data.constants,
+            ))),
+            data: RefCell::new(data),
+            defining_loader,
+            module: None,
+            static_values: RefCell::new(static_values),
+            init_state: RefCell::new(InitState::Uninitialized),
+            nest_host: RefCell::new(None),
+        }
+    }
+
+    pub fn is_interface(&self) -> bool {
+        self.access_flags & ACC_INTERFACE != 0
+    }
+
+    /// true if the class has any method other than abstract or static ones, `<clinit>` aside.
+    /// for an interface, these are the default methods.
+    pub fn declares_default_methods(&self) -> bool {
+        self.data
+            .borrow()
+            .methods
+            .values()
+            .any(|method| method.access_flags & (ACC_ABSTRACT | ACC_STATIC) == 0)
+    }
+
+    pub fn is_public(&self) -> bool {
+        self.access_flags & ACC_PUBLIC != 0
+    }
+
+    /// `com/acme` of `com/acme/Main`, and empty for the unnamed package
+    pub fn package_name(&self) -> &str {
+        package_name_of(&self.descriptor)
+    }
+
+    /// classes are in the same run-time package if they have the same package name
+    /// and the same defining loader, JVMS 5.3.
+    pub fn is_same_runtime_package(&self, other: &Class) -> bool {
+        self.defining_loader == other.defining_loader && self.package_name() == other.package_name()
+    }
+
+    pub fn static_value(&self, name_and_descriptor: &str) -> Option<Value> {
+        self.static_values
+            .borrow()
+            .get(name_and_descriptor)
+            .copied()
+    }
+
+    /// true if `class` is a direct or indirect superclass of this class.
+    /// a class is not a subclass of itself.
+    pub fn is_subclass_of(&self, class: &Class) -> bool {
+        let mut current = self.super_class.as_ref();
+        while let Some(super_class) = current {
+            if std::ptr::eq(super_class.as_ref(), class) {
+                return true;
+            }
+            current = super_class.super_class.as_ref();
+        }
+        false
+    }
+
+    /// true if this class, or any of its superclasses, implements `interface` directly or
+    /// through superinterfaces. for an interface, this means it extends `interface`.
+    pub fn implements(&self, interface: &Class) -> bool {
+        let directly_or_by_superinterfaces = self.interfaces.iter().any(|implemented| {
+            std::ptr::eq(implemented.as_ref(), interface) || implemented.implements(interface)
+        });
+        directly_or_by_superinterfaces
+            || self
+                .super_class
+                .as_ref()
+                .is_some_and(|super_class| super_class.implements(interface))
+    }
+
+    #[cfg(test)]
     pub fn constant_pool_value_at(&self, index: u16) -> String {
+        let data = self.data.borrow();
+        let constant_pool = &data.constant_pool;
         assert!(
-            self.constant_pool.len() > index as usize,
+            constant_pool.len() > index as usize,
             "constant_pool out of bounds: cp size {}, given index {}",
-            self.constant_pool.len(),
+            constant_pool.len(),
             index
         );
-        self.constant_pool[index as usize].clone()
+        constant_pool[index as usize].clone()
+    }
+
+    /// the parsed data of the current version of the class
+    pub fn data(&self) -> Arc<ClassData> {
+        Arc::clone(&self.data.borrow())
     }
+
+    /// the run-time constant pool of the current version of the class
+    pub fn runtime_constant_pool(&self) -> Rc<RuntimeConstantPool> {
+        Rc::clone(&self.runtime_constant_pool.borrow())
+    }
+
+    /// the current version of the method, by `name:descriptor`
+    pub fn method(&self, name_and_descriptor: &str) -> Option<Arc<MethodInfo>> {
+        self.data
+            .borrow()
+            .methods
+            .get(name_and_descriptor)
+            .map(Arc::clone)
+    }
+
+    /// the field declared by the class, by `name:descriptor`
+    pub fn field(&self, name_and_descriptor: &str) -> Option<Arc<FieldInfo>> {
+        self.data
+            .borrow()
+            .fields
+            .get(name_and_descriptor)
+            .map(Arc::clone)
+    }
+}
+
+#[test]
+fn test_parse_method_ref() {
+    let result = MethodRef::parse_from(String::from("com/acme/Calculator.increment:(I)I"));
+
+    assert_eq!(result.class, "com/acme/Calculator");
+    assert_eq!(result.name, "increment");
+    assert_eq!(result.descriptor, "(I)I");
+    assert_eq!(result.name_and_descriptor, "increment:(I)I");
 }
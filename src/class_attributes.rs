@@ -1,16 +1,21 @@
 use std::io::Cursor;
-use std::rc::Rc;
+use std::sync::Arc;
 
-use crate::binary::{read_to, read_u16, read_u32};
-use crate::class_attributes::PredefinedAttributes::Code;
-use crate::cp_info::{constant_pool_value_at, CpInfo};
+use crate::binary::{try_read_to, try_read_u16, try_read_u32};
+use crate::class_attributes::PredefinedAttributes::{
+    Code, ConstantValue, Module, ModuleMainClass, ModulePackages, NestHost, NestMembers,
+};
+use crate::cp_info::CP_TAGES::{
+    CONSTANT_Class, CONSTANT_Double, CONSTANT_Float, CONSTANT_Integer, CONSTANT_Long,
+    CONSTANT_Module, CONSTANT_Package, CONSTANT_String, CONSTANT_Utf8,
+};
+use crate::cp_info::{check_cp_index, constant_pool_value_at, CpInfo, CP_TAGES};
 
 #[derive(Debug)]
 pub struct FieldInfo {
     pub access_flags: u16,
     pub name_index: u16,
     pub descriptor_index: u16,
-    pub attributes_count: u16,
     pub attributes: Vec<AttributeInfo>,
 }
 
@@ -19,10 +24,19 @@ pub struct MethodInfo {
     pub access_flags: u16,
     pub name_index: u16,
     pub descriptor_index: u16,
-    pub attributes_count: u16,
     pub attributes: Vec<AttributeInfo>,
 }
 
+impl FieldInfo {
+    /// the index of the constant of the `ConstantValue` attribute, if any
+    pub fn constant_value_index(&self) -> Option<u16> {
+        self.attributes.iter().find_map(|attr| match attr {
+            AttributeInfo::ConstantValueAttributeInfo(info) => Some(info.constantvalue_index),
+            _ => None,
+        })
+    }
+}
+
 impl MethodInfo {
     pub fn get_code_attribute(&self) -> &CodeAttributeInfo {
         self.attributes
@@ -31,27 +45,41 @@ impl MethodInfo {
                 AttributeInfo::CodeAttributeInfo(code_attr) => Some(code_attr),
                 _ => None,
             })
-            .expect(
-                format!(
+            .unwrap_or_else(|| {
+                panic!(
                     "MethodInfo doesn't contain code_attribute_info. {:#?}",
                     self
                 )
-                .as_str(),
-            )
+            })
     }
 }
 
 #[derive(Debug)]
+#[allow(clippy::enum_variant_names)]
 pub enum AttributeInfo {
+    ConstantValueAttributeInfo(ConstantValueAttributeInfo),
     CodeAttributeInfo(CodeAttributeInfo),
+    NestHostAttributeInfo(NestHostAttributeInfo),
+    NestMembersAttributeInfo(NestMembersAttributeInfo),
+    ModuleAttributeInfo(ModuleAttributeInfo),
+    ModulePackagesAttributeInfo(ModulePackagesAttributeInfo),
+    ModuleMainClassAttributeInfo(ModuleMainClassAttributeInfo),
     GeneralAttributeInfo(GeneralAttributeInfo),
 }
 
 #[derive(Debug)]
 pub struct GeneralAttributeInfo {
-    attribute_name_index: u16,
-    attribute_length: u32,
-    info: Vec<u8>,
+    pub attribute_name_index: u16,
+    pub attribute_length: u32,
+    pub info: Vec<u8>,
+}
+
+/// https://docs.oracle.com/javase/specs/jvms/se17/html/jvms-4.html#jvms-4.7.2
+#[derive(Debug)]
+pub struct ConstantValueAttributeInfo {
+    pub attribute_name_index: u16,
+    pub attribute_length: u32,
+    pub constantvalue_index: u16,
 }
 
 #[derive(Debug)]
@@ -60,20 +88,76 @@ pub struct CodeAttributeInfo {
     pub attribute_length: u32,
     pub max_stack: u16,
     pub max_locals: u16,
-    pub code_length: u32,
     pub code: Vec<u8>,
-    pub exception_table_length: u16,
     pub exception_table: Vec<ExceptionTable>,
-    pub attributes_count: u16,
     pub attributes: Vec<AttributeInfo>,
 }
 
+/// https://docs.oracle.com/javase/specs/jvms/se17/html/jvms-4.html#jvms-4.7.28
+#[derive(Debug)]
+pub struct NestHostAttributeInfo {
+    pub attribute_name_index: u16,
+    pub attribute_length: u32,
+    pub host_class_index: u16,
+}
+
+/// https://docs.oracle.com/javase/specs/jvms/se17/html/jvms-4.html#jvms-4.7.29
+#[derive(Debug)]
+pub struct NestMembersAttributeInfo {
+    pub attribute_name_index: u16,
+    pub attribute_length: u32,
+    pub classes: Vec<u16>,
+}
+
+/// https://docs.oracle.com/javase/specs/jvms/se17/html/jvms-4.html#jvms-4.7.25
+/// only the items the module system reads, the others are checked while parsing
+#[derive(Debug)]
+pub struct ModuleAttributeInfo {
+    pub module_name_index: u16,
+    pub module_flags: u16,
+    pub requires: Vec<ModuleRequires>,
+    pub exports: Vec<ModuleExports>,
+    pub opens: Vec<ModuleExports>,
+    pub provides: Vec<ModuleProvides>,
+}
+
+#[derive(Debug)]
+pub struct ModuleRequires {
+    pub requires_index: u16,
+    pub requires_flags: u16,
+}
+
+/// an entry of either `exports` or `opens`, which have the same structure
+#[derive(Debug)]
+pub struct ModuleExports {
+    pub index: u16,
+    pub to_index: Vec<u16>,
+}
+
+#[derive(Debug)]
+pub struct ModuleProvides {
+    pub provides_index: u16,
+    pub provides_with_index: Vec<u16>,
+}
+
+/// https://docs.oracle.com/javase/specs/jvms/se17/html/jvms-4.html#jvms-4.7.26
+#[derive(Debug)]
+pub struct ModulePackagesAttributeInfo {
+    pub package_index: Vec<u16>,
+}
+
+/// https://docs.oracle.com/javase/specs/jvms/se17/html/jvms-4.html#jvms-4.7.27
+#[derive(Debug)]
+pub struct ModuleMainClassAttributeInfo {
+    pub main_class_index: u16,
+}
+
 #[derive(Debug)]
 pub struct ExceptionTable {
-    start_pc: u16,
-    end_pc: u16,
-    handler_pc: u16,
-    catch_type: u16,
+    pub start_pc: u16,
+    pub end_pc: u16,
+    pub handler_pc: u16,
+    pub catch_type: u16,
 }
 
 pub enum PredefinedAttributes {
@@ -110,8 +194,9 @@ pub enum PredefinedAttributes {
 }
 
 impl PredefinedAttributes {
-    fn from(value: &str) -> PredefinedAttributes {
-        match value {
+    /// `None` for the attributes not defined by JVMS, which are ignored, JVMS 4.7.1
+    fn from(value: &str) -> Option<PredefinedAttributes> {
+        let attribute = match value {
             "ConstantValue" => PredefinedAttributes::ConstantValue,
             "Code" => PredefinedAttributes::Code,
             "StackMapTable" => PredefinedAttributes::StackMapTable,
@@ -148,100 +233,283 @@ impl PredefinedAttributes {
             "NestMembers" => PredefinedAttributes::NestMembers,
             "Record" => PredefinedAttributes::Record,
             "PermittedSubclasses" => PredefinedAttributes::PermittedSubclasses,
-            _ => panic!("Unsupported Attribute: {}", value),
-        }
+            _ => return None,
+        };
+        Some(attribute)
     }
 }
 
-pub fn parse_interfaces(cursur: &mut Cursor<&[u8]>, interface_count: u16) -> Vec<u16> {
+pub fn parse_interfaces(
+    cursur: &mut Cursor<&[u8]>,
+    interface_count: u16,
+    cp: &[CpInfo],
+) -> Result<Vec<u16>, String> {
     let mut interfaces: Vec<u16> = vec![];
     for _ in 0..interface_count {
-        interfaces.push(read_u16(cursur));
+        let interface = try_read_u16(cursur)?;
+        check_cp_index(cp, interface, &[CONSTANT_Class])?;
+        interfaces.push(interface);
     }
-    interfaces
+    Ok(interfaces)
 }
 
 pub fn parse_fields(
     cursor: &mut Cursor<&[u8]>,
     fields_count: u16,
     cp: &Vec<CpInfo>,
-) -> Vec<Rc<FieldInfo>> {
-    let mut fields: Vec<Rc<FieldInfo>> = vec![];
+) -> Result<Vec<Arc<FieldInfo>>, String> {
+    let mut fields: Vec<Arc<FieldInfo>> = vec![];
     for _ in 0..fields_count {
-        fields.push(Rc::new(parse_field(cursor, cp)))
+        fields.push(Arc::new(parse_field(cursor, cp)?))
     }
-    fields
+    Ok(fields)
 }
 
-fn parse_field(cursor: &mut Cursor<&[u8]>, cp: &Vec<CpInfo>) -> FieldInfo {
-    let access_flags: u16 = read_u16(cursor);
-    let name_index: u16 = read_u16(cursor);
-    let descriptor_index: u16 = read_u16(cursor);
-    let attributes_count: u16 = read_u16(cursor);
-    let attributes = parse_attributes(cursor, attributes_count, cp);
+fn parse_field(cursor: &mut Cursor<&[u8]>, cp: &Vec<CpInfo>) -> Result<FieldInfo, String> {
+    let access_flags: u16 = try_read_u16(cursor)?;
+    let name_index: u16 = try_read_u16(cursor)?;
+    let descriptor_index: u16 = try_read_u16(cursor)?;
+    check_cp_index(cp, name_index, &[CONSTANT_Utf8])?;
+    check_cp_index(cp, descriptor_index, &[CONSTANT_Utf8])?;
+    let attributes_count: u16 = try_read_u16(cursor)?;
+    let attributes = parse_attributes(cursor, attributes_count, cp)?;
 
-    FieldInfo {
+    Ok(FieldInfo {
         access_flags,
         name_index,
         descriptor_index,
-        attributes_count,
         attributes,
-    }
+    })
 }
 
 pub fn parse_methods(
     cursor: &mut Cursor<&[u8]>,
     methods_count: u16,
     cp: &Vec<CpInfo>,
-) -> Vec<Rc<MethodInfo>> {
-    let mut methods: Vec<Rc<MethodInfo>> = vec![];
+) -> Result<Vec<Arc<MethodInfo>>, String> {
+    let mut methods: Vec<Arc<MethodInfo>> = vec![];
     for _ in 0..methods_count {
-        methods.push(Rc::new(parse_method(cursor, cp)))
+        methods.push(Arc::new(parse_method(cursor, cp)?))
     }
-    methods
+    Ok(methods)
 }
 
-fn parse_method(cursor: &mut Cursor<&[u8]>, cp: &Vec<CpInfo>) -> MethodInfo {
-    let access_flags: u16 = read_u16(cursor);
-    let name_index: u16 = read_u16(cursor);
-    let descriptor_index: u16 = read_u16(cursor);
-    let attributes_count: u16 = read_u16(cursor);
-    let attributes = parse_attributes(cursor, attributes_count, cp);
+fn parse_method(cursor: &mut Cursor<&[u8]>, cp: &Vec<CpInfo>) -> Result<MethodInfo, String> {
+    let access_flags: u16 = try_read_u16(cursor)?;
+    let name_index: u16 = try_read_u16(cursor)?;
+    let descriptor_index: u16 = try_read_u16(cursor)?;
+    check_cp_index(cp, name_index, &[CONSTANT_Utf8])?;
+    check_cp_index(cp, descriptor_index, &[CONSTANT_Utf8])?;
+    let attributes_count: u16 = try_read_u16(cursor)?;
+    let attributes = parse_attributes(cursor, attributes_count, cp)?;
 
-    MethodInfo {
+    Ok(MethodInfo {
         access_flags,
         name_index,
         descriptor_index,
-        attributes_count,
         attributes,
-    }
+    })
 }
 
 pub fn parse_attributes(
     cursor: &mut Cursor<&[u8]>,
     attributes_count: u16,
     cp: &Vec<CpInfo>,
-) -> Vec<AttributeInfo> {
+) -> Result<Vec<AttributeInfo>, String> {
     let mut attributes: Vec<AttributeInfo> = vec![];
     for _ in 0..attributes_count {
-        attributes.push(parse_attribute_info(cursor, cp))
+        attributes.push(parse_attribute_info(cursor, cp)?)
     }
-    attributes
+    Ok(attributes)
 }
 
-fn parse_attribute_info(cursor: &mut Cursor<&[u8]>, cp: &Vec<CpInfo>) -> AttributeInfo {
-    let attribute_name_index = read_u16(cursor);
-    let attribute_length = read_u32(cursor);
+/// the content of an attribute is parsed within its `attribute_length`,
+/// so a malformed attribute can't be read past its end.
+fn parse_attribute_info(
+    cursor: &mut Cursor<&[u8]>,
+    cp: &Vec<CpInfo>,
+) -> Result<AttributeInfo, String> {
+    let attribute_name_index = try_read_u16(cursor)?;
+    let attribute_length = try_read_u32(cursor)?;
+    check_cp_index(cp, attribute_name_index, &[CONSTANT_Utf8])?;
+    let info = try_read_to(cursor, attribute_length as usize)?;
 
     let attribute_name = constant_pool_value_at(cp, attribute_name_index);
-    match PredefinedAttributes::from(attribute_name.as_str()) {
-        Code => parse_code_attribute_info(cursor, attribute_name_index, attribute_length, cp),
-        _ => AttributeInfo::GeneralAttributeInfo(GeneralAttributeInfo {
-            attribute_name_index,
-            attribute_length,
-            info: read_to(cursor, attribute_length as usize),
-        }),
+    let content = &mut Cursor::new(info.as_slice());
+    let attribute = match PredefinedAttributes::from(attribute_name.as_str()) {
+        Some(ConstantValue) => {
+            let constantvalue_index = try_read_u16(content)?;
+            check_cp_index(
+                cp,
+                constantvalue_index,
+                &[
+                    CONSTANT_Integer,
+                    CONSTANT_Float,
+                    CONSTANT_Long,
+                    CONSTANT_Double,
+                    CONSTANT_String,
+                ],
+            )?;
+            AttributeInfo::ConstantValueAttributeInfo(ConstantValueAttributeInfo {
+                attribute_name_index,
+                attribute_length,
+                constantvalue_index,
+            })
+        }
+        Some(Code) => {
+            parse_code_attribute_info(content, attribute_name_index, attribute_length, cp)?
+        }
+        Some(NestHost) => {
+            let host_class_index = try_read_u16(content)?;
+            check_cp_index(cp, host_class_index, &[CONSTANT_Class])?;
+            AttributeInfo::NestHostAttributeInfo(NestHostAttributeInfo {
+                attribute_name_index,
+                attribute_length,
+                host_class_index,
+            })
+        }
+        Some(NestMembers) => {
+            let number_of_classes = try_read_u16(content)?;
+            AttributeInfo::NestMembersAttributeInfo(NestMembersAttributeInfo {
+                attribute_name_index,
+                attribute_length,
+                classes: read_index_table(content, number_of_classes, cp, &[CONSTANT_Class])?,
+            })
+        }
+        Some(Module) => parse_module_attribute_info(content, cp)?,
+        Some(ModulePackages) => {
+            let package_count = try_read_u16(content)?;
+            AttributeInfo::ModulePackagesAttributeInfo(ModulePackagesAttributeInfo {
+                package_index: read_index_table(content, package_count, cp, &[CONSTANT_Package])?,
+            })
+        }
+        Some(ModuleMainClass) => {
+            let main_class_index = try_read_u16(content)?;
+            check_cp_index(cp, main_class_index, &[CONSTANT_Class])?;
+            AttributeInfo::ModuleMainClassAttributeInfo(ModuleMainClassAttributeInfo {
+                main_class_index,
+            })
+        }
+        _ => {
+            return Ok(AttributeInfo::GeneralAttributeInfo(GeneralAttributeInfo {
+                attribute_name_index,
+                attribute_length,
+                info,
+            }))
+        }
+    };
+    if content.position() != attribute_length as u64 {
+        return Err(format!(
+            "{} attribute has wrong length {}",
+            attribute_name, attribute_length
+        ));
     }
+    Ok(attribute)
+}
+
+fn parse_module_attribute_info(
+    cursor: &mut Cursor<&[u8]>,
+    cp: &[CpInfo],
+) -> Result<AttributeInfo, String> {
+    let module_name_index = try_read_u16(cursor)?;
+    check_cp_index(cp, module_name_index, &[CONSTANT_Module])?;
+    let module_flags = try_read_u16(cursor)?;
+    // module_version_index
+    read_optional_index(cursor, cp, &[CONSTANT_Utf8])?;
+
+    let requires_count = try_read_u16(cursor)?;
+    let mut requires = vec![];
+    for _ in 0..requires_count {
+        let requires_index = try_read_u16(cursor)?;
+        check_cp_index(cp, requires_index, &[CONSTANT_Module])?;
+        requires.push(ModuleRequires {
+            requires_index,
+            requires_flags: try_read_u16(cursor)?,
+        });
+        // requires_version_index
+        read_optional_index(cursor, cp, &[CONSTANT_Utf8])?;
+    }
+    let exports_count = try_read_u16(cursor)?;
+    let exports = parse_module_exports(cursor, exports_count, cp)?;
+    let opens_count = try_read_u16(cursor)?;
+    let opens = parse_module_exports(cursor, opens_count, cp)?;
+    let uses_count = try_read_u16(cursor)?;
+    read_index_table(cursor, uses_count, cp, &[CONSTANT_Class])?;
+    let provides_count = try_read_u16(cursor)?;
+    let mut provides = vec![];
+    for _ in 0..provides_count {
+        let provides_index = try_read_u16(cursor)?;
+        check_cp_index(cp, provides_index, &[CONSTANT_Class])?;
+        let provides_with_count = try_read_u16(cursor)?;
+        provides.push(ModuleProvides {
+            provides_index,
+            provides_with_index: read_index_table(
+                cursor,
+                provides_with_count,
+                cp,
+                &[CONSTANT_Class],
+            )?,
+        });
+    }
+
+    Ok(AttributeInfo::ModuleAttributeInfo(ModuleAttributeInfo {
+        module_name_index,
+        module_flags,
+        requires,
+        exports,
+        opens,
+        provides,
+    }))
+}
+
+/// `exports` and `opens` of the Module attribute, which are of the same structure
+fn parse_module_exports(
+    cursor: &mut Cursor<&[u8]>,
+    count: u16,
+    cp: &[CpInfo],
+) -> Result<Vec<ModuleExports>, String> {
+    let mut exports = vec![];
+    for _ in 0..count {
+        let index = try_read_u16(cursor)?;
+        check_cp_index(cp, index, &[CONSTANT_Package])?;
+        // the flags of an export or open
+        try_read_u16(cursor)?;
+        let to_count = try_read_u16(cursor)?;
+        exports.push(ModuleExports {
+            index,
+            to_index: read_index_table(cursor, to_count, cp, &[CONSTANT_Module])?,
+        });
+    }
+    Ok(exports)
+}
+
+/// a table of constant pool indices, each of which is of one of the `expected` kinds
+fn read_index_table(
+    cursor: &mut Cursor<&[u8]>,
+    count: u16,
+    cp: &[CpInfo],
+    expected: &[CP_TAGES],
+) -> Result<Vec<u16>, String> {
+    let mut table = vec![];
+    for _ in 0..count {
+        let index = try_read_u16(cursor)?;
+        check_cp_index(cp, index, expected)?;
+        table.push(index);
+    }
+    Ok(table)
+}
+
+/// a constant pool index, or 0 for none
+fn read_optional_index(
+    cursor: &mut Cursor<&[u8]>,
+    cp: &[CpInfo],
+    expected: &[CP_TAGES],
+) -> Result<u16, String> {
+    let index = try_read_u16(cursor)?;
+    if index != 0 {
+        check_cp_index(cp, index, expected)?;
+    }
+    Ok(index)
 }
 
 fn parse_code_attribute_info(
@@ -249,36 +517,33 @@ fn parse_code_attribute_info(
     attribute_name_index: u16,
     attribute_length: u32,
     cp: &Vec<CpInfo>,
-) -> AttributeInfo {
-    let max_stack = read_u16(cursor);
-    let max_locals = read_u16(cursor);
-    let code_length = read_u32(cursor);
-    let code = read_to(cursor, code_length as usize);
-    let exception_table_length = read_u16(cursor);
+) -> Result<AttributeInfo, String> {
+    let max_stack = try_read_u16(cursor)?;
+    let max_locals = try_read_u16(cursor)?;
+    let code_length = try_read_u32(cursor)?;
+    let code = try_read_to(cursor, code_length as usize)?;
+    let exception_table_length = try_read_u16(cursor)?;
     let mut exception_table = vec![];
     for _ in 0..exception_table_length {
         exception_table.push(ExceptionTable {
-            start_pc: read_u16(cursor),
-            end_pc: read_u16(cursor),
-            handler_pc: read_u16(cursor),
-            catch_type: read_u16(cursor),
+            start_pc: try_read_u16(cursor)?,
+            end_pc: try_read_u16(cursor)?,
+            handler_pc: try_read_u16(cursor)?,
+            catch_type: try_read_u16(cursor)?,
         });
     }
-    let attributes_count = read_u16(cursor);
-    let attributes = parse_attributes(cursor, attributes_count, cp);
+    let attributes_count = try_read_u16(cursor)?;
+    let attributes = parse_attributes(cursor, attributes_count, cp)?;
 
-    AttributeInfo::CodeAttributeInfo(CodeAttributeInfo {
+    Ok(AttributeInfo::CodeAttributeInfo(CodeAttributeInfo {
         attribute_name_index,
         attribute_length,
         max_stack,
         max_locals,
-        code_length,
         code,
-        exception_table_length,
         exception_table,
-        attributes_count,
         attributes,
-    })
+    }))
 }
 
 #[test]
@@ -291,13 +556,12 @@ fn test_parse_method() {
     ];
     let mut cursor = Cursor::new(bytes);
 
-    let result = parse_method(&mut cursor, &cp_test::dummy_cp());
+    let result = parse_method(&mut cursor, &cp_test::dummy_cp()).unwrap();
 
     assert_eq!(result.access_flags, 0x09);
     assert_eq!(result.name_index, 0x1b);
     assert_eq!(result.descriptor_index, 0x1c);
-    assert_eq!(result.attributes_count, 0x01);
-    assert_eq!(result.attributes.len(), result.attributes_count as usize);
+    assert_eq!(result.attributes.len(), 0x01);
 }
 
 #[test]
@@ -310,19 +574,15 @@ fn test_parse_code_attribute_info() {
     ];
     let mut cursor = Cursor::new(bytes);
 
-    let result = parse_attribute_info(&mut cursor, &cp_test::dummy_cp());
+    let result = parse_attribute_info(&mut cursor, &cp_test::dummy_cp()).unwrap();
     match result {
         AttributeInfo::CodeAttributeInfo(code_attribute) => {
             assert_eq!(code_attribute.attribute_name_index, 0x0019);
             assert_eq!(code_attribute.attribute_length, 0x00000026);
             assert_eq!(code_attribute.max_stack, 2);
             assert_eq!(code_attribute.max_locals, 1);
-            assert_eq!(code_attribute.code_length, 10);
-            assert_eq!(
-                code_attribute.code.len(),
-                code_attribute.code_length as usize
-            );
-            assert_eq!(code_attribute.attributes_count, 0x1);
+            assert_eq!(code_attribute.code.len(), 10);
+            assert_eq!(code_attribute.attributes.len(), 0x1);
         }
         _ => panic!("parse failed!"),
     }
@@ -350,17 +610,14 @@ mod cp_test {
             },
             CpInfo::ConstantUtf8 {
                 tag: CP_TAGES::CONSTANT_Utf8,
-                length: 16,
                 bytes: "java/lang/Object".to_owned(),
             },
             CpInfo::ConstantUtf8 {
                 tag: CP_TAGES::CONSTANT_Utf8,
-                length: 6,
                 bytes: "<init>".to_owned(),
             },
             CpInfo::ConstantUtf8 {
                 tag: CP_TAGES::CONSTANT_Utf8,
-                length: 3,
                 bytes: "()V".to_owned(),
             },
             CpInfo::ConstantFieldref {
@@ -379,17 +636,14 @@ mod cp_test {
             },
             CpInfo::ConstantUtf8 {
                 tag: CP_TAGES::CONSTANT_Utf8,
-                length: 16,
                 bytes: "java/lang/System".to_owned(),
             },
             CpInfo::ConstantUtf8 {
                 tag: CP_TAGES::CONSTANT_Utf8,
-                length: 3,
                 bytes: "out".to_owned(),
             },
             CpInfo::ConstantUtf8 {
                 tag: CP_TAGES::CONSTANT_Utf8,
-                length: 21,
                 bytes: "Ljava/io/PrintStream;".to_owned(),
             },
             CpInfo::ConstantMethodRef {
@@ -408,17 +662,14 @@ mod cp_test {
             },
             CpInfo::ConstantUtf8 {
                 tag: CP_TAGES::CONSTANT_Utf8,
-                length: 9,
                 bytes: "SimpleSum".to_owned(),
             },
             CpInfo::ConstantUtf8 {
                 tag: CP_TAGES::CONSTANT_Utf8,
-                length: 3,
                 bytes: "sum".to_owned(),
             },
             CpInfo::ConstantUtf8 {
                 tag: CP_TAGES::CONSTANT_Utf8,
-                length: 3,
                 bytes: "()I".to_owned(),
             },
             CpInfo::ConstantMethodRef {
@@ -437,47 +688,38 @@ mod cp_test {
             },
             CpInfo::ConstantUtf8 {
                 tag: CP_TAGES::CONSTANT_Utf8,
-                length: 19,
                 bytes: "java/io/PrintStream".to_owned(),
             },
             CpInfo::ConstantUtf8 {
                 tag: CP_TAGES::CONSTANT_Utf8,
-                length: 7,
                 bytes: "println".to_owned(),
             },
             CpInfo::ConstantUtf8 {
                 tag: CP_TAGES::CONSTANT_Utf8,
-                length: 4,
                 bytes: "(I)V".to_owned(),
             },
             CpInfo::ConstantUtf8 {
                 tag: CP_TAGES::CONSTANT_Utf8,
-                length: 4,
                 bytes: "Code".to_owned(),
             },
             CpInfo::ConstantUtf8 {
                 tag: CP_TAGES::CONSTANT_Utf8,
-                length: 15,
                 bytes: "LineNumberTable".to_owned(),
             },
             CpInfo::ConstantUtf8 {
                 tag: CP_TAGES::CONSTANT_Utf8,
-                length: 4,
                 bytes: "main".to_owned(),
             },
             CpInfo::ConstantUtf8 {
                 tag: CP_TAGES::CONSTANT_Utf8,
-                length: 22,
                 bytes: "([Ljava/lang/String;)V".to_owned(),
             },
             CpInfo::ConstantUtf8 {
                 tag: CP_TAGES::CONSTANT_Utf8,
-                length: 10,
                 bytes: "SourceFile".to_owned(),
             },
             CpInfo::ConstantUtf8 {
                 tag: CP_TAGES::CONSTANT_Utf8,
-                length: 14,
                 bytes: "SimpleSum.java".to_owned(),
             },
         ]
@@ -0,0 +1,384 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+
+#[cfg(test)]
+use crate::binary::read_binary_file;
+use crate::class_attributes::{AttributeInfo, FieldInfo, MethodInfo};
+use crate::class_file::ClassFile;
+use crate::class_path::ClassPath;
+use crate::cp_info::constant_pool_value_at;
+use crate::runtime_constant_pool::{constants_of, Constant};
+use crate::throwable::{Throwable, CLASS_FORMAT_ERROR, UNSUPPORTED_CLASS_VERSION_ERROR};
+
+/// The parsed part of a class, which never changes once parsed.
+/// The statics, the initialization state and the resolved constants are of each JVM,
+/// in `Class`, while this is shared by the JVMs of the process loading the same class file.
+#[derive(Debug)]
+pub struct ClassData {
+    pub descriptor: String,
+    pub access_flags: u16,
+    /// `None` only for java/lang/Object
+    pub super_class_name: Option<String>,
+    /// direct superinterfaces, in the order of the class file
+    pub interface_names: Vec<String>,
+    /// symbolic form of each constant pool entry, as `javap` prints
+    pub constant_pool: Vec<String>,
+    /// the constants which the run-time constant pool of each JVM resolves
+    pub constants: Arc<Vec<Constant>>,
+    /// by `name:descriptor`
+    pub methods: HashMap<String, Arc<MethodInfo>>,
+    /// by `name:descriptor`
+    pub fields: HashMap<String, Arc<FieldInfo>>,
+    /// the class named by `NestHost`, `None` if the class is the host of its own nest
+    pub nest_host: Option<String>,
+    /// classes named by `NestMembers`, which claim this class as their nest host
+    pub nest_members: Vec<String>,
+}
+
+/// parsed classes by the hash of their class files, kept after the JVMs loading them are gone
+static SHARED_CLASS_DATA: OnceLock<Mutex<SharedClassData>> = OnceLock::new();
+
+/// the number of parsed classes kept, the least recently used one is dropped beyond it
+const SHARED_CLASS_DATA_CAPACITY: usize = 4096;
+
+/// the 128-bit hash of a class file, two hashes of it with different prefixes,
+/// so that two class files colliding by accident is out of the question.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct ContentHash(u64, u64);
+
+impl ContentHash {
+    fn of(binary: &[u8]) -> ContentHash {
+        let hash = |prefix: u8| {
+            let mut hasher = DefaultHasher::new();
+            prefix.hash(&mut hasher);
+            binary.hash(&mut hasher);
+            hasher.finish()
+        };
+        ContentHash(hash(0), hash(1))
+    }
+}
+
+struct SharedClassData {
+    /// the class data, and when it was last used
+    classes: HashMap<ContentHash, (Arc<ClassData>, u64)>,
+    capacity: usize,
+    clock: u64,
+}
+
+impl Default for SharedClassData {
+    fn default() -> Self {
+        SharedClassData::with_capacity(SHARED_CLASS_DATA_CAPACITY)
+    }
+}
+
+impl SharedClassData {
+    fn with_capacity(capacity: usize) -> Self {
+        SharedClassData {
+            classes: HashMap::new(),
+            capacity,
+            clock: 0,
+        }
+    }
+
+    fn get(&mut self, hash: ContentHash) -> Option<Arc<ClassData>> {
+        self.clock += 1;
+        let (data, last_used) = self.classes.get_mut(&hash)?;
+        *last_used = self.clock;
+        Some(Arc::clone(data))
+    }
+
+    fn insert(&mut self, hash: ContentHash, data: Arc<ClassData>) -> Arc<ClassData> {
+        // another thread may have parsed the same class file meanwhile
+        if let Some(shared) = self.get(hash) {
+            return shared;
+        }
+        if self.classes.len() >= self.capacity {
+            let least_recently_used = self
+                .classes
+                .iter()
+                .min_by_key(|(_, (_, last_used))| *last_used)
+                .map(|(&hash, _)| hash);
+            if let Some(least_recently_used) = least_recently_used {
+                self.classes.remove(&least_recently_used);
+            }
+        }
+        self.classes.insert(hash, (Arc::clone(&data), self.clock));
+        data
+    }
+}
+
+impl ClassData {
+    /// the class data of the class file, parsed only by the first JVM of the process loading it
+    pub fn shared(binary: &[u8]) -> Result<Arc<ClassData>, Throwable> {
+        let cache = SHARED_CLASS_DATA.get_or_init(Default::default);
+        let hash = ContentHash::of(binary);
+        if let Some(data) = cache.lock().unwrap().get(hash) {
+            return Ok(data);
+        }
+
+        // parsed without the lock, not to keep other threads waiting
+        let data = Arc::new(ClassData::parse(binary)?);
+        Ok(cache.lock().unwrap().insert(hash, data))
+    }
+
+    /// a malformed class file raises ClassFormatError, and a class file of a later release
+    /// raises UnsupportedClassVersionError, JVMS 5.3.5
+    pub fn parse(binary: &[u8]) -> Result<ClassData, Throwable> {
+        let class_file = ClassFile::parse_from(binary).map_err(|message| {
+            let class_name = if ClassFile::has_unsupported_version(binary) {
+                UNSUPPORTED_CLASS_VERSION_ERROR
+            } else {
+                CLASS_FORMAT_ERROR
+            };
+            Throwable::new(class_name, message)
+        })?;
+        Ok(ClassData::from_class_file(class_file))
+    }
+
+    pub fn from_class_file(class_file: ClassFile) -> ClassData {
+        let cp = &class_file.constant_pool;
+        let descriptor = constant_pool_value_at(cp, class_file.this_class);
+        // `super_class` is 0 only for java/lang/Object
+        let super_class_name = match class_file.super_class {
+            0 => None,
+            index => Some(constant_pool_value_at(cp, index)),
+        };
+        let interface_names = class_file
+            .interfaces
+            .iter()
+            .map(|&index| constant_pool_value_at(cp, index))
+            .collect();
+
+        let mut constant_pool = vec![];
+        // constant_pool_index start from 1. then push default to 0
+        constant_pool.push(String::from(""));
+        for i in 1..class_file.constant_pool_count {
+            constant_pool.push(constant_pool_value_at(cp, i));
+        }
+        let constants = Arc::new(constants_of(cp));
+
+        let mut nest_host = None;
+        let mut nest_members = vec![];
+        for attribute in &class_file.attributes {
+            match attribute {
+                AttributeInfo::NestHostAttributeInfo(info) => {
+                    nest_host = Some(constant_pool[info.host_class_index as usize].clone());
+                }
+                AttributeInfo::NestMembersAttributeInfo(info) => {
+                    nest_members = info
+                        .classes
+                        .iter()
+                        .map(|&index| constant_pool[index as usize].clone())
+                        .collect();
+                }
+                _ => {}
+            }
+        }
+
+        let member_id = |name_index: u16, descriptor_index: u16| {
+            format!(
+                "{}:{}",
+                constant_pool[name_index as usize], constant_pool[descriptor_index as usize]
+            )
+        };
+        let methods = class_file
+            .methods
+            .into_iter()
+            .map(|method| {
+                (
+                    member_id(method.name_index, method.descriptor_index),
+                    method,
+                )
+            })
+            .collect();
+        let fields = class_file
+            .fields
+            .into_iter()
+            .map(|field| (member_id(field.name_index, field.descriptor_index), field))
+            .collect();
+
+        ClassData {
+            descriptor,
+            access_flags: class_file.access_flags,
+            super_class_name,
+            interface_names,
+            constant_pool,
+            constants,
+            methods,
+            fields,
+            nest_host,
+            nest_members,
+        }
+    }
+}
+
+/// a class file found on the class path, and its class data parsed ahead of its loading.
+/// the bytes are kept for the transformers, which must see them when the class is loaded.
+pub struct ParsedClassFile {
+    pub binary: Vec<u8>,
+    pub data: Arc<ClassData>,
+}
+
+/// read and parse the class files of `class_names` on `threads` workers,
+/// then defining the classes neither reads nor parses them again.
+/// `None` for a class file which is not found, or whose parsing panicked, which is then
+/// left to the loading on demand to report as it would without parsing ahead.
+pub fn parse_in_parallel(
+    class_path: &ClassPath,
+    class_names: &[String],
+    threads: usize,
+) -> Vec<Option<Result<ParsedClassFile, Throwable>>> {
+    let next = AtomicUsize::new(0);
+    let mut parsed: Vec<_> = class_names.iter().map(|_| None).collect();
+    thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads.max(1))
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut parsed = vec![];
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(class_name) = class_names.get(index) else {
+                            return parsed;
+                        };
+                        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                            parse_class_file(class_path, class_name)
+                        }));
+                        match result {
+                            Ok(Some(data)) => parsed.push((index, data)),
+                            Ok(None) => {}
+                            Err(_) => println!(
+                                "[DEBUG] -- parse ahead skips {}, which panicked",
+                                class_name
+                            ),
+                        }
+                    }
+                })
+            })
+            .collect();
+        for worker in workers {
+            // the panics of the classes are caught by the worker itself
+            for (index, data) in worker.join().unwrap_or_default() {
+                parsed[index] = Some(data);
+            }
+        }
+    });
+    parsed
+}
+
+fn parse_class_file(
+    class_path: &ClassPath,
+    class_name: &str,
+) -> Option<Result<ParsedClassFile, Throwable>> {
+    match class_path.find_class(class_name) {
+        Ok(Some(binary)) => {
+            let data = ClassData::shared(&binary);
+            Some(data.map(|data| ParsedClassFile { binary, data }))
+        }
+        Ok(None) => None,
+        Err(throwable) => Some(Err(throwable)),
+    }
+}
+
+#[test]
+fn test_class_data_from_class_file() {
+    let binary = read_binary_file(&"java/SimpleSum.class".to_owned()).unwrap();
+    let class_file = ClassFile::parse_from(binary.as_slice()).unwrap();
+    let cp_count = class_file.constant_pool_count;
+
+    let result = ClassData::from_class_file(class_file);
+
+    assert_eq!(result.descriptor, "SimpleSum");
+    assert_eq!(result.super_class_name.as_deref(), Some("java/lang/Object"));
+    assert_eq!(result.constant_pool.len(), cp_count as usize);
+    assert_eq!(result.constants.len(), cp_count as usize);
+    assert!(result.methods.contains_key("main:()I"));
+}
+
+#[test]
+fn test_shared_class_data() {
+    let binary = read_binary_file(&"java/Calculator.class".to_owned()).unwrap();
+    let other = read_binary_file(&"java/SimpleSum.class".to_owned()).unwrap();
+
+    let data = ClassData::shared(&binary).unwrap();
+
+    assert!(Arc::ptr_eq(
+        &ClassData::shared(&binary.clone()).unwrap(),
+        &data
+    ));
+    assert!(!Arc::ptr_eq(&ClassData::shared(&other).unwrap(), &data));
+}
+
+#[test]
+fn test_shared_class_data_kept() {
+    use crate::jvm::JVM;
+
+    // the first JVM is gone before the second one loads the class
+    let first = JVM::create_with_class_path("java");
+    let data = first.resolve_class("Unshared").unwrap().data();
+    drop(first);
+
+    let second = JVM::create_with_class_path("java");
+    let class = second.resolve_class("Unshared").unwrap();
+    assert!(Arc::ptr_eq(&class.data(), &data));
+}
+
+#[test]
+fn test_shared_class_data_capacity() {
+    let binaries = ["Calculator", "SimpleSum", "Unshared"]
+        .map(|name| read_binary_file(&format!("java/{}.class", name)).unwrap());
+    let [first, second, third] = binaries.each_ref().map(|binary| ContentHash::of(binary));
+    let mut cache = SharedClassData::with_capacity(2);
+    for (hash, binary) in [(first, &binaries[0]), (second, &binaries[1])] {
+        cache.insert(hash, Arc::new(ClassData::parse(binary).unwrap()));
+    }
+
+    // the first one is used again, then the second one is the least recently used
+    assert!(cache.get(first).is_some());
+    cache.insert(third, Arc::new(ClassData::parse(&binaries[2]).unwrap()));
+
+    assert_eq!(cache.classes.len(), 2);
+    assert!(cache.get(first).is_some());
+    assert!(cache.get(second).is_none());
+    assert!(cache.get(third).is_some());
+    assert_ne!(ContentHash::of(&binaries[0][1..]), first);
+}
+
+#[test]
+fn test_parse_in_parallel() {
+    let jar = crate::class_path::damaged_jar("parse-in-parallel");
+    let class_path = ClassPath::parse(&format!("{}:java", jar.display()));
+    let class_names = ["com/acme/Main", "SimpleSum", "NotExists"].map(String::from);
+
+    let parsed = parse_in_parallel(&class_path, &class_names, 2);
+    std::fs::remove_file(&jar).unwrap();
+
+    // a result for each class, the damaged one does not stop the others
+    let Some(Err(throwable)) = &parsed[0] else {
+        panic!("com/acme/Main is not a ClassFormatError");
+    };
+    assert_eq!(throwable.class_name, CLASS_FORMAT_ERROR);
+    assert!(matches!(&parsed[1], Some(Ok(parsed)) if parsed.data.descriptor == "SimpleSum"));
+    assert!(parsed[2].is_none());
+}
+
+#[test]
+fn test_parse_malformed_class_data() {
+    let binary = read_binary_file(&"java/SimpleSum.class".to_owned()).unwrap();
+
+    let truncated = ClassData::shared(&binary[..5]).unwrap_err();
+    assert_eq!(
+        truncated,
+        Throwable::new(CLASS_FORMAT_ERROR, String::from("Truncated class file"))
+    );
+
+    let mut later = binary.clone();
+    later[7] = 62;
+    assert!(ClassData::parse(&later)
+        .unwrap_err()
+        .is(UNSUPPORTED_CLASS_VERSION_ERROR));
+}
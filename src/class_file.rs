@@ -1,86 +1,163 @@
 use std::io::Cursor;
-use std::rc::Rc;
+use std::sync::Arc;
 
-use crate::binary::{read_binary_file, read_u16, read_u32};
+#[cfg(test)]
+use crate::binary::read_binary_file;
+use crate::binary::{try_read_u16, try_read_u32};
 use crate::class_attributes::{
     parse_attributes, parse_fields, parse_interfaces, parse_methods, AttributeInfo, FieldInfo,
     MethodInfo,
 };
-use crate::cp_info::{parse_cp_info, CpInfo};
+use crate::cp_info::CP_TAGES::CONSTANT_Class;
+use crate::cp_info::{check_cp_index, parse_cp_info, CpInfo};
+use crate::jvm::JAVA_FEATURE_VERSION;
+
+/// Class access and property modifiers
+/// https://docs.oracle.com/javase/specs/jvms/se17/html/jvms-4.html#jvms-4.1-200-E.1
+pub const ACC_PUBLIC: u16 = 0x0001;
+pub const ACC_FINAL: u16 = 0x0010;
+pub const ACC_SUPER: u16 = 0x0020;
+pub const ACC_INTERFACE: u16 = 0x0200;
+pub const ACC_ABSTRACT: u16 = 0x0400;
+pub const ACC_MODULE: u16 = 0x8000;
+
+/// Field and method access and property modifiers, in addition to the ones above
+/// https://docs.oracle.com/javase/specs/jvms/se17/html/jvms-4.html#jvms-4.5-200-A.1
+pub const ACC_PRIVATE: u16 = 0x0002;
+pub const ACC_PROTECTED: u16 = 0x0004;
+pub const ACC_STATIC: u16 = 0x0008;
+pub const ACC_VARARGS: u16 = 0x0080;
+pub const ACC_NATIVE: u16 = 0x0100;
+
+/// Module flags, of `module_flags` and `requires_flags` in the Module attribute
+/// https://docs.oracle.com/javase/specs/jvms/se17/html/jvms-4.html#jvms-4.7.25
+pub const ACC_OPEN: u16 = 0x0020;
+pub const ACC_TRANSITIVE: u16 = 0x0020;
+pub const ACC_STATIC_PHASE: u16 = 0x0040;
+
+const MAGIC: u32 = 0xCAFEBABE;
+/// 61 for Java SE 17
+const MAX_MAJOR_VERSION: u16 = JAVA_FEATURE_VERSION + 44;
 
 #[derive(Default, Debug)]
 pub struct ClassFile {
     /// ClassFile Structure
     /// https://docs.oracle.com/javase/specs/jvms/se17/html/jvms-4.html#jvms-4.1
-    pub magic: u32,
-    pub minor_version: u16,
-    pub major_version: u16,
+    /// the magic and the version are checked while parsing
     pub constant_pool_count: u16,
     pub constant_pool: Vec<CpInfo>,
     pub access_flags: u16,
     pub this_class: u16,
     pub super_class: u16,
-    pub interfaces_count: u16,
     pub interfaces: Vec<u16>,
-    pub fields_count: u16,
-    pub fields: Vec<Rc<FieldInfo>>,
-    pub methods_count: u16,
-    pub methods: Vec<Rc<MethodInfo>>,
-    pub attributes_count: u16,
+    pub fields: Vec<Arc<FieldInfo>>,
+    pub methods: Vec<Arc<MethodInfo>>,
     pub attributes: Vec<AttributeInfo>,
 }
 
 impl ClassFile {
-    pub fn parse_from(binary: &[u8]) -> ClassFile {
+    /// parse a class file, which may be given by a user loader or a transformer as is.
+    /// the error is the message of ClassFormatError, or of UnsupportedClassVersionError
+    /// if `has_unsupported_version` is true for the bytes. JVMS 4.8, 5.3.5.
+    pub fn parse_from(binary: &[u8]) -> Result<ClassFile, String> {
         let mut cursor = Cursor::new(binary);
-        let magic: u32 = read_u32(&mut cursor);
-        let minor_version: u16 = read_u16(&mut cursor);
-        let major_version: u16 = read_u16(&mut cursor);
-        let constant_pool_count: u16 = read_u16(&mut cursor);
-        let constant_pool = parse_cp_info(&mut cursor, constant_pool_count);
-        let access_flags: u16 = read_u16(&mut cursor);
-        let this_class: u16 = read_u16(&mut cursor);
-        let super_class: u16 = read_u16(&mut cursor);
-        let interfaces_count: u16 = read_u16(&mut cursor);
-        let interfaces = parse_interfaces(&mut cursor, interfaces_count);
-        let fields_count: u16 = read_u16(&mut cursor);
-        let fields = parse_fields(&mut cursor, fields_count, &constant_pool);
-        let methods_count: u16 = read_u16(&mut cursor);
-        let methods = parse_methods(&mut cursor, methods_count, &constant_pool);
-        let attributes_count: u16 = read_u16(&mut cursor);
-        let attributes = parse_attributes(&mut cursor, attributes_count, &constant_pool);
-
-        ClassFile {
-            magic,
-            minor_version,
-            major_version,
+        let magic: u32 = try_read_u32(&mut cursor).map_err(truncated)?;
+        if magic != MAGIC {
+            return Err(format!("Incompatible magic value {} in class file", magic));
+        }
+        let minor_version: u16 = try_read_u16(&mut cursor).map_err(truncated)?;
+        let major_version: u16 = try_read_u16(&mut cursor).map_err(truncated)?;
+        if !is_supported_version(major_version, minor_version) {
+            return Err(unsupported_version(major_version, minor_version));
+        }
+
+        let class_file = ClassFile::parse_after_version(&mut cursor)?;
+        if cursor.position() != binary.len() as u64 {
+            return Err(String::from("Extra bytes at the end of class file"));
+        }
+        Ok(class_file)
+    }
+
+    /// true if the bytes start with the magic and a version of a later Java SE release
+    pub fn has_unsupported_version(binary: &[u8]) -> bool {
+        let mut cursor = Cursor::new(binary);
+        let (Ok(MAGIC), Ok(minor_version), Ok(major_version)) = (
+            try_read_u32(&mut cursor),
+            try_read_u16(&mut cursor),
+            try_read_u16(&mut cursor),
+        ) else {
+            return false;
+        };
+        !is_supported_version(major_version, minor_version)
+    }
+
+    fn parse_after_version(cursor: &mut Cursor<&[u8]>) -> Result<ClassFile, String> {
+        let constant_pool_count: u16 = try_read_u16(cursor)?;
+        if constant_pool_count == 0 {
+            return Err(String::from("Illegal constant pool size 0 in class file"));
+        }
+        let constant_pool = parse_cp_info(cursor, constant_pool_count)?;
+        let access_flags: u16 = try_read_u16(cursor)?;
+        let this_class: u16 = try_read_u16(cursor)?;
+        check_cp_index(&constant_pool, this_class, &[CONSTANT_Class])?;
+        // `super_class` is 0 only for java/lang/Object, and for module-info
+        let super_class: u16 = try_read_u16(cursor)?;
+        if super_class != 0 {
+            check_cp_index(&constant_pool, super_class, &[CONSTANT_Class])?;
+        }
+        let interfaces_count: u16 = try_read_u16(cursor)?;
+        let interfaces = parse_interfaces(cursor, interfaces_count, &constant_pool)?;
+        let fields_count: u16 = try_read_u16(cursor)?;
+        let fields = parse_fields(cursor, fields_count, &constant_pool)?;
+        let methods_count: u16 = try_read_u16(cursor)?;
+        let methods = parse_methods(cursor, methods_count, &constant_pool)?;
+        let attributes_count: u16 = try_read_u16(cursor)?;
+        let attributes = parse_attributes(cursor, attributes_count, &constant_pool)?;
+
+        Ok(ClassFile {
             constant_pool_count,
             constant_pool,
             access_flags,
             this_class,
             super_class,
-            interfaces_count,
             interfaces,
-            fields_count,
             fields,
-            methods_count,
             methods,
-            attributes_count,
             attributes,
-        }
+        })
     }
 }
 
+/// 45 is of JDK 1.0.2, and each feature release from Java SE 9 adds one to the major version.
+/// the preview features of a release, of the minor version 65535, are not supported.
+/// https://docs.oracle.com/javase/specs/jvms/se17/html/jvms-4.html#jvms-4.1-200-B.2
+fn is_supported_version(major_version: u16, minor_version: u16) -> bool {
+    match major_version {
+        45..=55 => true,
+        56..=MAX_MAJOR_VERSION => minor_version == 0,
+        _ => false,
+    }
+}
+
+fn unsupported_version(major_version: u16, minor_version: u16) -> String {
+    format!(
+        "class file version {}.{} is not supported, this version of the Java Runtime only \
+         recognizes class file versions up to {}.0",
+        major_version, minor_version, MAX_MAJOR_VERSION
+    )
+}
+
+fn truncated(_message: String) -> String {
+    String::from("Truncated class file")
+}
+
 #[test]
 fn test_parse_class() {
     // let bytes: &[u8] = &[0xCA, 0xFE, 0xBE, 0xBE];
     let binary = read_binary_file(&"java/SimpleSum.class".to_owned()).unwrap();
 
-    let result = ClassFile::parse_from(binary.as_slice());
+    let result = ClassFile::parse_from(binary.as_slice()).unwrap();
 
-    assert_eq!(result.magic, 0xCAFEBABE_u32);
-    assert_eq!(result.minor_version, 0);
-    assert_eq!(result.major_version, 61);
     assert_eq!(result.constant_pool_count, 30);
     assert_eq!(
         result.constant_pool.len(),
@@ -88,17 +165,42 @@ fn test_parse_class() {
     );
     assert_eq!(result.access_flags, 0x21);
     assert_eq!(result.this_class, 14_u16);
-    assert_eq!(result.super_class, 02_u16);
+    assert_eq!(result.super_class, 2_u16);
+
+    assert_eq!(result.interfaces.len(), 0);
+    assert_eq!(result.fields.len(), 0);
+    assert_eq!(result.methods.len(), 3);
+    assert_eq!(result.attributes.len(), 1);
+}
 
-    assert_eq!(result.interfaces_count, 0);
-    assert_eq!(result.interfaces.len(), result.interfaces_count as usize);
+#[test]
+fn test_parse_malformed_class() {
+    let binary = read_binary_file(&"java/SimpleSum.class".to_owned()).unwrap();
 
-    assert_eq!(result.fields_count, 0);
-    assert_eq!(result.fields.len(), result.fields_count as usize);
+    let truncated = ClassFile::parse_from(&binary[..5]).unwrap_err();
+    assert_eq!(truncated, "Truncated class file");
+    let in_the_middle = ClassFile::parse_from(&binary[..binary.len() - 1]).unwrap_err();
+    assert!(
+        in_the_middle.starts_with("truncated at"),
+        "{}",
+        in_the_middle
+    );
 
-    assert_eq!(result.methods_count, 3);
-    assert_eq!(result.methods.len(), result.methods_count as usize);
+    let mut not_a_class = binary.clone();
+    not_a_class[0] = 0;
+    assert_eq!(
+        ClassFile::parse_from(&not_a_class).unwrap_err(),
+        "Incompatible magic value 16693950 in class file"
+    );
+    assert!(!ClassFile::has_unsupported_version(&not_a_class));
 
-    assert_eq!(result.attributes_count, 1);
-    assert_eq!(result.attributes.len(), result.attributes_count as usize);
+    // major version 62, of Java SE 18
+    let mut later = binary.clone();
+    later[7] = 62;
+    assert_eq!(
+        ClassFile::parse_from(&later).unwrap_err(),
+        "class file version 62.0 is not supported, this version of the Java Runtime only \
+         recognizes class file versions up to 61.0"
+    );
+    assert!(ClassFile::has_unsupported_version(&later));
 }
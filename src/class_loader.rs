@@ -1,78 +1,395 @@
+#[cfg(test)]
 use crate::binary::read_binary_file;
-use crate::class::Class;
+use crate::class::{package_name_of, Class};
+use crate::class_attributes::{AttributeInfo, CodeAttributeInfo, MethodInfo};
+use crate::class_data::ClassData;
+#[cfg(test)]
 use crate::class_file::ClassFile;
-use crate::cp_info::constant_pool_value_at;
+use crate::class_file::{ACC_PUBLIC, ACC_SUPER};
+#[cfg(test)]
+use crate::class_path::Resource;
+use crate::class_path::{class_file_name_of, class_read_error, ClassPath, ClassPathEntry};
+use crate::jimage::ImageFile;
+use crate::module_path::ModuleReference;
+use crate::runtime_constant_pool::Constant;
+use crate::throwable::{Throwable, ILLEGAL_ACCESS_ERROR, INCOMPATIBLE_CLASS_CHANGE_ERROR};
 use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+use std::rc::Rc;
+use std::sync::Arc;
 
-pub struct ClassLoader {}
+pub const JAVA_LANG_OBJECT: &str = "java/lang/Object";
 
-impl ClassLoader {
-    pub fn load_class(&self, class_name: &String) -> Class {
-        let filename = class_name.to_owned() + ".class";
-        let binary = match read_binary_file(&filename) {
-            Ok(class) => class,
-            Err(_e) => panic!(
-                "Error: Can not find or read class {}\n Reason {}",
-                class_name, _e
-            ),
-        };
+/// index of a class loader registered to the JVM.
+/// a class is identified by its name together with the loader which defined it, JVMS 5.3.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LoaderId(pub usize);
 
-        let class_file = ClassFile::parse_from(binary.as_slice());
-        // too messy, turn on when only needed...
-        // println!("{:#?}", class_file);
+pub const BOOTSTRAP_LOADER: LoaderId = LoaderId(0);
+pub const PLATFORM_LOADER: LoaderId = LoaderId(1);
+pub const APPLICATION_LOADER: LoaderId = LoaderId(2);
 
-        create_class_from(class_file)
+/// A class loader, as `java.lang.ClassLoader`.
+/// The JVM asks the parent first, and `find_class` is called only when the parent
+/// can't load the class, the same as the default `ClassLoader.loadClass`.
+/// A loader may look into itself first instead, see `is_parent_first`.
+/// https://docs.oracle.com/en/java/javase/17/docs/api/java.base/java/lang/ClassLoader.html
+///
+/// User-defined loaders are implemented in Rust and registered by `JVM::add_class_loader`.
+/// A subclass of `java.lang.ClassLoader` written in Java is not a loader yet: it needs
+/// objects, virtual calls and the native `defineClass1`, which the interpreter lacks.
+/// That part of the request is left to a follow-up.
+pub trait ClassLoader {
+    fn name(&self) -> &str;
+
+    /// the loader to delegate to, `None` only for the bootstrap loader
+    fn parent(&self) -> Option<LoaderId>;
+
+    /// the class file of `class_name`, found by this loader itself, or the error reading it
+    fn find_class(&self, class_name: &str) -> Result<Option<Vec<u8>>, Throwable>;
+
+    /// false to look into this loader itself before the parent, as plugin hosts often do
+    fn is_parent_first(&self, _class_name: &str) -> bool {
+        true
+    }
+
+    /// the files of `name` found by this loader itself, as `ClassLoader.findResources`
+    #[cfg(test)]
+    fn find_resources(&self, _name: &str) -> Vec<Resource> {
+        vec![]
+    }
+
+    /// the class path this loader searches, if it is a `URLClassLoader` kind of loader
+    fn class_path(&self) -> Option<&ClassPath> {
+        None
+    }
+
+    /// the named module of `package`, if this loader defines the system modules it is in
+    fn module_of_package(&self, _package: &str) -> Option<String> {
+        None
+    }
+}
+
+/// The bootstrap loader, the root of the delegation.
+/// It loads the class library from the run-time image of a JDK, if one is given.
+/// Otherwise it only has the class path appended by the user and the synthetic java/lang/Object.
+pub struct BootstrapClassLoader {
+    pub class_path: ClassPath,
+}
+
+/// the run-time image in a JDK home directory
+pub const JDK_MODULES_IMAGE: &str = "lib/modules";
+
+impl BootstrapClassLoader {
+    pub fn create() -> Self {
+        BootstrapClassLoader {
+            class_path: ClassPath::from_paths(vec![]),
+        }
+    }
+
+    /// load the class library of the JDK installed at `jdk_home`, from its `lib/modules`
+    pub fn with_jdk_home(jdk_home: &Path) -> io::Result<Self> {
+        let image = ImageFile::open(jdk_home.join(JDK_MODULES_IMAGE))?;
+        Ok(BootstrapClassLoader {
+            class_path: ClassPath {
+                entries: vec![ClassPathEntry::Image(image)],
+            },
+        })
     }
 }
 
-fn create_class_from(class_file: ClassFile) -> Class {
-    let descriptor = constant_pool_value_at(&class_file.constant_pool, class_file.this_class);
+impl ClassLoader for BootstrapClassLoader {
+    fn name(&self) -> &str {
+        "bootstrap"
+    }
+
+    fn parent(&self) -> Option<LoaderId> {
+        None
+    }
+
+    fn find_class(&self, class_name: &str) -> Result<Option<Vec<u8>>, Throwable> {
+        self.class_path.find_class(class_name)
+    }
 
-    let mut constant_pool = vec![];
-    // constant_pool_index start from 1. then push default to 0
-    constant_pool.push(String::from(""));
-    for i in 1..class_file.constant_pool_count {
-        let value = constant_pool_value_at(&class_file.constant_pool, i);
-        constant_pool.push(value);
+    #[cfg(test)]
+    fn find_resources(&self, name: &str) -> Vec<Resource> {
+        self.class_path.find_resources(name)
     }
 
-    let mut methods = HashMap::new();
-    for method in class_file.methods {
-        let method_name = constant_pool[method.name_index as usize].clone();
-        let method_descriptor = constant_pool[method.descriptor_index as usize].clone();
-        let method_id = format!("{}:{}", method_name, method_descriptor);
-        methods.insert(method_id, method);
+    /// the module of the run-time image which has the package
+    fn module_of_package(&self, package: &str) -> Option<String> {
+        self.class_path
+            .entries
+            .iter()
+            .find_map(|entry| match entry {
+                ClassPathEntry::Image(image) => image.module_of_package(package),
+                _ => None,
+            })
     }
+}
+
+/// A loader searching its own class path, as `java.net.URLClassLoader`.
+/// The platform and application loaders are of this kind, and so are user-defined loaders
+/// which isolate plugins from each other.
+pub struct ClassPathLoader {
+    pub name: String,
+    pub parent: LoaderId,
+    pub class_path: ClassPath,
+    /// modules defined to this loader. a package of a module is never searched on the class path.
+    pub modules: Vec<Rc<ModuleReference>>,
+}
+
+impl ClassPathLoader {
+    pub fn create(name: &str, parent: LoaderId, class_path: ClassPath) -> Self {
+        ClassPathLoader {
+            name: name.to_owned(),
+            parent,
+            class_path,
+            modules: vec![],
+        }
+    }
+
+    /// no platform modules are available yet, then the platform loader always delegates.
+    pub fn platform() -> Self {
+        ClassPathLoader::create("platform", BOOTSTRAP_LOADER, ClassPath::from_paths(vec![]))
+    }
+
+    pub fn application(class_path: ClassPath) -> Self {
+        ClassPathLoader::create("app", PLATFORM_LOADER, class_path)
+    }
+
+    pub fn with_modules(mut self, modules: Vec<Rc<ModuleReference>>) -> Self {
+        self.modules = modules;
+        self
+    }
+}
 
-    let mut fields = HashMap::new();
-    for field in class_file.fields {
-        let field_name = constant_pool[field.name_index as usize].clone();
-        let field_descriptor = constant_pool[field.descriptor_index as usize].clone();
-        let field_id = format!("{}:{}", field_name, field_descriptor);
-        fields.insert(field_id, field);
+impl ClassLoader for ClassPathLoader {
+    fn name(&self) -> &str {
+        &self.name
     }
 
-    let class = Class {
-        descriptor,
-        constant_pool,
-        methods,
-        fields,
+    fn parent(&self) -> Option<LoaderId> {
+        Some(self.parent)
+    }
+
+    fn find_class(&self, class_name: &str) -> Result<Option<Vec<u8>>, Throwable> {
+        let file_name = class_file_name_of(class_name);
+        let package = package_name_of(&file_name);
+        match self
+            .modules
+            .iter()
+            .find(|module| module.descriptor.contains(package))
+        {
+            Some(module) => module
+                .location
+                .read_file(&file_name)
+                .map_err(|e| class_read_error(class_name, e)),
+            None => self.class_path.find_class(class_name),
+        }
+    }
+
+    /// a resource in a package of a module is found only in the module, and only if it is
+    /// a class file or the package is opened to every module. a resource out of any package,
+    /// such as `META-INF/...`, is searched in the modules then on the class path.
+    /// https://docs.oracle.com/en/java/javase/17/docs/api/java.base/java/lang/ClassLoader.html#getResource(java.lang.String)
+    #[cfg(test)]
+    fn find_resources(&self, name: &str) -> Vec<Resource> {
+        let package = package_name_of(name);
+        if let Some(module) = self
+            .modules
+            .iter()
+            .find(|module| module.descriptor.contains(package))
+        {
+            let descriptor = &module.descriptor;
+            let is_open = descriptor.is_open
+                || descriptor
+                    .opens
+                    .iter()
+                    .any(|opens| opens.is_to(package, None));
+            if !name.ends_with(".class") && !is_open {
+                return vec![];
+            }
+            return module.location.resource_of(name).into_iter().collect();
+        }
+
+        let mut resources: Vec<Resource> = self
+            .modules
+            .iter()
+            .filter_map(|module| module.location.resource_of(name))
+            .collect();
+        resources.extend(self.class_path.find_resources(name));
+        resources
+    }
+
+    fn class_path(&self) -> Option<&ClassPath> {
+        Some(&self.class_path)
+    }
+}
+
+/// derive a class from its class file, JVMS 5.3.5, with `loader` as its defining loader.
+/// the superclass and superinterfaces are resolved by `resolve_class` before
+/// the class itself is created, so the returned class is linked to its hierarchy.
+pub fn define_class<F>(
+    loader: LoaderId,
+    binary: &[u8],
+    resolve_class: F,
+) -> Result<Class, Throwable>
+where
+    F: FnMut(&str) -> Result<Rc<Class>, Throwable>,
+{
+    define_class_of(loader, ClassData::shared(binary)?, resolve_class)
+}
+
+/// `define_class` from the class data already parsed from the class file
+pub fn define_class_of<F>(
+    loader: LoaderId,
+    data: Arc<ClassData>,
+    mut resolve_class: F,
+) -> Result<Class, Throwable>
+where
+    F: FnMut(&str) -> Result<Rc<Class>, Throwable>,
+{
+    let this_class = &data.descriptor;
+
+    let super_class = match &data.super_class_name {
+        Some(super_class_name) => {
+            let super_class = resolve_class(super_class_name)?;
+            if super_class.is_interface() {
+                return Err(Throwable::new(
+                    INCOMPATIBLE_CLASS_CHANGE_ERROR,
+                    format!(
+                        "class {} has interface {} as super class",
+                        this_class, super_class.descriptor
+                    ),
+                ));
+            }
+            if !is_accessible_from(&super_class, loader, this_class) {
+                return Err(Throwable::new(
+                    ILLEGAL_ACCESS_ERROR,
+                    format!(
+                        "class {} cannot access its superclass {}",
+                        this_class, super_class.descriptor
+                    ),
+                ));
+            }
+            Some(super_class)
+        }
+        None => None,
     };
-    // println!("[DEBUG] -- {:#?}", class);
-    class
+
+    let mut interfaces = vec![];
+    for interface_name in &data.interface_names {
+        let interface = resolve_class(interface_name)?;
+        if !interface.is_interface() {
+            return Err(Throwable::new(
+                INCOMPATIBLE_CLASS_CHANGE_ERROR,
+                format!(
+                    "class {} can not implement {}, because it is not an interface",
+                    this_class, interface.descriptor
+                ),
+            ));
+        }
+        if !is_accessible_from(&interface, loader, this_class) {
+            return Err(Throwable::new(
+                ILLEGAL_ACCESS_ERROR,
+                format!(
+                    "class {} cannot access its superinterface {}",
+                    this_class, interface.descriptor
+                ),
+            ));
+        }
+        interfaces.push(interface);
+    }
+
+    Ok(Class::create(data, super_class, interfaces, loader))
+}
+
+/// a class is accessible from the class being defined if it is public
+/// or in the same run-time package, JVMS 5.4.4.
+fn is_accessible_from(class: &Class, loader: LoaderId, class_name: &str) -> bool {
+    class.is_public()
+        || (class.defining_loader == loader && class.package_name() == package_name_of(class_name))
+}
+
+/// without the class library of a JDK, the root of the class hierarchy is defined by the VM,
+/// with an empty constructor only.
+pub fn bootstrap_object_class() -> Class {
+    let constructor = MethodInfo {
+        access_flags: ACC_PUBLIC,
+        name_index: 0,
+        descriptor_index: 0,
+        attributes: vec![AttributeInfo::CodeAttributeInfo(CodeAttributeInfo {
+            attribute_name_index: 0,
+            attribute_length: 0,
+            max_stack: 0,
+            max_locals: 1,
+            // return
+            code: vec![0xb1],
+            exception_table: vec![],
+            attributes: vec![],
+        })],
+    };
+
+    let data = ClassData {
+        descriptor: JAVA_LANG_OBJECT.to_owned(),
+        access_flags: ACC_PUBLIC | ACC_SUPER,
+        super_class_name: None,
+        interface_names: vec![],
+        constant_pool: vec![String::from("")],
+        constants: Arc::new(vec![Constant::Unusable]),
+        methods: HashMap::from([(String::from("<init>:()V"), Arc::new(constructor))]),
+        fields: HashMap::new(),
+        nest_host: None,
+        nest_members: vec![],
+    };
+    Class::create(Arc::new(data), None, vec![], BOOTSTRAP_LOADER)
 }
 
 #[test]
 pub fn test_create_class() {
     let binary = read_binary_file(&"java/SimpleSum.class".to_owned()).unwrap();
-    let class_file = ClassFile::parse_from(binary.as_slice());
+    let class_file = ClassFile::parse_from(binary.as_slice()).unwrap();
     let cp_count = class_file.constant_pool_count;
 
-    let result = create_class_from(class_file);
+    let data = Arc::new(ClassData::from_class_file(class_file));
+
+    let result = Class::create(Arc::clone(&data), None, vec![], APPLICATION_LOADER);
 
     assert_eq!(result.descriptor, "SimpleSum");
-    assert_eq!(result.constant_pool[1], "java/lang/Object.<init>:()V");
-    assert_eq!(result.constant_pool.len(), cp_count as usize);
+    assert_eq!(
+        result.constant_pool_value_at(1),
+        "java/lang/Object.<init>:()V"
+    );
+    assert_eq!(result.runtime_constant_pool().len(), cp_count as usize);
+    assert!(Arc::ptr_eq(&result.data(), &data));
+
+    assert!(result.method("main:()I").is_some());
+}
+
+#[test]
+pub fn test_define_class_resolves_super_class() {
+    let binary = read_binary_file(&"java/Shape.class".to_owned()).unwrap();
+    let mut resolved = vec![];
+
+    let result = define_class(APPLICATION_LOADER, &binary, |name| {
+        resolved.push(name.to_owned());
+        Ok(Rc::new(bootstrap_object_class()))
+    })
+    .unwrap();
+
+    assert_eq!(resolved, [JAVA_LANG_OBJECT]);
+    assert!(result.is_interface());
+    assert_eq!(result.defining_loader, APPLICATION_LOADER);
+    assert_eq!(result.super_class.unwrap().descriptor, JAVA_LANG_OBJECT);
+}
+
+#[test]
+pub fn test_class_path_loader_find_class() {
+    let class_loader = ClassPathLoader::application(ClassPath::parse("java"));
 
-    assert!(result.methods.get("main:()I").is_some());
+    assert_eq!(class_loader.parent(), Some(PLATFORM_LOADER));
+    assert!(class_loader.find_class("Shape").unwrap().is_some());
+    assert!(class_loader.find_class("NotExists").unwrap().is_none());
 }
@@ -0,0 +1,333 @@
+use std::collections::HashSet;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::binary::read_binary_file;
+use crate::jar::JarFile;
+use crate::jimage::ImageFile;
+use crate::throwable::{Throwable, CLASS_FORMAT_ERROR, NO_CLASS_DEF_FOUND_ERROR};
+
+/// The default class path, used when neither `-cp` nor `CLASSPATH` is given.
+pub const DEFAULT_CLASS_PATH: &str = ".";
+
+/// Ordered list of locations to search for class files.
+/// Entries are searched from first to last, and the first hit wins.
+/// https://docs.oracle.com/en/java/javase/17/docs/specs/man/java.html#standard-options-for-java
+#[derive(Debug)]
+pub struct ClassPath {
+    pub entries: Vec<ClassPathEntry>,
+}
+
+/// A file found by a class loader, as `ClassLoader.getResource` returns its URL and
+/// `getResourceAsStream` its content.
+#[cfg(test)]
+#[derive(Debug)]
+pub struct Resource {
+    /// `file:`, `jar:file:...!/` or `jrt:/<module>/` followed by the name
+    pub url: String,
+    pub bytes: Vec<u8>,
+}
+
+#[derive(Debug)]
+pub enum ClassPathEntry {
+    Directory(PathBuf),
+    Jar(JarFile),
+    /// the run-time image of a JDK, `lib/modules`, searched by the package of each class
+    Image(ImageFile),
+}
+
+impl ClassPath {
+    /// parse a platform dependent path list, such as `lib:build/classes` on unix.
+    pub fn parse(class_path: &str) -> ClassPath {
+        ClassPath::from_paths(env::split_paths(class_path))
+    }
+
+    pub fn from_paths<I: IntoIterator<Item = PathBuf>>(paths: I) -> ClassPath {
+        let entries = paths
+            .into_iter()
+            .filter_map(|path| {
+                // an empty entry means the current directory
+                if path.as_os_str().is_empty() {
+                    Some(ClassPathEntry::Directory(PathBuf::from(DEFAULT_CLASS_PATH)))
+                } else {
+                    ClassPathEntry::open(path)
+                }
+            })
+            .collect();
+        ClassPath { entries }
+    }
+
+    /// find the class file bytes for either a binary name (`com.acme.Main`)
+    /// or an internal name (`com/acme/Main`), or the error reading the one found.
+    pub fn find_class(&self, class_name: &str) -> Result<Option<Vec<u8>>, Throwable> {
+        self.find_resource(&class_file_name_of(class_name))
+            .map_err(|e| class_read_error(class_name, e))
+    }
+
+    /// find a file by its `/` separated name, such as `META-INF/services/java.sql.Driver`.
+    /// a file found but not readable stops the search.
+    pub fn find_resource(&self, name: &str) -> io::Result<Option<Vec<u8>>> {
+        for entry in &self.entries {
+            if let Some(bytes) = entry.read_file(name)? {
+                return Ok(Some(bytes));
+            }
+        }
+        Ok(None)
+    }
+
+    /// the files of the name in every entry, in the order of the entries
+    #[cfg(test)]
+    pub fn find_resources(&self, name: &str) -> Vec<Resource> {
+        self.entries
+            .iter()
+            .filter_map(|entry| entry.resource_of(name))
+            .collect()
+    }
+
+    /// the internal names of every class file on the class path, in the order of the entries.
+    /// a class shadowed by the same name in an earlier entry is listed once.
+    pub fn class_names(&self) -> Vec<String> {
+        let mut found = HashSet::new();
+        self.entries
+            .iter()
+            .flat_map(ClassPathEntry::class_names)
+            .filter(|class_name| found.insert(class_name.clone()))
+            .collect()
+    }
+}
+
+impl ClassPathEntry {
+    /// `.jar` and `.zip` files are opened as archives, anything else is taken as a directory.
+    /// archives which can't be opened are dropped from the class path, as the java launcher does.
+    fn open(path: PathBuf) -> Option<ClassPathEntry> {
+        if !is_archive(&path) {
+            return Some(ClassPathEntry::Directory(path));
+        }
+        match JarFile::open(&path) {
+            Ok(jar) => Some(ClassPathEntry::Jar(jar)),
+            Err(_e) => {
+                println!(
+                    "[DEBUG] -- skip class path entry {}: {}",
+                    path.display(),
+                    _e
+                );
+                None
+            }
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        match self {
+            ClassPathEntry::Directory(root) => root,
+            ClassPathEntry::Jar(jar) => &jar.archive.path,
+            ClassPathEntry::Image(image) => &image.path,
+        }
+    }
+
+    /// the file with its URL. the run-time image only gives its class files,
+    /// since the other resources in the packages of its modules are encapsulated.
+    #[cfg(test)]
+    pub fn resource_of(&self, name: &str) -> Option<Resource> {
+        if matches!(self, ClassPathEntry::Image(_)) && !name.ends_with(".class") {
+            return None;
+        }
+        let bytes = self.read_file(name).ok()??;
+        let absolute = |path: &Path| {
+            fs::canonicalize(path)
+                .unwrap_or_else(|_| path.to_path_buf())
+                .display()
+                .to_string()
+        };
+        let url = match self {
+            ClassPathEntry::Directory(root) => format!("file:{}/{}", absolute(root), name),
+            ClassPathEntry::Jar(jar) => {
+                format!("jar:file:{}!/{}", absolute(&jar.archive.path), name)
+            }
+            ClassPathEntry::Image(image) => {
+                let package = name.rsplit_once('/').map_or("", |(package, _)| package);
+                let module = image.module_of_package(package).unwrap_or_default();
+                format!("jrt:/{}/{}", module, name)
+            }
+        };
+        Some(Resource { url, bytes })
+    }
+
+    /// the class files in a directory or a jar by their path, sorted. `module-info` and
+    /// the versioned classes of a multi-release jar are not classes of their own.
+    /// the run-time image is not indexed, its classes are loaded on demand.
+    pub fn class_names(&self) -> Vec<String> {
+        let mut file_names = match self {
+            ClassPathEntry::Directory(root) => {
+                let mut file_names = vec![];
+                files_in_directory(root, "", &mut file_names);
+                file_names
+            }
+            ClassPathEntry::Jar(jar) => jar.archive.names().to_vec(),
+            ClassPathEntry::Image(_) => vec![],
+        };
+        file_names.sort();
+        file_names
+            .into_iter()
+            .filter(|name| !name.starts_with("META-INF/") && name != "module-info.class")
+            .filter_map(|name| name.strip_suffix(".class").map(str::to_owned))
+            .collect()
+    }
+
+    /// `None` if the file is not in this entry, or the error reading the file found
+    pub fn read_file(&self, filename: &str) -> io::Result<Option<Vec<u8>>> {
+        let result = match self {
+            ClassPathEntry::Directory(root) => {
+                let path = root.join(filename);
+                read_binary_file(&path.to_string_lossy().into_owned())
+            }
+            ClassPathEntry::Jar(jar) => jar.read(filename).map_err(|e| {
+                io::Error::new(e.kind(), format!("{} in {}", e, jar.archive.path.display()))
+            }),
+            ClassPathEntry::Image(image) => return Ok(image.read_resource(filename)),
+        };
+        match result {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// the `/` separated names of the files under `directory`, prefixed by `prefix`
+fn files_in_directory(directory: &Path, prefix: &str, file_names: &mut Vec<String>) {
+    let Ok(entries) = fs::read_dir(directory) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let name = format!("{}{}", prefix, entry.file_name().to_string_lossy());
+        if entry.path().is_dir() {
+            files_in_directory(&entry.path(), &format!("{}/", name), file_names);
+        } else {
+            file_names.push(name);
+        }
+    }
+}
+
+fn is_archive(path: &Path) -> bool {
+    match path.extension() {
+        Some(extension) => {
+            extension.eq_ignore_ascii_case("jar") || extension.eq_ignore_ascii_case("zip")
+        }
+        None => false,
+    }
+}
+
+/// the error of a class file found but not readable, ClassFormatError if it is malformed,
+/// such as a jar entry of a wrong crc-32, or else NoClassDefFoundError.
+pub fn class_read_error(class_name: &str, error: io::Error) -> Throwable {
+    let throwable = if error.kind() == io::ErrorKind::InvalidData {
+        CLASS_FORMAT_ERROR
+    } else {
+        NO_CLASS_DEF_FOUND_ERROR
+    };
+    Throwable::new(throwable, format!("{} ({})", class_name, error))
+}
+
+/// `com.acme.Main` and `com/acme/Main` are both resolved to `com/acme/Main.class`.
+pub fn class_file_name_of(class_name: &str) -> String {
+    class_name.replace('.', "/") + ".class"
+}
+
+#[test]
+fn test_class_file_name_of() {
+    assert_eq!(class_file_name_of("SimpleSum"), "SimpleSum.class");
+    assert_eq!(class_file_name_of("com.acme.Main"), "com/acme/Main.class");
+    assert_eq!(class_file_name_of("com/acme/Main"), "com/acme/Main.class");
+}
+
+#[test]
+fn test_find_class_in_order() {
+    let class_path = ClassPath::parse("java/com:java:.");
+    assert_eq!(class_path.entries.len(), 3);
+
+    let binary_name = class_path.find_class("com.acme.Main").unwrap();
+    let internal_name = class_path.find_class("com/acme/Main").unwrap();
+    assert_eq!(binary_name, internal_name);
+
+    assert!(class_path.find_class("SimpleSum").unwrap().is_some());
+    assert!(class_path.find_class("NotExists").unwrap().is_none());
+}
+
+#[test]
+fn test_find_class_in_jar() {
+    let class_path = ClassPath::parse("java/not-exists.jar:java/acme.jar:java/acme-stored.jar");
+    assert_eq!(class_path.entries.len(), 2);
+
+    let from_jar = class_path.find_class("com.acme.Main").unwrap();
+    let from_directory = ClassPath::parse("java")
+        .find_class("com.acme.Main")
+        .unwrap();
+    assert_eq!(from_jar, from_directory);
+
+    assert!(class_path.find_class("SimpleSum").unwrap().is_none());
+}
+
+/// a copy of java/acme-stored.jar at a temporary `name`, where com/acme/Main.class
+/// doesn't match its crc-32
+#[cfg(test)]
+pub fn damaged_jar(name: &str) -> PathBuf {
+    let mut data = read_binary_file(&"java/acme-stored.jar".to_owned()).unwrap();
+    let magic = data
+        .windows(4)
+        .position(|bytes| bytes == b"\xCA\xFE\xBA\xBE")
+        .unwrap();
+    data[magic + 10] ^= 0xFF;
+    let jar = env::temp_dir().join(format!("{}-{}.jar", name, std::process::id()));
+    fs::write(&jar, data).unwrap();
+    jar
+}
+
+#[test]
+fn test_find_class_in_damaged_jar() {
+    // a stored class file of a wrong crc-32 is a malformed class, rather than a missing one
+    let jar = damaged_jar("damaged");
+
+    let result = ClassPath::parse(&jar.display().to_string()).find_class("com/acme/Main");
+    fs::remove_file(&jar).unwrap();
+
+    let throwable = result.unwrap_err();
+    assert_eq!(throwable.class_name, CLASS_FORMAT_ERROR);
+    assert!(throwable
+        .message
+        .starts_with("com/acme/Main (invalid entry crc-32 for com/acme/Main.class"));
+}
+
+#[test]
+fn test_find_in_multi_release_jar() {
+    let class_path = ClassPath::parse("java/multi-release.jar");
+
+    let simple_sum = read_binary_file(&"java/SimpleSum.class".to_owned()).unwrap();
+    assert_eq!(
+        class_path.find_class("com.acme.Main").unwrap().unwrap(),
+        simple_sum
+    );
+    assert_eq!(
+        class_path.find_resource("version.txt").unwrap().unwrap(),
+        b"17"
+    );
+}
+
+#[test]
+fn test_class_names() {
+    let class_path =
+        ClassPath::parse("java/services/cp/codecs.jar:java/redefine:java/services/cp/broken.jar");
+
+    assert_eq!(
+        class_path.class_names(),
+        [
+            "com/acme/codec/HexCodec",
+            "Caller",
+            "Greeting",
+            "added/Greeting",
+            "v2/Greeting",
+            "com/acme/codec/NotACodec"
+        ]
+    );
+}
@@ -26,14 +26,20 @@ fn parse_cp_info(cursor: &mut Cursor<&[u8]>) -> Vec<Box<dyn CpInfo>> {
 
 use std::io::Cursor;
 
-use crate::binary::{read_binary_file, read_string_to, read_u16, read_u32, read_u8};
+#[cfg(test)]
+use crate::binary::{read_binary_file, read_u16, read_u32};
+use crate::binary::{try_read_to, try_read_u16, try_read_u32, try_read_u8};
+#[cfg(test)]
 use crate::class_file::ClassFile;
 
 #[derive(Debug)]
 pub enum CpInfo {
     /// ConstantPool Structures
     /// [Ref](https://docs.oracle.com/javase/specs/jvms/se17/html/jvms-4.html)
-    ConstantClassInfo { tag: CP_TAGES, name_index: u16 },
+    ConstantClassInfo {
+        tag: CP_TAGES,
+        name_index: u16,
+    },
 
     // Field, Method, Interface
     ConstantFieldref {
@@ -62,13 +68,109 @@ pub enum CpInfo {
     // Utf8
     ConstantUtf8 {
         tag: CP_TAGES,
-        length: u16,
         bytes: String,
     },
+
+    // String, numeric constants
+    ConstantString {
+        tag: CP_TAGES,
+        string_index: u16,
+    },
+    ConstantInteger {
+        tag: CP_TAGES,
+        bytes: u32,
+    },
+    ConstantFloat {
+        tag: CP_TAGES,
+        bytes: u32,
+    },
+    ConstantLong {
+        tag: CP_TAGES,
+        high_bytes: u32,
+        low_bytes: u32,
+    },
+    ConstantDouble {
+        tag: CP_TAGES,
+        high_bytes: u32,
+        low_bytes: u32,
+    },
+    /// the entry following a long or double, which is valid but unusable
+    Unusable,
+
+    // MethodHandle, MethodType, Dynamic
+    ConstantMethodHandle {
+        tag: CP_TAGES,
+        reference_kind: u8,
+        reference_index: u16,
+    },
+    ConstantMethodType {
+        tag: CP_TAGES,
+        descriptor_index: u16,
+    },
+    ConstantDynamic {
+        tag: CP_TAGES,
+        bootstrap_method_attr_index: u16,
+        name_and_type_index: u16,
+    },
+    ConstantInvokeDynamic {
+        tag: CP_TAGES,
+        bootstrap_method_attr_index: u16,
+        name_and_type_index: u16,
+    },
+
+    // Module, Package
+    ConstantModule {
+        tag: CP_TAGES,
+        name_index: u16,
+    },
+    ConstantPackage {
+        tag: CP_TAGES,
+        name_index: u16,
+    },
+}
+
+impl CpInfo {
+    /// `None` for the entry following a long or double
+    pub fn tag(&self) -> Option<CP_TAGES> {
+        match self {
+            CpInfo::ConstantClassInfo { tag, .. }
+            | CpInfo::ConstantFieldref { tag, .. }
+            | CpInfo::ConstantMethodRef { tag, .. }
+            | CpInfo::ConstantInterfaceMethodRef { tag, .. }
+            | CpInfo::ConstantNameAndType { tag, .. }
+            | CpInfo::ConstantUtf8 { tag, .. }
+            | CpInfo::ConstantString { tag, .. }
+            | CpInfo::ConstantInteger { tag, .. }
+            | CpInfo::ConstantFloat { tag, .. }
+            | CpInfo::ConstantLong { tag, .. }
+            | CpInfo::ConstantDouble { tag, .. }
+            | CpInfo::ConstantMethodHandle { tag, .. }
+            | CpInfo::ConstantMethodType { tag, .. }
+            | CpInfo::ConstantDynamic { tag, .. }
+            | CpInfo::ConstantInvokeDynamic { tag, .. }
+            | CpInfo::ConstantModule { tag, .. }
+            | CpInfo::ConstantPackage { tag, .. } => Some(*tag),
+            CpInfo::Unusable => None,
+        }
+    }
 }
 
+/// names of `reference_kind` of CONSTANT_MethodHandle, from 1 to 9
+/// https://docs.oracle.com/javase/specs/jvms/se17/html/jvms-5.html#jvms-5.4.3.5
+pub const REFERENCE_KINDS: [&str; 9] = [
+    "REF_getField",
+    "REF_getStatic",
+    "REF_putField",
+    "REF_putStatic",
+    "REF_invokeVirtual",
+    "REF_invokeStatic",
+    "REF_invokeSpecial",
+    "REF_newInvokeSpecial",
+    "REF_invokeInterface",
+];
+
 #[allow(non_camel_case_types)]
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum CP_TAGES {
     CONSTANT_Class = 7,
     CONSTANT_Fieldref = 9,
@@ -90,8 +192,8 @@ pub enum CP_TAGES {
 }
 
 impl CP_TAGES {
-    fn from_u8(value: u8) -> CP_TAGES {
-        match value {
+    fn from_u8(value: u8) -> Result<CP_TAGES, String> {
+        let tag = match value {
             7 => CP_TAGES::CONSTANT_Class,
             9 => CP_TAGES::CONSTANT_Fieldref,
             10 => CP_TAGES::CONSTANT_Methodref,
@@ -109,102 +211,359 @@ impl CP_TAGES {
             18 => CP_TAGES::CONSTANT_InvokeDynamic,
             19 => CP_TAGES::CONSTANT_Module,
             20 => CP_TAGES::CONSTANT_Package,
-            _ => panic!("the value {} is not cp_tag", value),
-        }
+            _ => return Err(format!("Unknown constant tag {} in class file", value)),
+        };
+        Ok(tag)
     }
 }
 
-pub fn parse_cp_info(cursor: &mut Cursor<&[u8]>, constant_pool_count: u16) -> Vec<CpInfo> {
+pub fn parse_cp_info(
+    cursor: &mut Cursor<&[u8]>,
+    constant_pool_count: u16,
+) -> Result<Vec<CpInfo>, String> {
     let mut constant_pool: Vec<CpInfo> = vec![];
 
-    for i in 1..constant_pool_count {
-        let tag = CP_TAGES::from_u8(read_u8(cursor));
+    let mut i = 1;
+    while i < constant_pool_count {
+        let tag = CP_TAGES::from_u8(try_read_u8(cursor)?)?;
         let cp_info = match tag {
             CP_TAGES::CONSTANT_Class => CpInfo::ConstantClassInfo {
                 tag,
-                name_index: read_u16(cursor),
+                name_index: try_read_u16(cursor)?,
             },
 
             // Method, Filed, Interface
             CP_TAGES::CONSTANT_Methodref => CpInfo::ConstantMethodRef {
                 tag,
-                class_index: read_u16(cursor),
-                name_and_type_index: read_u16(cursor),
+                class_index: try_read_u16(cursor)?,
+                name_and_type_index: try_read_u16(cursor)?,
             },
             CP_TAGES::CONSTANT_Fieldref => CpInfo::ConstantFieldref {
                 tag,
-                class_index: read_u16(cursor),
-                name_and_type_index: read_u16(cursor),
+                class_index: try_read_u16(cursor)?,
+                name_and_type_index: try_read_u16(cursor)?,
             },
             CP_TAGES::CONSTANT_InterfaceMethodref => CpInfo::ConstantInterfaceMethodRef {
                 tag,
-                class_index: read_u16(cursor),
-                name_and_type_index: read_u16(cursor),
+                class_index: try_read_u16(cursor)?,
+                name_and_type_index: try_read_u16(cursor)?,
             },
 
             // NameAndType
             CP_TAGES::CONSTANT_NameAndType => CpInfo::ConstantNameAndType {
                 tag,
-                name_index: read_u16(cursor),
-                descriptor_index: read_u16(cursor),
+                name_index: try_read_u16(cursor)?,
+                descriptor_index: try_read_u16(cursor)?,
             },
 
             // Utf8
             CP_TAGES::CONSTANT_Utf8 => {
-                let length = read_u16(cursor);
+                let length = try_read_u16(cursor)?;
+                let bytes = try_read_to(cursor, length as usize)?;
                 CpInfo::ConstantUtf8 {
                     tag,
-                    length,
-                    bytes: read_string_to(cursor, length as usize),
+                    bytes: decode_modified_utf8(&bytes)?,
                 }
             }
-            _ => panic!(
-                "#{} the Constant Pool {:?} is not implement yet",
-                i + 1,
-                tag
-            ),
+
+            // String, numeric constants
+            CP_TAGES::CONSTANT_String => CpInfo::ConstantString {
+                tag,
+                string_index: try_read_u16(cursor)?,
+            },
+            CP_TAGES::CONSTANT_Integer => CpInfo::ConstantInteger {
+                tag,
+                bytes: try_read_u32(cursor)?,
+            },
+            CP_TAGES::CONSTANT_Float => CpInfo::ConstantFloat {
+                tag,
+                bytes: try_read_u32(cursor)?,
+            },
+            CP_TAGES::CONSTANT_Long => CpInfo::ConstantLong {
+                tag,
+                high_bytes: try_read_u32(cursor)?,
+                low_bytes: try_read_u32(cursor)?,
+            },
+            CP_TAGES::CONSTANT_Double => CpInfo::ConstantDouble {
+                tag,
+                high_bytes: try_read_u32(cursor)?,
+                low_bytes: try_read_u32(cursor)?,
+            },
+
+            // MethodHandle, MethodType, Dynamic
+            CP_TAGES::CONSTANT_MethodHandle => CpInfo::ConstantMethodHandle {
+                tag,
+                reference_kind: try_read_u8(cursor)?,
+                reference_index: try_read_u16(cursor)?,
+            },
+            CP_TAGES::CONSTANT_MethodType => CpInfo::ConstantMethodType {
+                tag,
+                descriptor_index: try_read_u16(cursor)?,
+            },
+            CP_TAGES::CONSTANT_Dynamic => CpInfo::ConstantDynamic {
+                tag,
+                bootstrap_method_attr_index: try_read_u16(cursor)?,
+                name_and_type_index: try_read_u16(cursor)?,
+            },
+            CP_TAGES::CONSTANT_InvokeDynamic => CpInfo::ConstantInvokeDynamic {
+                tag,
+                bootstrap_method_attr_index: try_read_u16(cursor)?,
+                name_and_type_index: try_read_u16(cursor)?,
+            },
+
+            // Module, Package
+            CP_TAGES::CONSTANT_Module => CpInfo::ConstantModule {
+                tag,
+                name_index: try_read_u16(cursor)?,
+            },
+            CP_TAGES::CONSTANT_Package => CpInfo::ConstantPackage {
+                tag,
+                name_index: try_read_u16(cursor)?,
+            },
         };
         println!("[DEBUG] -- Load CP: #{} = {:?}", i, cp_info);
+        // long and double take two entries, JVMS 4.4.5
+        let takes_two_entries = matches!(
+            cp_info,
+            CpInfo::ConstantLong { .. } | CpInfo::ConstantDouble { .. }
+        );
+        if takes_two_entries && i + 1 >= constant_pool_count {
+            return Err(format!(
+                "Invalid constant pool entry {}, a long or double at the end",
+                i
+            ));
+        }
         constant_pool.push(cp_info);
+        i += 1;
+        if takes_two_entries {
+            constant_pool.push(CpInfo::Unusable);
+            i += 1;
+        }
+    }
+
+    check_constant_pool(&constant_pool)?;
+    Ok(constant_pool)
+}
+
+/// every index in the constant pool refers to an entry of the kind JVMS 4.4 requires,
+/// then `constant_pool_value_at` can follow the indices of the entries.
+fn check_constant_pool(constant_pool: &[CpInfo]) -> Result<(), String> {
+    use CP_TAGES::*;
+
+    for cp_info in constant_pool {
+        match cp_info {
+            CpInfo::ConstantClassInfo { name_index, .. }
+            | CpInfo::ConstantModule { name_index, .. }
+            | CpInfo::ConstantPackage { name_index, .. } => {
+                check_cp_index(constant_pool, *name_index, &[CONSTANT_Utf8])?;
+            }
+            CpInfo::ConstantFieldref {
+                class_index,
+                name_and_type_index,
+                ..
+            }
+            | CpInfo::ConstantMethodRef {
+                class_index,
+                name_and_type_index,
+                ..
+            }
+            | CpInfo::ConstantInterfaceMethodRef {
+                class_index,
+                name_and_type_index,
+                ..
+            } => {
+                check_cp_index(constant_pool, *class_index, &[CONSTANT_Class])?;
+                check_cp_index(constant_pool, *name_and_type_index, &[CONSTANT_NameAndType])?;
+            }
+            CpInfo::ConstantNameAndType {
+                name_index,
+                descriptor_index,
+                ..
+            } => {
+                check_cp_index(constant_pool, *name_index, &[CONSTANT_Utf8])?;
+                check_cp_index(constant_pool, *descriptor_index, &[CONSTANT_Utf8])?;
+            }
+            CpInfo::ConstantString { string_index, .. } => {
+                check_cp_index(constant_pool, *string_index, &[CONSTANT_Utf8])?;
+            }
+            CpInfo::ConstantMethodHandle {
+                reference_kind,
+                reference_index,
+                ..
+            } => {
+                if !(1..=REFERENCE_KINDS.len() as u8).contains(reference_kind) {
+                    return Err(format!("Invalid reference kind {}", reference_kind));
+                }
+                check_cp_index(
+                    constant_pool,
+                    *reference_index,
+                    &[
+                        CONSTANT_Fieldref,
+                        CONSTANT_Methodref,
+                        CONSTANT_InterfaceMethodref,
+                    ],
+                )?;
+            }
+            CpInfo::ConstantMethodType {
+                descriptor_index, ..
+            } => {
+                check_cp_index(constant_pool, *descriptor_index, &[CONSTANT_Utf8])?;
+            }
+            CpInfo::ConstantDynamic {
+                name_and_type_index,
+                ..
+            }
+            | CpInfo::ConstantInvokeDynamic {
+                name_and_type_index,
+                ..
+            } => {
+                check_cp_index(constant_pool, *name_and_type_index, &[CONSTANT_NameAndType])?;
+            }
+            CpInfo::ConstantUtf8 { .. }
+            | CpInfo::ConstantInteger { .. }
+            | CpInfo::ConstantFloat { .. }
+            | CpInfo::ConstantLong { .. }
+            | CpInfo::ConstantDouble { .. }
+            | CpInfo::Unusable => {}
+        }
     }
+    Ok(())
+}
 
-    constant_pool
+/// an index read from a class file must be of an entry of one of the `expected` kinds
+pub fn check_cp_index(
+    constant_pool: &[CpInfo],
+    index: u16,
+    expected: &[CP_TAGES],
+) -> Result<(), String> {
+    let tag = match index {
+        0 => None,
+        index => constant_pool.get(index as usize - 1).and_then(CpInfo::tag),
+    };
+    match tag {
+        Some(tag) if expected.contains(&tag) => Ok(()),
+        _ => Err(format!(
+            "Invalid constant pool index {}, {:?} is expected",
+            index, expected
+        )),
+    }
+}
+
+/// CONSTANT_Utf8 is in modified UTF-8, JVMS 4.4.7, where the null character takes 2 bytes
+/// and a supplementary character is a surrogate pair of 3 bytes each
+fn decode_modified_utf8(bytes: &[u8]) -> Result<String, String> {
+    let malformed = || String::from("Illegal UTF8 string in constant pool");
+    let continuation = |index: usize| match bytes.get(index) {
+        Some(&byte) if byte & 0xc0 == 0x80 => Ok((byte & 0x3f) as u16),
+        _ => Err(malformed()),
+    };
+
+    let mut units = vec![];
+    let mut i = 0;
+    while i < bytes.len() {
+        let byte = bytes[i] as u16;
+        let (unit, length) = match byte {
+            0x01..=0x7f => (byte, 1),
+            0xc0..=0xdf => (((byte & 0x1f) << 6) | continuation(i + 1)?, 2),
+            0xe0..=0xef => (
+                ((byte & 0x0f) << 12) | (continuation(i + 1)? << 6) | continuation(i + 2)?,
+                3,
+            ),
+            _ => return Err(malformed()),
+        };
+        units.push(unit);
+        i += length;
+    }
+    char::decode_utf16(units)
+        .map(|c| c.map_err(|_| malformed()))
+        .collect()
 }
 
 pub fn constant_pool_value_at(constant_pool: &Vec<CpInfo>, index: u16) -> String {
     let parse_cp_value = |cp: &CpInfo| match cp {
-        CpInfo::ConstantUtf8 { tag, length, bytes } => bytes.clone(),
-        CpInfo::ConstantClassInfo { tag, name_index } => {
-            constant_pool_value_at(constant_pool, name_index.clone())
+        CpInfo::ConstantUtf8 { bytes, .. } => bytes.clone(),
+        CpInfo::ConstantClassInfo { name_index, .. } => {
+            constant_pool_value_at(constant_pool, *name_index)
         }
         CpInfo::ConstantNameAndType {
-            tag,
             name_index,
             descriptor_index,
+            ..
         } => {
-            let name = constant_pool_value_at(constant_pool, name_index.clone());
-            let desc = constant_pool_value_at(constant_pool, descriptor_index.clone());
+            let name = constant_pool_value_at(constant_pool, *name_index);
+            let desc = constant_pool_value_at(constant_pool, *descriptor_index);
             format!("{}:{}", name, desc)
         }
         CpInfo::ConstantMethodRef {
-            tag,
             class_index,
             name_and_type_index,
+            ..
         } => {
-            let class = constant_pool_value_at(constant_pool, class_index.clone());
-            let nt = constant_pool_value_at(constant_pool, name_and_type_index.clone());
+            let class = constant_pool_value_at(constant_pool, *class_index);
+            let nt = constant_pool_value_at(constant_pool, *name_and_type_index);
             format!("{}.{}", class, nt)
         }
         CpInfo::ConstantFieldref {
-            tag,
             class_index,
             name_and_type_index,
+            ..
+        }
+        | CpInfo::ConstantInterfaceMethodRef {
+            class_index,
+            name_and_type_index,
+            ..
         } => {
-            let class = constant_pool_value_at(constant_pool, class_index.clone());
-            let nt = constant_pool_value_at(constant_pool, name_and_type_index.clone());
+            let class = constant_pool_value_at(constant_pool, *class_index);
+            let nt = constant_pool_value_at(constant_pool, *name_and_type_index);
             format!("{}.{}", class, nt)
         }
-        _ => todo!("not implemented yet!!"),
+        CpInfo::ConstantString { string_index, .. } => {
+            constant_pool_value_at(constant_pool, *string_index)
+        }
+        CpInfo::ConstantInteger { bytes, .. } => (*bytes as i32).to_string(),
+        CpInfo::ConstantFloat { bytes, .. } => f32::from_bits(*bytes).to_string(),
+        CpInfo::ConstantLong {
+            high_bytes,
+            low_bytes,
+            ..
+        } => long_bits_of(*high_bytes, *low_bytes).to_string(),
+        CpInfo::ConstantDouble {
+            high_bytes,
+            low_bytes,
+            ..
+        } => f64::from_bits(long_bits_of(*high_bytes, *low_bytes) as u64).to_string(),
+        CpInfo::Unusable => String::new(),
+        CpInfo::ConstantMethodHandle {
+            reference_kind,
+            reference_index,
+            ..
+        } => {
+            let reference = constant_pool_value_at(constant_pool, *reference_index);
+            format!(
+                "{} {}",
+                REFERENCE_KINDS[*reference_kind as usize - 1],
+                reference
+            )
+        }
+        CpInfo::ConstantMethodType {
+            descriptor_index, ..
+        } => constant_pool_value_at(constant_pool, *descriptor_index),
+        CpInfo::ConstantDynamic {
+            bootstrap_method_attr_index,
+            name_and_type_index,
+            ..
+        }
+        | CpInfo::ConstantInvokeDynamic {
+            bootstrap_method_attr_index,
+            name_and_type_index,
+            ..
+        } => {
+            let nt = constant_pool_value_at(constant_pool, *name_and_type_index);
+            format!("#{}:{}", bootstrap_method_attr_index, nt)
+        }
+        CpInfo::ConstantModule { name_index, .. } | CpInfo::ConstantPackage { name_index, .. } => {
+            constant_pool_value_at(constant_pool, *name_index)
+        }
     };
     let cp_not_found_error = |index: u16| {
         panic!(
@@ -215,11 +574,15 @@ pub fn constant_pool_value_at(constant_pool: &Vec<CpInfo>, index: u16) -> String
     };
 
     let maybe_cp = constant_pool.get((index - 1) as usize);
-    let value = match maybe_cp {
+    match maybe_cp {
         Some(cp) => parse_cp_value(cp),
         None => cp_not_found_error(index),
-    };
-    value
+    }
+}
+
+/// the value of CONSTANT_Long, also the bits of CONSTANT_Double
+pub fn long_bits_of(high_bytes: u32, low_bytes: u32) -> i64 {
+    (((high_bytes as u64) << 32) | low_bytes as u64) as i64
 }
 
 #[test]
@@ -233,14 +596,40 @@ fn test_parse_cp_info() {
     let _ = read_u16(&mut cursor);
     let constant_pool_count = read_u16(&mut cursor);
 
-    let result = parse_cp_info(&mut cursor, constant_pool_count);
+    let result = parse_cp_info(&mut cursor, constant_pool_count).unwrap();
     assert_eq!(result.len(), (constant_pool_count - 1) as usize);
 }
 
+#[test]
+fn test_parse_cp_info_with_invalid_index() {
+    // #1 = Class #3, where #3 is out of the constant pool
+    let bytes: &[u8] = &[0x07, 0x00, 0x03, 0x01, 0x00, 0x01, b'A'];
+    let mut cursor = Cursor::new(bytes);
+
+    let result = parse_cp_info(&mut cursor, 3);
+
+    assert_eq!(
+        result.unwrap_err(),
+        "Invalid constant pool index 3, [CONSTANT_Utf8] is expected"
+    );
+}
+
+#[test]
+fn test_decode_modified_utf8() {
+    // the null character and U+1F600 as a surrogate pair
+    let bytes: &[u8] = &[
+        b'a', 0xc0, 0x80, 0xed, 0xa0, 0xbd, 0xed, 0xb8, 0x80, 0xe3, 0x81, 0x82,
+    ];
+
+    assert_eq!(decode_modified_utf8(bytes).unwrap(), "a\0\u{1F600}\u{3042}");
+    assert!(decode_modified_utf8(&[0x00]).is_err());
+    assert!(decode_modified_utf8(&[0xc0]).is_err());
+}
+
 #[test]
 fn test_constant_pool_value_at() {
     let binary = read_binary_file(&"java/SimpleSum.class".to_owned()).unwrap();
-    let class_file = ClassFile::parse_from(binary.as_slice());
+    let class_file = ClassFile::parse_from(binary.as_slice()).unwrap();
 
     // for cp index, see @sampleSum.jvm file
     let cp = class_file.constant_pool;
@@ -260,11 +649,39 @@ fn test_constant_pool_value_at() {
     assert_eq!(field_ref, "java/lang/System.out:Ljava/io/PrintStream;");
 }
 
+#[test]
+fn test_constant_pool_value_of_loadable_constants() {
+    let binary = read_binary_file(&"java/Constants.class".to_owned()).unwrap();
+    let class_file = ClassFile::parse_from(binary.as_slice()).unwrap();
+
+    // for cp index, see Constants.jvm. long and double take two entries
+    let cp = class_file.constant_pool;
+    assert_eq!(constant_pool_value_at(&cp, 7), "1234567890123");
+    assert!(matches!(cp[7], CpInfo::Unusable));
+    assert_eq!(constant_pool_value_at(&cp, 9), "3.14159");
+    assert_eq!(constant_pool_value_at(&cp, 11), "0.25");
+    assert_eq!(constant_pool_value_at(&cp, 12), "100000");
+    assert_eq!(constant_pool_value_at(&cp, 13), "hello");
+    assert_eq!(
+        constant_pool_value_at(&cp, 21),
+        "java/util/function/IntSupplier.getAsInt:()I"
+    );
+    assert_eq!(
+        constant_pool_value_at(&cp, 58),
+        "REF_invokeStatic Constants.large:()I"
+    );
+    assert_eq!(constant_pool_value_at(&cp, 57), "()I");
+    assert_eq!(
+        constant_pool_value_at(&cp, 17),
+        "#0:getAsInt:()Ljava/util/function/IntSupplier;"
+    );
+}
+
 #[test]
 #[should_panic]
 fn test_constant_pool_value_obe() {
     let binary = read_binary_file(&"java/SimpleSum.class".to_owned()).unwrap();
-    let class_file = ClassFile::parse_from(binary.as_slice());
+    let class_file = ClassFile::parse_from(binary.as_slice()).unwrap();
     let cp = class_file.constant_pool;
 
     let _ = constant_pool_value_at(&cp, 31);
@@ -0,0 +1,106 @@
+use std::fs;
+use std::io::BufRead;
+use std::rc::Rc;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+use crate::class::Class;
+use crate::jvm::JVM;
+use crate::redefinition::redefine_class;
+
+/// Commands of a debugger attached to the running VM, read one per line as `jdb` takes them.
+/// `redefine <class id> <class file name>` replaces the methods of a loaded class,
+/// so a long-running program picks up the recompiled class without a restart.
+/// https://docs.oracle.com/en/java/javase/17/docs/specs/man/jdb.html
+pub struct Debugger {
+    commands: Receiver<String>,
+}
+
+impl Debugger {
+    /// read the commands from `input` on a thread of its own, such as the standard input
+    pub fn attach<R: BufRead + Send + 'static>(input: R) -> Debugger {
+        let (sender, commands) = mpsc::channel();
+        thread::spawn(move || {
+            for line in input.lines().map_while(Result::ok) {
+                if sender.send(line).is_err() {
+                    return;
+                }
+            }
+        });
+        Debugger { commands }
+    }
+
+    /// run the commands received so far, between two instructions of the interpreter
+    pub fn poll(&self, vm: &JVM) {
+        while let Ok(line) = self.commands.try_recv() {
+            match execute_command(vm, &line) {
+                Ok(reply) => println!("> {}", reply),
+                Err(message) => println!("> {}: {}", line.trim(), message),
+            }
+        }
+    }
+}
+
+/// run a command line, returning the reply for the user
+pub fn execute_command(vm: &JVM, line: &str) -> Result<String, String> {
+    let mut words = line.split_whitespace();
+    match (words.next(), words.next(), words.next(), words.next()) {
+        (Some("redefine"), Some(class_name), Some(class_file), None) => {
+            let class = loaded_class(vm, class_name)?;
+            let binary = fs::read(class_file)
+                .map_err(|e| format!("Unable to read {}, {}", class_file, e))?;
+            redefine_class(vm, &class, &binary).map_err(|throwable| throwable.to_string())?;
+            Ok(format!("redefined {}", class_name))
+        }
+        (Some("redefine"), ..) => Err(String::from("Usage: redefine <class id> <class file name>")),
+        (Some(command), ..) => Err(format!("Unrecognized command: '{}'", command)),
+        (None, ..) => Err(String::from("No command")),
+    }
+}
+
+/// the class of the binary name, by whichever loader defined it
+fn loaded_class(vm: &JVM, class_name: &str) -> Result<Rc<Class>, String> {
+    let class_name = class_name.replace('.', "/");
+    vm.loaded_classes()
+        .into_iter()
+        .find(|class| class.descriptor == class_name)
+        .ok_or_else(|| format!("{} is not loaded", class_name.replace('/', ".")))
+}
+
+#[test]
+fn test_redefine_command() {
+    let vm = JVM::create_with_class_path("java/redefine");
+    let greeting = vm.resolve_class("Greeting").unwrap();
+    let (sender, commands) = mpsc::channel();
+    let debugger = Debugger { commands };
+    sender
+        .send(String::from(
+            "redefine Greeting java/redefine/v2/Greeting.class",
+        ))
+        .unwrap();
+
+    debugger.poll(&vm);
+
+    // iconst_2, ireturn of v2
+    let method = greeting.method("value:()I").unwrap();
+    assert_eq!(method.get_code_attribute().code, [0x05, 0xac]);
+    assert!(debugger.commands.try_recv().is_err());
+}
+
+#[test]
+fn test_invalid_command() {
+    let vm = JVM::create_with_class_path("java/redefine");
+
+    assert_eq!(
+        execute_command(&vm, "redefine Caller java/redefine/Caller.class"),
+        Err(String::from("Caller is not loaded"))
+    );
+    assert_eq!(
+        execute_command(&vm, "redefine Caller"),
+        Err(String::from("Usage: redefine <class id> <class file name>"))
+    );
+    assert_eq!(
+        execute_command(&vm, "stop at Caller:3"),
+        Err(String::from("Unrecognized command: 'stop'"))
+    );
+}
@@ -0,0 +1,119 @@
+use crate::class::Class;
+use crate::class_file::{ACC_FINAL, ACC_STATIC};
+use crate::jvm::{CLASS_INITIALIZER, JVM};
+use crate::runtime_constant_pool::{resolve_constant_in, ResolvedConstant};
+use crate::thread::Thread;
+use crate::throwable::{
+    Throwable, ILLEGAL_ACCESS_ERROR, INCOMPATIBLE_CLASS_CHANGE_ERROR,
+    UNSUPPORTED_OPERATION_EXCEPTION,
+};
+use std::rc::Rc;
+use std::sync::Arc;
+
+/// resolve the field ref at `cp_index`, which must be a static field.
+/// the class declaring it is initialized by the caller once linked, JVMS 5.5.
+/// a static field inherited from a superclass only initializes that superclass.
+fn resolve_static_field(
+    vm: &JVM,
+    thread: &Thread,
+    fieldref_cp_index: u16,
+) -> Result<(Rc<Class>, String), Throwable> {
+    let current_frame = thread.java_virtual_machine_stack.last().unwrap();
+    let ResolvedConstant::Field {
+        class,
+        name_and_descriptor,
+    } = resolve_constant_in(
+        vm,
+        &current_frame.context,
+        &current_frame.constant_pool,
+        fieldref_cp_index,
+    )?
+    else {
+        panic!(
+            "Error: #{} is not a field ref in {}",
+            fieldref_cp_index, current_frame.context.descriptor
+        )
+    };
+    if class.field(&name_and_descriptor).unwrap().access_flags & ACC_STATIC == 0 {
+        return Err(Throwable::new(
+            INCOMPATIBLE_CLASS_CHANGE_ERROR,
+            format!(
+                "Expected static field {}.{}",
+                class.descriptor, name_and_descriptor
+            ),
+        ));
+    }
+
+    Ok((class, name_and_descriptor))
+}
+
+/// a final field is set only by the `<clinit>` of its class, JVMS 6.5.putstatic
+fn check_final_static(
+    thread: &Thread,
+    owner: &Rc<Class>,
+    name_and_descriptor: &str,
+) -> Result<(), Throwable> {
+    if owner.field(name_and_descriptor).unwrap().access_flags & ACC_FINAL == 0 {
+        return Ok(());
+    }
+    let current_frame = thread.java_virtual_machine_stack.last().unwrap();
+    let context = &current_frame.context;
+    let field = format!("{}.{}", owner.descriptor, name_and_descriptor);
+    if !Rc::ptr_eq(context, owner) {
+        return Err(Throwable::new(
+            ILLEGAL_ACCESS_ERROR,
+            format!(
+                "Update to static final field {} attempted from a different class ({}) than the field's declaring class",
+                field, context.descriptor
+            ),
+        ));
+    }
+    let method = &current_frame.current_method;
+    if context
+        .method(CLASS_INITIALIZER)
+        .is_some_and(|clinit| Arc::ptr_eq(&clinit, method))
+    {
+        return Ok(());
+    }
+    Err(Throwable::new(
+        ILLEGAL_ACCESS_ERROR,
+        format!(
+            "Update to static final field {} attempted from a different method ({}) than the initializer method <clinit>",
+            field,
+            context.data().constant_pool[method.name_index as usize]
+        ),
+    ))
+}
+
+pub fn get_static(vm: &JVM, thread: &mut Thread, fieldref_cp_index: u16) -> Result<(), Throwable> {
+    let (owner, name_and_descriptor) = resolve_static_field(vm, thread, fieldref_cp_index)?;
+    vm.initialize_class(&owner)?;
+    // only a String constant has no value, see `Class::create`
+    let value = owner.static_value(&name_and_descriptor).ok_or_else(|| {
+        Throwable::new(
+            UNSUPPORTED_OPERATION_EXCEPTION,
+            format!(
+                "the String constant {}.{} needs a heap, which isn't supported yet",
+                owner.descriptor, name_and_descriptor
+            ),
+        )
+    })?;
+
+    let current_frame = thread.java_virtual_machine_stack.last_mut().unwrap();
+    current_frame.push(value);
+    Ok(())
+}
+
+pub fn put_static(vm: &JVM, thread: &mut Thread, fieldref_cp_index: u16) -> Result<(), Throwable> {
+    let (owner, name_and_descriptor) = resolve_static_field(vm, thread, fieldref_cp_index)?;
+    check_final_static(thread, &owner, &name_and_descriptor)?;
+    vm.initialize_class(&owner)?;
+
+    let current_frame = thread.java_virtual_machine_stack.last_mut().unwrap();
+    let value = current_frame.pop();
+    owner
+        .static_values
+        .borrow_mut()
+        .insert(name_and_descriptor, value);
+    Ok(())
+}
@@ -0,0 +1,318 @@
+use std::io;
+
+/// DEFLATE decoder for the entries of JAR/ZIP files.
+/// https://www.rfc-editor.org/rfc/rfc1951
+pub fn inflate(input: &[u8]) -> io::Result<Vec<u8>> {
+    let mut reader = BitReader::create(input);
+    let mut output = vec![];
+
+    loop {
+        let is_final_block = reader.bits(1)? == 1;
+        match reader.bits(2)? {
+            0 => inflate_stored_block(&mut reader, &mut output)?,
+            1 => {
+                let (literal_length, distance) = fixed_huffman();
+                inflate_compressed_block(&mut reader, &mut output, &literal_length, &distance)?
+            }
+            2 => {
+                let (literal_length, distance) = dynamic_huffman(&mut reader)?;
+                inflate_compressed_block(&mut reader, &mut output, &literal_length, &distance)?
+            }
+            block_type => return Err(invalid_data(format!("invalid block type {}", block_type))),
+        }
+        if is_final_block {
+            break;
+        }
+    }
+    Ok(output)
+}
+
+const MAX_CODE_LENGTH: usize = 15;
+const END_OF_BLOCK: u16 = 256;
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA_BITS: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA_BITS: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+/// the order of the code length code lengths in a dynamic block header
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+/// reads bits from the least significant bit of each byte, as DEFLATE packs them.
+struct BitReader<'a> {
+    input: &'a [u8],
+    position: usize,
+    bit_buffer: u32,
+    bit_count: u32,
+}
+
+impl<'a> BitReader<'a> {
+    fn create(input: &'a [u8]) -> Self {
+        BitReader {
+            input,
+            position: 0,
+            bit_buffer: 0,
+            bit_count: 0,
+        }
+    }
+
+    fn bits(&mut self, count: u32) -> io::Result<u32> {
+        while self.bit_count < count {
+            let byte = self.next_byte()?;
+            self.bit_buffer |= (byte as u32) << self.bit_count;
+            self.bit_count += 8;
+        }
+        let value = self.bit_buffer & ((1u64 << count) - 1) as u32;
+        self.bit_buffer >>= count;
+        self.bit_count -= count;
+        Ok(value)
+    }
+
+    /// discard the remaining bits of the current byte
+    fn align_to_byte(&mut self) {
+        self.bit_buffer = 0;
+        self.bit_count = 0;
+    }
+
+    fn next_byte(&mut self) -> io::Result<u8> {
+        let byte = *self
+            .input
+            .get(self.position)
+            .ok_or_else(|| invalid_data("unexpected end of deflate stream".to_owned()))?;
+        self.position += 1;
+        Ok(byte)
+    }
+}
+
+/// canonical huffman code, decoded by code length.
+/// `counts[n]` is the number of codes of length n, `symbols` is ordered by code.
+struct Huffman {
+    counts: [u16; MAX_CODE_LENGTH + 1],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn from_lengths(lengths: &[u8]) -> io::Result<Huffman> {
+        let mut counts = [0u16; MAX_CODE_LENGTH + 1];
+        for &length in lengths {
+            counts[length as usize] += 1;
+        }
+        counts[0] = 0;
+
+        // reject over-subscribed code sets. incomplete ones are allowed, as zlib does.
+        let mut left: i32 = 1;
+        for count in counts.iter().skip(1) {
+            left = (left << 1) - *count as i32;
+            if left < 0 {
+                return Err(invalid_data("over-subscribed huffman code".to_owned()));
+            }
+        }
+
+        let mut offsets = [0u16; MAX_CODE_LENGTH + 2];
+        for length in 1..=MAX_CODE_LENGTH {
+            offsets[length + 1] = offsets[length] + counts[length];
+        }
+        let mut symbols = vec![0u16; lengths.len()];
+        for (symbol, &length) in lengths.iter().enumerate() {
+            if length != 0 {
+                symbols[offsets[length as usize] as usize] = symbol as u16;
+                offsets[length as usize] += 1;
+            }
+        }
+        Ok(Huffman { counts, symbols })
+    }
+
+    fn decode(&self, reader: &mut BitReader) -> io::Result<u16> {
+        let mut code: i32 = 0;
+        let mut first: i32 = 0;
+        let mut index: i32 = 0;
+        for length in 1..=MAX_CODE_LENGTH {
+            code |= reader.bits(1)? as i32;
+            let count = self.counts[length] as i32;
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first += count;
+            first <<= 1;
+            code <<= 1;
+        }
+        Err(invalid_data("invalid huffman code".to_owned()))
+    }
+}
+
+fn inflate_stored_block(reader: &mut BitReader, output: &mut Vec<u8>) -> io::Result<()> {
+    reader.align_to_byte();
+    let length = reader.bits(16)? as u16;
+    let complement = reader.bits(16)? as u16;
+    if length != !complement {
+        return Err(invalid_data(
+            "stored block length doesn't match its complement".to_owned(),
+        ));
+    }
+    for _ in 0..length {
+        output.push(reader.next_byte()?);
+    }
+    Ok(())
+}
+
+fn inflate_compressed_block(
+    reader: &mut BitReader,
+    output: &mut Vec<u8>,
+    literal_length: &Huffman,
+    distance: &Huffman,
+) -> io::Result<()> {
+    loop {
+        let symbol = literal_length.decode(reader)?;
+        if symbol < END_OF_BLOCK {
+            output.push(symbol as u8);
+            continue;
+        }
+        if symbol == END_OF_BLOCK {
+            return Ok(());
+        }
+
+        let index = (symbol - 257) as usize;
+        if index >= LENGTH_BASE.len() {
+            return Err(invalid_data(format!("invalid length symbol {}", symbol)));
+        }
+        let length =
+            LENGTH_BASE[index] as usize + reader.bits(LENGTH_EXTRA_BITS[index] as u32)? as usize;
+
+        let index = distance.decode(reader)? as usize;
+        if index >= DISTANCE_BASE.len() {
+            return Err(invalid_data(format!("invalid distance symbol {}", index)));
+        }
+        let distance = DISTANCE_BASE[index] as usize
+            + reader.bits(DISTANCE_EXTRA_BITS[index] as u32)? as usize;
+        if distance > output.len() {
+            return Err(invalid_data(format!(
+                "distance {} is too far back, only {} bytes are decoded",
+                distance,
+                output.len()
+            )));
+        }
+
+        // the copied range may overlap with the bytes being written
+        let start = output.len() - distance;
+        for i in 0..length {
+            output.push(output[start + i]);
+        }
+    }
+}
+
+fn fixed_huffman() -> (Huffman, Huffman) {
+    let mut lengths = [0u8; 288];
+    lengths[0..144].fill(8);
+    lengths[144..256].fill(9);
+    lengths[256..280].fill(7);
+    lengths[280..288].fill(8);
+    let literal_length = Huffman::from_lengths(&lengths).unwrap();
+    let distance = Huffman::from_lengths(&[5u8; 30]).unwrap();
+    (literal_length, distance)
+}
+
+fn dynamic_huffman(reader: &mut BitReader) -> io::Result<(Huffman, Huffman)> {
+    let literal_length_count = reader.bits(5)? as usize + 257;
+    let distance_count = reader.bits(5)? as usize + 1;
+    let code_length_count = reader.bits(4)? as usize + 4;
+
+    let mut code_length_lengths = [0u8; 19];
+    for &index in CODE_LENGTH_ORDER.iter().take(code_length_count) {
+        code_length_lengths[index] = reader.bits(3)? as u8;
+    }
+    let code_length = Huffman::from_lengths(&code_length_lengths)?;
+
+    let mut lengths = vec![];
+    while lengths.len() < literal_length_count + distance_count {
+        let symbol = code_length.decode(reader)?;
+        let (value, repeat) = match symbol {
+            0..=15 => (symbol as u8, 1),
+            16 => {
+                let previous = *lengths
+                    .last()
+                    .ok_or_else(|| invalid_data("repeat with no previous length".to_owned()))?;
+                (previous, 3 + reader.bits(2)?)
+            }
+            17 => (0, 3 + reader.bits(3)?),
+            18 => (0, 11 + reader.bits(7)?),
+            _ => {
+                return Err(invalid_data(format!(
+                    "invalid code length symbol {}",
+                    symbol
+                )))
+            }
+        };
+        for _ in 0..repeat {
+            lengths.push(value);
+        }
+    }
+    if lengths.len() > literal_length_count + distance_count {
+        return Err(invalid_data("too many code lengths".to_owned()));
+    }
+    if lengths[END_OF_BLOCK as usize] == 0 {
+        return Err(invalid_data("missing end-of-block code".to_owned()));
+    }
+
+    let literal_length = Huffman::from_lengths(&lengths[..literal_length_count])?;
+    let distance = Huffman::from_lengths(&lengths[literal_length_count..])?;
+    Ok((literal_length, distance))
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[test]
+fn test_inflate_stored() {
+    // python3 -c "import zlib; c = zlib.compressobj(0, wbits=-15); print(list(c.compress(b'rust-jvm') + c.flush()))"
+    let bytes: &[u8] = &[
+        0x01, 0x08, 0x00, 0xf7, 0xff, 0x72, 0x75, 0x73, 0x74, 0x2d, 0x6a, 0x76, 0x6d,
+    ];
+
+    let result = inflate(bytes).unwrap();
+
+    assert_eq!(result, b"rust-jvm");
+}
+
+#[test]
+fn test_inflate_fixed_huffman() {
+    // python3 -c "import zlib; c = zlib.compressobj(9, wbits=-15); print(list(c.compress(b'abcabcabcabc') + c.flush()))"
+    let bytes: &[u8] = &[0x4b, 0x4c, 0x4a, 0x4e, 0x84, 0x21, 0x00];
+
+    let result = inflate(bytes).unwrap();
+
+    assert_eq!(result, b"abcabcabcabc");
+}
+
+#[test]
+fn test_inflate_class_file() {
+    // a class file deflated by the jar tool, with a dynamic huffman block
+    let expected = crate::binary::read_binary_file(&"java/com/acme/Main.class".to_owned()).unwrap();
+    let archive = crate::zip::ZipArchive::open("java/acme.jar").unwrap();
+
+    let result = archive.read("com/acme/Main.class").unwrap();
+
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_inflate_truncated() {
+    let bytes: &[u8] = &[0x4b, 0x4c, 0x4a];
+
+    let result = inflate(bytes);
+
+    assert!(result.is_err());
+}
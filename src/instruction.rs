@@ -1,39 +1,56 @@
-use crate::binary::{debug_bytes, read_i16, read_u16, read_u8};
+use crate::binary::{read_i16, read_i8, read_u16, read_u8};
+use crate::instruction::Fields::{GetStatic, PutStatic};
 use crate::instruction::Invokes::InvokeStatic;
-use crate::instruction::Result::{Invoke, Return};
-use crate::instruction::Returns::IReturn;
+use crate::instruction::Result::{Field, Invoke, LoadConstant, Return, Throw};
+use crate::instruction::Returns::{DReturn, FReturn, IReturn, LReturn};
 use crate::instruction_set::Instruction;
 use crate::thread::Frame;
-use std::io;
-use std::io::{Cursor, Write};
+use crate::throwable::{Throwable, ARITHMETIC_EXCEPTION};
+use crate::value::Value;
+use std::cmp::Ordering;
+use std::io::Cursor;
+use std::sync::Arc;
 
 #[derive(Debug)]
 pub enum Invokes {
     InvokeStatic { cp_index: u16 },
 }
 
+#[derive(Debug)]
+pub enum Fields {
+    GetStatic { cp_index: u16 },
+    PutStatic { cp_index: u16 },
+}
+
 #[derive(Debug)]
 pub enum Returns {
-    IReturn { val: u64 },
+    IReturn { val: i32 },
+    LReturn { val: i64 },
+    FReturn { val: f32 },
+    DReturn { val: f64 },
     Return,
 }
 
 #[derive(Debug)]
 pub enum Result {
+    Field(Fields),
     Invoke(Invokes),
     Return(Returns),
+    /// `ldc`, `ldc_w` or `ldc2_w` of the entry, resolved by the run-time constant pool
+    LoadConstant {
+        cp_index: u16,
+    },
+    /// an exception thrown by the instruction itself, such as `idiv` by zero
+    Throw(Throwable),
 }
 
 pub fn instruction(frame: &mut Frame) -> Result {
-    let code = &frame.current_method.get_code_attribute().code;
-    // debug_bytes(code);
+    let method = Arc::clone(&frame.current_method);
+    let code = &method.get_code_attribute().code;
 
     let cursor = &mut Cursor::new(code.as_slice());
     cursor.set_position(frame.pc);
 
-    let local_variable = &mut frame.local_variable;
-    let operand_stack = &mut frame.operand_stack;
-
     let result = loop {
         frame.pc = cursor.position();
         let instruction_code = read_u8(cursor);
@@ -45,79 +62,313 @@ pub fn instruction(frame: &mut Frame) -> Result {
 
         match instruction {
             Instruction::BIPUSH => {
-                // TODO: handle type...
-                let val = read_u8(cursor);
-                operand_stack.push(val as u64);
+                // the immediate byte is sign-extended to an int
+                let val = read_i8(cursor);
+                frame.push(Value::Int(val as i32));
+            }
+            Instruction::SIPUSH => {
+                let val = read_i16(cursor);
+                frame.push(Value::Int(val as i32));
+            }
+
+            // iconst_<i> pushes <i>, from iconst_m1 (0x2) to iconst_5 (0x8)
+            Instruction::ICONST_M1
+            | Instruction::ICONST_0
+            | Instruction::ICONST_1
+            | Instruction::ICONST_2
+            | Instruction::ICONST_3
+            | Instruction::ICONST_4
+            | Instruction::ICONST_5 => {
+                let val = instruction_code as i32 - Instruction::ICONST_0 as i32;
+                frame.push(Value::Int(val));
+            }
+
+            Instruction::ISTORE => {
+                let index = read_u8(cursor) as usize;
+                let val = frame.pop();
+                frame.store(index, val);
+            }
+            Instruction::ISTORE_0
+            | Instruction::ISTORE_1
+            | Instruction::ISTORE_2
+            | Instruction::ISTORE_3 => {
+                let index = (instruction_code - Instruction::ISTORE_0 as u8) as usize;
+                let val = frame.pop();
+                frame.store(index, val);
+            }
+
+            Instruction::ILOAD => {
+                let index = read_u8(cursor) as usize;
+                frame.push(frame.load(index));
+            }
+            Instruction::ILOAD_0
+            | Instruction::ILOAD_1
+            | Instruction::ILOAD_2
+            | Instruction::ILOAD_3 => {
+                let index = (instruction_code - Instruction::ILOAD_0 as u8) as usize;
+                frame.push(frame.load(index));
+            }
+
+            // int arithmetic wraps around on overflow, JVMS 2.11.3
+            Instruction::IADD
+            | Instruction::ISUB
+            | Instruction::IMUL
+            | Instruction::ISHL
+            | Instruction::ISHR
+            | Instruction::IUSHR
+            | Instruction::IAND
+            | Instruction::IOR
+            | Instruction::IXOR => {
+                let val2 = frame.pop().as_int();
+                let val1 = frame.pop().as_int();
+
+                frame.push(Value::Int(int_operation(&instruction, val1, val2)));
+            }
+
+            Instruction::IDIV | Instruction::IREM => {
+                let val2 = frame.pop().as_int();
+                let val1 = frame.pop().as_int();
+                if val2 == 0 {
+                    break Throw(divide_by_zero());
+                }
+
+                // Integer.MIN_VALUE / -1 overflows to Integer.MIN_VALUE, and its remainder is 0
+                let result = match instruction {
+                    Instruction::IDIV => val1.wrapping_div(val2),
+                    _ => val1.wrapping_rem(val2),
+                };
+                frame.push(Value::Int(result));
             }
 
-            Instruction::ICONST_0 => {
-                operand_stack.push(0);
+            Instruction::INEG => {
+                let val = frame.pop().as_int();
+                frame.push(Value::Int(val.wrapping_neg()));
             }
-            Instruction::ICONST_1 => {
-                operand_stack.push(1);
+
+            Instruction::IINC => {
+                let index = read_u8(cursor) as usize;
+                let const_val = read_i8(cursor);
+
+                let val = frame.load(index).as_int();
+                frame.store(index, Value::Int(val.wrapping_add(const_val as i32)));
+            }
+
+            Instruction::LCONST_0 | Instruction::LCONST_1 => {
+                let val = (instruction_code - Instruction::LCONST_0 as u8) as i64;
+                frame.push(Value::Long(val));
+            }
+            Instruction::LDC => {
+                let cp_index = read_u8(cursor) as u16;
+                break LoadConstant { cp_index };
+            }
+            Instruction::LDC_W | Instruction::LDC2_W => {
+                let cp_index = read_u16(cursor);
+                break LoadConstant { cp_index };
+            }
+
+            Instruction::FCONST_0 | Instruction::FCONST_1 | Instruction::FCONST_2 => {
+                let val = (instruction_code - Instruction::FCONST_0 as u8) as f32;
+                frame.push(Value::Float(val));
             }
-            Instruction::ICONST_2 => {
-                operand_stack.push(2);
+            Instruction::DCONST_0 | Instruction::DCONST_1 => {
+                let val = (instruction_code - Instruction::DCONST_0 as u8) as f64;
+                frame.push(Value::Double(val));
             }
 
-            Instruction::ISTORE_0 => {
-                let val = operand_stack.pop().unwrap();
-                local_variable[0] = val;
+            Instruction::FSTORE | Instruction::DSTORE => {
+                let index = read_u8(cursor) as usize;
+                let val = frame.pop();
+                frame.store(index, val);
             }
-            Instruction::ISTORE_1 => {
-                let val = operand_stack.pop().unwrap();
-                local_variable[1] = val;
+            Instruction::FSTORE_0
+            | Instruction::FSTORE_1
+            | Instruction::FSTORE_2
+            | Instruction::FSTORE_3 => {
+                let index = (instruction_code - Instruction::FSTORE_0 as u8) as usize;
+                let val = frame.pop();
+                frame.store(index, val);
             }
-            Instruction::ISTORE_2 => {
-                let val = operand_stack.pop().unwrap();
-                local_variable[2] = val;
+            Instruction::DSTORE_0
+            | Instruction::DSTORE_1
+            | Instruction::DSTORE_2
+            | Instruction::DSTORE_3 => {
+                let index = (instruction_code - Instruction::DSTORE_0 as u8) as usize;
+                let val = frame.pop();
+                frame.store(index, val);
             }
 
-            Instruction::ILOAD_0 => {
-                let val = local_variable[0];
-                operand_stack.push(val);
+            Instruction::FLOAD | Instruction::DLOAD => {
+                let index = read_u8(cursor) as usize;
+                frame.push(frame.load(index));
             }
-            Instruction::ILOAD_1 => {
-                let val = local_variable[1];
-                operand_stack.push(val);
+            Instruction::FLOAD_0
+            | Instruction::FLOAD_1
+            | Instruction::FLOAD_2
+            | Instruction::FLOAD_3 => {
+                let index = (instruction_code - Instruction::FLOAD_0 as u8) as usize;
+                frame.push(frame.load(index));
             }
-            Instruction::ILOAD_2 => {
-                let val = local_variable[2];
-                operand_stack.push(val);
+            Instruction::DLOAD_0
+            | Instruction::DLOAD_1
+            | Instruction::DLOAD_2
+            | Instruction::DLOAD_3 => {
+                let index = (instruction_code - Instruction::DLOAD_0 as u8) as usize;
+                frame.push(frame.load(index));
             }
 
-            Instruction::IADD => {
-                let val2 = operand_stack.pop().unwrap();
-                let val1 = operand_stack.pop().unwrap();
+            // IEEE 754 arithmetic, where `%` truncates like C's fmod rather than rounding
+            // like IEEE remainder, JVMS 2.8
+            Instruction::FADD
+            | Instruction::FSUB
+            | Instruction::FMUL
+            | Instruction::FDIV
+            | Instruction::FREM => {
+                let val2 = frame.pop().as_float();
+                let val1 = frame.pop().as_float();
+
+                let result = match instruction {
+                    Instruction::FADD => val1 + val2,
+                    Instruction::FSUB => val1 - val2,
+                    Instruction::FMUL => val1 * val2,
+                    Instruction::FDIV => val1 / val2,
+                    _ => val1 % val2,
+                };
+                frame.push(Value::Float(result));
+            }
+            Instruction::DADD
+            | Instruction::DSUB
+            | Instruction::DMUL
+            | Instruction::DDIV
+            | Instruction::DREM => {
+                let val2 = frame.pop().as_double();
+                let val1 = frame.pop().as_double();
+
+                let result = match instruction {
+                    Instruction::DADD => val1 + val2,
+                    Instruction::DSUB => val1 - val2,
+                    Instruction::DMUL => val1 * val2,
+                    Instruction::DDIV => val1 / val2,
+                    _ => val1 % val2,
+                };
+                frame.push(Value::Double(result));
+            }
 
-                let result = val1 + val2;
-                operand_stack.push(result);
+            Instruction::FNEG => {
+                let val = frame.pop().as_float();
+                frame.push(Value::Float(-val));
+            }
+            Instruction::DNEG => {
+                let val = frame.pop().as_double();
+                frame.push(Value::Double(-val));
             }
 
-            Instruction::ISUB => {
-                let val2 = operand_stack.pop().unwrap();
-                let val1 = operand_stack.pop().unwrap();
+            // the `l` forms push -1 and the `g` forms push 1 when either value is NaN
+            Instruction::FCMPL | Instruction::FCMPG => {
+                let val2 = frame.pop().as_float();
+                let val1 = frame.pop().as_float();
+
+                let nan_result = if matches!(instruction, Instruction::FCMPG) {
+                    1
+                } else {
+                    -1
+                };
+                frame.push(Value::Int(compare(val1.partial_cmp(&val2), nan_result)));
+            }
+            Instruction::DCMPL | Instruction::DCMPG => {
+                let val2 = frame.pop().as_double();
+                let val1 = frame.pop().as_double();
+
+                let nan_result = if matches!(instruction, Instruction::DCMPG) {
+                    1
+                } else {
+                    -1
+                };
+                frame.push(Value::Int(compare(val1.partial_cmp(&val2), nan_result)));
+            }
 
-                let result = val1 - val2;
-                operand_stack.push(result);
+            // a long takes two local variables, from `index` to `index + 1`
+            Instruction::LSTORE => {
+                let index = read_u8(cursor) as usize;
+                let val = frame.pop();
+                frame.store(index, val);
+            }
+            Instruction::LSTORE_0
+            | Instruction::LSTORE_1
+            | Instruction::LSTORE_2
+            | Instruction::LSTORE_3 => {
+                let index = (instruction_code - Instruction::LSTORE_0 as u8) as usize;
+                let val = frame.pop();
+                frame.store(index, val);
             }
 
-            Instruction::SIPUSH => {
-                // TODO: handle type...
-                let val = read_u16(cursor);
-                operand_stack.push(val as u64);
+            Instruction::LLOAD => {
+                let index = read_u8(cursor) as usize;
+                frame.push(frame.load(index));
+            }
+            Instruction::LLOAD_0
+            | Instruction::LLOAD_1
+            | Instruction::LLOAD_2
+            | Instruction::LLOAD_3 => {
+                let index = (instruction_code - Instruction::LLOAD_0 as u8) as usize;
+                frame.push(frame.load(index));
             }
 
-            Instruction::IINC => {
-                let index = read_u8(cursor);
-                let const_val = read_u8(cursor);
+            Instruction::LADD
+            | Instruction::LSUB
+            | Instruction::LMUL
+            | Instruction::LAND
+            | Instruction::LOR
+            | Instruction::LXOR => {
+                let val2 = frame.pop().as_long();
+                let val1 = frame.pop().as_long();
 
-                local_variable[index as usize] += const_val as u64;
+                frame.push(Value::Long(long_operation(&instruction, val1, val2)));
+            }
+
+            // the shift distance is an int, and only its low 6 bits are used
+            Instruction::LSHL | Instruction::LSHR | Instruction::LUSHR => {
+                let val2 = frame.pop().as_int() & 0x3f;
+                let val1 = frame.pop().as_long();
+
+                let result = match instruction {
+                    Instruction::LSHL => val1 << val2,
+                    Instruction::LSHR => val1 >> val2,
+                    _ => ((val1 as u64) >> val2) as i64,
+                };
+                frame.push(Value::Long(result));
+            }
+
+            Instruction::LDIV | Instruction::LREM => {
+                let val2 = frame.pop().as_long();
+                let val1 = frame.pop().as_long();
+                if val2 == 0 {
+                    break Throw(divide_by_zero());
+                }
+
+                // Long.MIN_VALUE / -1 overflows to Long.MIN_VALUE, and its remainder is 0
+                let result = match instruction {
+                    Instruction::LDIV => val1.wrapping_div(val2),
+                    _ => val1.wrapping_rem(val2),
+                };
+                frame.push(Value::Long(result));
+            }
+
+            Instruction::LNEG => {
+                let val = frame.pop().as_long();
+                frame.push(Value::Long(val.wrapping_neg()));
+            }
+
+            // 1 if greater, 0 if equal, -1 if less
+            Instruction::LCMP => {
+                let val2 = frame.pop().as_long();
+                let val1 = frame.pop().as_long();
+
+                frame.push(Value::Int(val1.cmp(&val2) as i32));
             }
 
             Instruction::IFGT => {
                 let next_pc_offset = read_i16(cursor);
-                let val = operand_stack.pop().unwrap();
+                let val = frame.pop().as_int();
 
                 if val > 0 {
                     goto_offset(cursor, frame.pc, next_pc_offset);
@@ -127,8 +378,8 @@ pub fn instruction(frame: &mut Frame) -> Result {
             Instruction::IF_ICMPGE => {
                 // pc offset should be read anyway, regardless the comp result
                 let next_pc_offset = read_i16(cursor);
-                let val2 = operand_stack.pop().unwrap();
-                let val1 = operand_stack.pop().unwrap();
+                let val2 = frame.pop().as_int();
+                let val1 = frame.pop().as_int();
 
                 if val1 >= val2 {
                     goto_offset(cursor, frame.pc, next_pc_offset);
@@ -138,8 +389,8 @@ pub fn instruction(frame: &mut Frame) -> Result {
             Instruction::IF_ICMPNE => {
                 // pc offset should be read anyway, regardless the comp result
                 let next_pc_offset = read_i16(cursor);
-                let val2 = operand_stack.pop().unwrap();
-                let val1 = operand_stack.pop().unwrap();
+                let val2 = frame.pop().as_int();
+                let val1 = frame.pop().as_int();
 
                 if val1 != val2 {
                     goto_offset(cursor, frame.pc, next_pc_offset);
@@ -152,21 +403,45 @@ pub fn instruction(frame: &mut Frame) -> Result {
             }
 
             Instruction::POP => {
-                operand_stack.pop();
+                frame.pop();
             }
 
-            /// invoke
+            // field, the declaring class may need to be loaded and initialized first
+            Instruction::GETSTATIC => {
+                let cp_index = read_u16(cursor);
+                break Field(GetStatic { cp_index });
+            }
+            Instruction::PUTSTATIC => {
+                let cp_index = read_u16(cursor);
+                break Field(PutStatic { cp_index });
+            }
+
+            // invoke
             Instruction::INVOKESTATIC => {
                 let cp_index = read_u16(cursor);
                 break Invoke(InvokeStatic { cp_index });
             }
 
-            /// return
+            // return
             Instruction::IRETURN => {
-                let val = operand_stack.pop().unwrap();
+                let val = frame.pop().as_int();
                 break Return(IReturn { val });
             }
 
+            Instruction::LRETURN => {
+                let val = frame.pop().as_long();
+                break Return(LReturn { val });
+            }
+
+            Instruction::FRETURN => {
+                let val = frame.pop().as_float();
+                break Return(FReturn { val });
+            }
+            Instruction::DRETURN => {
+                let val = frame.pop().as_double();
+                break Return(DReturn { val });
+            }
+
             Instruction::RETURN => {
                 break Return(Returns::Return);
             }
@@ -181,6 +456,48 @@ pub fn instruction(frame: &mut Frame) -> Result {
     result
 }
 
+/// the binary int operations that can't fail.
+/// only the low 5 bits of the shift distance are used.
+fn int_operation(instruction: &Instruction, val1: i32, val2: i32) -> i32 {
+    match instruction {
+        Instruction::IADD => val1.wrapping_add(val2),
+        Instruction::ISUB => val1.wrapping_sub(val2),
+        Instruction::IMUL => val1.wrapping_mul(val2),
+        Instruction::ISHL => val1 << (val2 & 0x1f),
+        Instruction::ISHR => val1 >> (val2 & 0x1f),
+        Instruction::IUSHR => ((val1 as u32) >> (val2 & 0x1f)) as i32,
+        Instruction::IAND => val1 & val2,
+        Instruction::IOR => val1 | val2,
+        Instruction::IXOR => val1 ^ val2,
+        _ => panic!("Error: {:#?} isn't an int operation", instruction),
+    }
+}
+
+/// the binary long operations that can't fail, except for the shifts
+fn long_operation(instruction: &Instruction, val1: i64, val2: i64) -> i64 {
+    match instruction {
+        Instruction::LADD => val1.wrapping_add(val2),
+        Instruction::LSUB => val1.wrapping_sub(val2),
+        Instruction::LMUL => val1.wrapping_mul(val2),
+        Instruction::LAND => val1 & val2,
+        Instruction::LOR => val1 | val2,
+        Instruction::LXOR => val1 ^ val2,
+        _ => panic!("Error: {:#?} isn't a long operation", instruction),
+    }
+}
+
+/// 1 if greater, 0 if equal, -1 if less, or `nan_result` if they are unordered
+fn compare(ordering: Option<Ordering>, nan_result: i32) -> i32 {
+    match ordering {
+        Some(ordering) => ordering as i32,
+        None => nan_result,
+    }
+}
+
+fn divide_by_zero() -> Throwable {
+    Throwable::new(ARITHMETIC_EXCEPTION, "/ by zero".to_string())
+}
+
 fn goto_offset(cursor: &mut Cursor<&[u8]>, current_pc: u64, offset: i16) {
     // TODO! unsigned offset calc is suck
     cursor.set_position((current_pc as i64 + offset as i64) as u64);
@@ -192,8 +509,8 @@ fn test_invoke_sum() {
 
     let code: Vec<u8> = vec![0x04, 0x3C, 0x05, 0x3D, 0x1B, 0x1C, 0x60, 0xAC];
     let context = frame_test::dummy_class();
-    let mut current_method = frame_test::dummy_method(code);
-    let mut frame = Frame::create(&Rc::new(context), &Rc::new(current_method));
+    let current_method = frame_test::dummy_method(code);
+    let mut frame = Frame::create(&Rc::new(context), &Arc::new(current_method));
 
     let result = instruction(&mut frame);
 
@@ -210,7 +527,7 @@ fn test_invoke_loop() {
     ];
     let context = frame_test::dummy_class();
     let current_method = frame_test::dummy_method(code);
-    let mut frame = Frame::create(&Rc::new(context), &Rc::new(current_method));
+    let mut frame = Frame::create(&Rc::new(context), &Arc::new(current_method));
 
     let result = instruction(&mut frame);
 
@@ -228,28 +545,169 @@ fn test_invoke_static() {
     ];
     let context = frame_test::dummy_class();
     let current_method = frame_test::dummy_method(code);
-    let mut frame = Frame::create(&Rc::new(context), &Rc::new(current_method));
+    let mut frame = Frame::create(&Rc::new(context), &Arc::new(current_method));
     // argument i = 2
-    frame.local_variable[0] = 2;
+    frame.local_variable[0] = Value::Int(2);
 
     let result = instruction(&mut frame);
 
     assert!(matches!(result, Invoke(InvokeStatic { cp_index: 7 })))
 }
 
+#[test]
+fn test_isub_negative_result() {
+    use std::rc::Rc;
+
+    // iconst_1, iconst_2, isub, ireturn
+    let code: Vec<u8> = vec![0x04, 0x05, 0x64, 0xac];
+    let context = frame_test::dummy_class();
+    let current_method = frame_test::dummy_method(code);
+    let mut frame = Frame::create(&Rc::new(context), &Arc::new(current_method));
+
+    let result = instruction(&mut frame);
+
+    assert!(matches!(result, Return(IReturn { val: -1 })));
+}
+
+#[test]
+fn test_push_sign_extended() {
+    use std::rc::Rc;
+
+    // bipush -2, sipush -300, iadd, ireturn
+    let code: Vec<u8> = vec![0x10, 0xfe, 0x11, 0xfe, 0xd4, 0x60, 0xac];
+    let context = frame_test::dummy_class();
+    let current_method = frame_test::dummy_method(code);
+    let mut frame = Frame::create(&Rc::new(context), &Arc::new(current_method));
+
+    let result = instruction(&mut frame);
+
+    assert!(matches!(result, Return(IReturn { val: -302 })));
+}
+
+#[test]
+fn test_idiv_by_zero() {
+    use std::rc::Rc;
+
+    // iconst_1, iconst_0, idiv, ireturn
+    let code: Vec<u8> = vec![0x04, 0x03, 0x6c, 0xac];
+    let context = frame_test::dummy_class();
+    let current_method = frame_test::dummy_method(code);
+    let mut frame = Frame::create(&Rc::new(context), &Arc::new(current_method));
+
+    let result = instruction(&mut frame);
+
+    assert!(matches!(result, Throw(throwable) if throwable == divide_by_zero()));
+    // the frame stays at idiv, where the exception is thrown
+    assert_eq!(frame.pc, 3);
+}
+
+#[test]
+fn test_lcmp() {
+    use std::rc::Rc;
+
+    // lconst_0, lconst_1, lcmp, ireturn
+    let code: Vec<u8> = vec![0x09, 0x0a, 0x94, 0xac];
+    let context = frame_test::dummy_class();
+    let current_method = frame_test::dummy_method(code);
+    let mut frame = Frame::create(&Rc::new(context), &Arc::new(current_method));
+
+    let result = instruction(&mut frame);
+
+    assert!(matches!(result, Return(IReturn { val: -1 })));
+}
+
+#[test]
+fn test_ldiv_by_zero() {
+    use std::rc::Rc;
+
+    // lconst_1, lstore_1, lload_1, lconst_0, ldiv, lreturn
+    let code: Vec<u8> = vec![0x0a, 0x40, 0x1f, 0x09, 0x6d, 0xad];
+    let context = frame_test::dummy_class();
+    let current_method = frame_test::dummy_method(code);
+    let mut frame = Frame::create(&Rc::new(context), &Arc::new(current_method));
+
+    let result = instruction(&mut frame);
+
+    assert!(matches!(result, Throw(throwable) if throwable == divide_by_zero()));
+    // the long takes the local variables 1 and 2
+    assert_eq!(frame.local_variable[1..3], [Value::Long(1), Value::Top]);
+}
+
+#[test]
+fn test_fcmp_nan() {
+    use std::rc::Rc;
+
+    // fconst_0, fconst_0, fdiv, fstore_0, fload_0, fconst_1, fcmpl, fload_0, fconst_1, fcmpg,
+    // isub, ireturn
+    let code: Vec<u8> = vec![
+        0x0b, 0x0b, 0x6e, 0x43, 0x22, 0x0c, 0x95, 0x22, 0x0c, 0x96, 0x64, 0xac,
+    ];
+    let context = frame_test::dummy_class();
+    let current_method = frame_test::dummy_method(code);
+    let mut frame = Frame::create(&Rc::new(context), &Arc::new(current_method));
+
+    let result = instruction(&mut frame);
+
+    // NaN compared by fcmpl is -1, and by fcmpg is 1
+    assert!(matches!(result, Return(IReturn { val: -2 })));
+}
+
+#[test]
+fn test_dcmp() {
+    use std::rc::Rc;
+
+    // dconst_1, dconst_0, dcmpl, dconst_0, dconst_0, dcmpg, iadd, ireturn
+    let code: Vec<u8> = vec![0x0f, 0x0e, 0x97, 0x0e, 0x0e, 0x98, 0x60, 0xac];
+    let context = frame_test::dummy_class();
+    let current_method = frame_test::dummy_method(code);
+    let mut frame = Frame::create(&Rc::new(context), &Arc::new(current_method));
+
+    let result = instruction(&mut frame);
+
+    assert!(matches!(result, Return(IReturn { val: 1 })));
+}
+
+#[test]
+fn test_dneg_zero() {
+    use std::rc::Rc;
+
+    // dconst_0, dneg, dreturn
+    let code: Vec<u8> = vec![0x0e, 0x77, 0xaf];
+    let context = frame_test::dummy_class();
+    let current_method = frame_test::dummy_method(code);
+    let mut frame = Frame::create(&Rc::new(context), &Arc::new(current_method));
+
+    let result = instruction(&mut frame);
+
+    assert!(matches!(result, Return(DReturn { val }) if val.to_bits() == (-0.0f64).to_bits()));
+}
+
 #[cfg(test)]
 pub mod frame_test {
     use std::collections::HashMap;
+    use std::sync::Arc;
 
     use crate::class::Class;
     use crate::class_attributes::{AttributeInfo, CodeAttributeInfo, MethodInfo};
+    use crate::class_data::ClassData;
+    use crate::class_loader::BOOTSTRAP_LOADER;
 
     pub fn dummy_class() -> Class {
-        Class {
+        Class::create(Arc::new(dummy_class_data()), None, vec![], BOOTSTRAP_LOADER)
+    }
+
+    pub fn dummy_class_data() -> ClassData {
+        ClassData {
             descriptor: "dummy".to_string(),
+            access_flags: 0,
+            super_class_name: None,
+            interface_names: vec![],
             constant_pool: vec![],
+            constants: Arc::new(vec![]),
             methods: HashMap::new(),
             fields: HashMap::new(),
+            nest_host: None,
+            nest_members: vec![],
         }
     }
 
@@ -258,7 +716,6 @@ pub mod frame_test {
             access_flags: 0,
             name_index: 0,
             descriptor_index: 0,
-            attributes_count: 1,
             attributes: vec![dummy_code(code)],
         }
     }
@@ -269,11 +726,8 @@ pub mod frame_test {
             attribute_length: 0,
             max_stack: 100,
             max_locals: 100,
-            code_length: 0,
             code,
-            exception_table_length: 0,
             exception_table: vec![],
-            attributes_count: 0,
             attributes: vec![],
         })
     }
@@ -0,0 +1,127 @@
+use std::rc::Rc;
+
+use crate::class::Class;
+use crate::class_loader::LoaderId;
+#[cfg(test)]
+use crate::value::Value;
+
+/// A hook receiving the bytes of each class file before it is parsed,
+/// as `java.lang.instrument.ClassFileTransformer`.
+/// `class_being_redefined` is the class for a redefinition, and `None` for a class being loaded.
+/// returning `None` leaves the class file as is, and the next transformer receives
+/// the bytes returned by the previous one.
+/// https://docs.oracle.com/en/java/javase/17/docs/api/java.instrument/java/lang/instrument/ClassFileTransformer.html
+pub trait ClassFileTransformer {
+    fn transform(
+        &self,
+        loader: LoaderId,
+        class_name: &str,
+        class_being_redefined: Option<&Rc<Class>>,
+        class_file: &[u8],
+    ) -> Option<Vec<u8>>;
+}
+
+/// a closure can be registered as a transformer
+impl<F> ClassFileTransformer for F
+where
+    F: Fn(LoaderId, &str, Option<&Rc<Class>>, &[u8]) -> Option<Vec<u8>>,
+{
+    fn transform(
+        &self,
+        loader: LoaderId,
+        class_name: &str,
+        class_being_redefined: Option<&Rc<Class>>,
+        class_file: &[u8],
+    ) -> Option<Vec<u8>> {
+        self(loader, class_name, class_being_redefined, class_file)
+    }
+}
+
+/// run the transformers in the order of their registration.
+/// `None` if none of them changed the class file.
+pub fn transform_class_file(
+    transformers: &[Box<dyn ClassFileTransformer>],
+    loader: LoaderId,
+    class_name: &str,
+    class_being_redefined: Option<&Rc<Class>>,
+    class_file: &[u8],
+) -> Option<Vec<u8>> {
+    let mut transformed: Option<Vec<u8>> = None;
+    for transformer in transformers {
+        let current = transformed.as_deref().unwrap_or(class_file);
+        if let Some(class_file) =
+            transformer.transform(loader, class_name, class_being_redefined, current)
+        {
+            transformed = Some(class_file);
+        }
+    }
+    transformed
+}
+
+#[test]
+fn test_transform_class_file_on_loading() {
+    use crate::binary::read_binary_file;
+    use crate::class_loader::APPLICATION_LOADER;
+    use crate::interpreter::interpret;
+    use crate::jvm::JVM;
+    use crate::thread::{Frame, Thread};
+    use std::cell::RefCell;
+
+    let mut vm = JVM::create_with_class_path("java/redefine");
+    let seen = Rc::new(RefCell::new(vec![]));
+    let recorder = Rc::clone(&seen);
+    vm.add_transformer(Box::new(
+        move |loader: LoaderId, class_name: &str, _: Option<&Rc<Class>>, _: &[u8]| {
+            recorder.borrow_mut().push((loader, class_name.to_owned()));
+            None
+        },
+    ));
+    // Greeting.value() returns 2 instead of 1, from the class file of another version
+    vm.add_transformer(Box::new(
+        |_: LoaderId, class_name: &str, _: Option<&Rc<Class>>, _: &[u8]| {
+            (class_name == "Greeting")
+                .then(|| read_binary_file(&"java/redefine/v2/Greeting.class".to_owned()).unwrap())
+        },
+    ));
+
+    let caller = vm.resolve_class("Caller").unwrap();
+    let mut thread = Thread::create();
+    let run = caller.method("run:()V").unwrap();
+    thread
+        .java_virtual_machine_stack
+        .push(Frame::create(&caller, &run));
+    interpret(&vm, &mut thread).unwrap();
+
+    assert_eq!(caller.static_value("result:I"), Some(Value::Int(2)));
+    assert_eq!(
+        *seen.borrow(),
+        [
+            (APPLICATION_LOADER, String::from("Caller")),
+            (APPLICATION_LOADER, String::from("Greeting"))
+        ]
+    );
+}
+
+#[test]
+fn test_transform_class_file_on_redefinition() {
+    use crate::binary::read_binary_file;
+    use crate::jvm::JVM;
+    use crate::redefinition::redefine_class;
+
+    let mut vm = JVM::create_with_class_path("java/redefine");
+    let greeting = vm.resolve_class("Greeting").unwrap();
+    // the transformer is registered after Greeting is loaded, and gets the class being redefined
+    vm.add_transformer(Box::new(
+        |_: LoaderId, _: &str, class_being_redefined: Option<&Rc<Class>>, _: &[u8]| {
+            class_being_redefined
+                .map(|_| read_binary_file(&"java/redefine/v2/Greeting.class".to_owned()).unwrap())
+        },
+    ));
+
+    let original = read_binary_file(&"java/redefine/Greeting.class".to_owned()).unwrap();
+    redefine_class(&vm, &greeting, &original).unwrap();
+
+    // iconst_2, ireturn
+    let method = greeting.method("value:()I").unwrap();
+    assert_eq!(method.get_code_attribute().code, [0x05, 0xac]);
+}
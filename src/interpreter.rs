@@ -1,33 +1,48 @@
+use crate::field::{get_static, put_static};
+use crate::instruction::Fields::{GetStatic, PutStatic};
 use crate::instruction::Invokes::InvokeStatic;
-use crate::instruction::Result::{Invoke, Return};
+use crate::instruction::Result::{Field, Invoke, LoadConstant, Return, Throw};
 use crate::instruction::{instruction, Returns};
-use crate::instruction_set::Instruction;
 use crate::invoke::{i_return, invoke_static, java_return};
-use crate::thread::{Frame, Thread};
-use crate::JVM;
+use crate::jvm::JVM;
+use crate::runtime_constant_pool::load_constant;
+use crate::thread::Thread;
+use crate::throwable::Throwable;
+use crate::value::Value;
 
 const MAX_LOOP_COUNT: i32 = 1_000_000;
 
-pub fn interpret(thread: &mut Thread) {
+/// run the thread until its stack gets empty.
+/// exception handlers are not looked up yet, then any throwable ends the thread.
+pub fn interpret(vm: &JVM, thread: &mut Thread) -> Result<(), Throwable> {
     let mut counter = 0;
-    while thread.java_virtual_machine_stack.len() >= 1 {
+    while !thread.java_virtual_machine_stack.is_empty() {
         if counter > MAX_LOOP_COUNT {
             panic!("[ERROR] MAX_LOOP_COUNT exceeded: Infinite loops are suspected.")
         }
-        counter = counter + 1;
+        counter += 1;
+        vm.poll_debugger();
 
         let frame = thread.java_virtual_machine_stack.last_mut().unwrap();
 
         match instruction(frame) {
             Return(ret) => match ret {
-                Returns::IReturn { val } => i_return(thread, val),
+                Returns::IReturn { val } => i_return(thread, Value::Int(val)),
+                Returns::LReturn { val } => i_return(thread, Value::Long(val)),
+                Returns::FReturn { val } => i_return(thread, Value::Float(val)),
+                Returns::DReturn { val } => i_return(thread, Value::Double(val)),
                 Returns::Return => java_return(thread),
             },
-            Invoke(invoke) => {
-                if let InvokeStatic { cp_index } = invoke {
-                    invoke_static(thread, cp_index)
-                }
-            }
+            Field(field) => match field {
+                GetStatic { cp_index } => get_static(vm, thread, cp_index)?,
+                PutStatic { cp_index } => put_static(vm, thread, cp_index)?,
+            },
+            Invoke(invoke) => match invoke {
+                InvokeStatic { cp_index } => invoke_static(vm, thread, cp_index)?,
+            },
+            LoadConstant { cp_index } => load_constant(vm, thread, cp_index)?,
+            Throw(throwable) => return Err(throwable),
         };
     }
+    Ok(())
 }
@@ -1,61 +1,95 @@
-use crate::class::MethodRef;
-use crate::class_loader::ClassLoader;
-use crate::cp_info::constant_pool_value_at;
-use crate::interpreter::interpret;
+use crate::class_file::ACC_STATIC;
+use crate::jvm::JVM;
+use crate::runtime_constant_pool::{resolve_constant_in, ResolvedConstant};
 use crate::thread::{Frame, Thread};
-use std::borrow::Borrow;
-use std::cell::RefCell;
+use crate::throwable::{Throwable, INCOMPATIBLE_CLASS_CHANGE_ERROR};
+use crate::value::Value;
 use std::rc::Rc;
-use std::str::Chars;
 
-fn parse_descriptor(descriptor: &String) -> String {
-    let arguments = String::from(descriptor.trim_start_matches("("));
-    let arguments: Vec<&str> = arguments.split(")").collect();
-    String::from(arguments[0])
+/// the number of parameters in a method descriptor, where `(IJ[Ljava/lang/String;)V` has 3
+fn parameter_count_of(descriptor: &str) -> usize {
+    let parameters = descriptor
+        .trim_start_matches('(')
+        .split_once(')')
+        .map_or("", |(parameters, _)| parameters);
+    let mut count = 0;
+    let mut rest = parameters.chars();
+    while let Some(c) = rest.next() {
+        match c {
+            // the dimensions of an array type come before its element type
+            '[' => continue,
+            'L' => {
+                rest.by_ref().find(|&c| c == ';');
+            }
+            _ => {}
+        }
+        count += 1;
+    }
+    count
 }
 
 pub fn invoke_static(
-    // class_loader: &ClassLoader,
+    vm: &JVM,
     thread: &mut Thread,
     methodref_cp_index: u16,
-) {
-    // 0. class lookup
-    // TODO: from ClassLoader
+) -> Result<(), Throwable> {
+    // 0. constantpool lookup, the class is loaded and the method is found on its first use
+    let current_frame = thread.java_virtual_machine_stack.last_mut().unwrap();
+    let context = Rc::clone(&current_frame.context);
+    let constant_pool = Rc::clone(&current_frame.constant_pool);
+    let ResolvedConstant::Method {
+        class,
+        method: method_info,
+        name_and_descriptor,
+    } = resolve_constant_in(vm, &context, &constant_pool, methodref_cp_index)?
+    else {
+        panic!(
+            "Error: #{} is not a method ref in {}",
+            methodref_cp_index, context.descriptor
+        )
+    };
+
+    if method_info.access_flags & ACC_STATIC == 0 {
+        return Err(Throwable::new(
+            INCOMPATIBLE_CLASS_CHANGE_ERROR,
+            format!(
+                "Expected static method {}.{}",
+                class.descriptor, name_and_descriptor
+            ),
+        ));
+    }
+
+    // 1. invokestatic is an active use of the class, run <clinit> before the method
+    vm.initialize_class(&class)?;
     let current_frame = thread.java_virtual_machine_stack.last_mut().unwrap();
-    let class = &current_frame.context;
-
-    // 1. constantpool lookup
-    let method_descriptor = class.constant_pool_value_at(methodref_cp_index);
-    let method_ref = MethodRef::parse_from(method_descriptor);
-
-    // 2. method lookup
-    let method_info = class
-        .methods
-        .get(method_ref.name_and_descriptor.as_str())
-        .expect(format!("Method Not Found: {}", method_ref.name_and_descriptor).as_str());
-
-    // 3. create new frame, push arguments as local_val
-    let mut invoked_frame = Frame::create(class, method_info);
-
-    // 4. pop arguments val from current frame operand_stack, set it to new frame's local val
-    let descriptor = method_ref.descriptor;
-    // TODO: parser, multiple args
-    let args = parse_descriptor(&descriptor);
-    if args.len() > 0 {
-        let arg = current_frame.operand_stack.pop().unwrap();
-        invoked_frame.local_variable[0] = arg;
+
+    // 2. create new frame, push arguments as local_val
+    let mut invoked_frame = Frame::create(&class, &method_info);
+
+    // 3. pop arguments val from current frame operand_stack, set it to new frame's local val.
+    // a long or double argument takes two local variables.
+    let (_, descriptor) = name_and_descriptor.split_once(':').unwrap();
+    let count = parameter_count_of(descriptor);
+    let args = current_frame
+        .operand_stack
+        .split_off(current_frame.operand_stack.len() - count);
+    let mut index = 0;
+    for arg in args {
+        invoked_frame.store(index, arg);
+        index += arg.category();
     }
 
-    // 5. push to java_stack
+    // 4. push to java_stack
     thread.java_virtual_machine_stack.push(invoked_frame);
 
     println!(
         "\n[DEBUG] -- >>>> invoke_static: {}.{}",
-        method_ref.class, method_ref.name_and_descriptor
-    )
+        class.descriptor, name_and_descriptor
+    );
+    Ok(())
 }
 
-pub fn i_return(thread: &mut Thread, returned_value: u64) {
+pub fn i_return(thread: &mut Thread, returned_value: Value) {
     println!("[DEBUG] -- <<<< i_return: value: {}", returned_value);
 
     // 1. pop current frame from stack
@@ -64,7 +98,7 @@ pub fn i_return(thread: &mut Thread, returned_value: u64) {
     // 2. get previous frame as invoker
     if let Some(invoker_stack) = thread.java_virtual_machine_stack.last_mut() {
         // 3. push returned value to invoker frame operand_stack
-        invoker_stack.operand_stack.push(returned_value);
+        invoker_stack.push(returned_value);
     }
 }
 
@@ -77,31 +111,47 @@ pub fn java_return(thread: &mut Thread) {
 
 #[test]
 pub fn test_invoke_static() {
-    use crate::instruction::frame_test::{dummy_class, dummy_method};
+    use crate::class::{Class, MethodRef};
+    use crate::class_loader::BOOTSTRAP_LOADER;
+    use crate::instruction::frame_test::{dummy_class_data, dummy_method};
+    use crate::runtime_constant_pool::Constant;
+    use std::sync::Arc;
 
-    // let class_loader = ClassLoader {};
+    let vm = JVM::create();
     let mut thread = Thread::create();
 
-    let mut class = dummy_class();
+    let mut data = dummy_class_data();
+    data.descriptor = String::from("Dummy");
     let full_method_name = String::from("Dummy.main:()I");
-    let cp = vec![String::from(""), full_method_name.clone()];
-    class.constant_pool = cp;
+    data.constant_pool = vec![String::from(""), full_method_name.clone()];
+    data.constants = Arc::new(vec![
+        Constant::Unusable,
+        Constant::MethodRef(MethodRef::parse_from(full_method_name)),
+    ]);
 
     // icnost_2, ireturn
     let method_name = String::from("main:()I");
     let code: Vec<u8> = vec![0x5, 0xac];
-    let method_info = Rc::new(dummy_method(code));
-    class.methods.insert(method_name, Rc::clone(&method_info));
-
-    let mut current_frame = Frame::create(&Rc::new(class), &Rc::clone(&method_info));
+    let mut method_info = dummy_method(code);
+    method_info.access_flags = ACC_STATIC;
+    let method_info = Arc::new(method_info);
+    data.methods.insert(method_name, Arc::clone(&method_info));
+
+    let class = Class::create(Arc::new(data), None, vec![], BOOTSTRAP_LOADER);
+    let class = vm.register_class(class).unwrap();
+    let current_frame = Frame::create(&class, &method_info);
     thread.java_virtual_machine_stack.push(current_frame);
     let mr_index: u16 = 1;
 
-    invoke_static(
-        // &class_loader,
-        &mut thread,
-        mr_index,
-    );
+    invoke_static(&vm, &mut thread, mr_index).unwrap();
 
     assert_eq!(thread.java_virtual_machine_stack.len(), 2);
 }
+
+#[test]
+fn test_parameter_count_of() {
+    assert_eq!(parameter_count_of("()V"), 0);
+    assert_eq!(parameter_count_of("(I)I"), 1);
+    assert_eq!(parameter_count_of("(IJ[Ljava/lang/String;)V"), 3);
+    assert_eq!(parameter_count_of("([[DLjava/util/List;Z)V"), 3);
+}
@@ -0,0 +1,91 @@
+use std::io;
+use std::path::Path;
+
+use crate::jvm::JAVA_FEATURE_VERSION;
+use crate::manifest::{Manifest, MANIFEST_NAME};
+use crate::zip::ZipArchive;
+
+const VERSIONS_DIRECTORY: &str = "META-INF/versions";
+/// versioned directories are only defined for Java 9 and later
+const MIN_VERSIONED_RELEASE: u16 = 9;
+
+/// JAR file, a zip archive with its manifest.
+/// For a Multi-Release jar, `META-INF/versions/<N>/` entries take precedence
+/// over the base entries for every N up to the runtime feature version.
+/// https://docs.oracle.com/en/java/javase/17/docs/specs/jar/jar.html#multi-release-jar-files
+#[derive(Debug)]
+pub struct JarFile {
+    pub archive: ZipArchive,
+    pub manifest: Manifest,
+    pub multi_release: bool,
+}
+
+impl JarFile {
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<JarFile> {
+        let archive = ZipArchive::open(path)?;
+        let manifest = match archive.read(MANIFEST_NAME) {
+            Ok(binary) => Manifest::parse_from(&binary),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Manifest::default(),
+            Err(e) => return Err(e),
+        };
+        let multi_release = manifest
+            .main_attribute("Multi-Release")
+            .is_some_and(|value| value.eq_ignore_ascii_case("true"));
+
+        Ok(JarFile {
+            archive,
+            manifest,
+            multi_release,
+        })
+    }
+
+    /// read an entry, either a class file or a resource, as seen by the runtime version.
+    pub fn read(&self, name: &str) -> io::Result<Vec<u8>> {
+        self.archive.read(&self.versioned_name_of(name))
+    }
+
+    /// the name of the entry which actually provides `name` for this runtime.
+    pub fn versioned_name_of(&self, name: &str) -> String {
+        // META-INF itself is never versioned
+        if !self.multi_release || name.starts_with("META-INF/") {
+            return name.to_owned();
+        }
+        (MIN_VERSIONED_RELEASE..=JAVA_FEATURE_VERSION)
+            .rev()
+            .map(|version| format!("{}/{}/{}", VERSIONS_DIRECTORY, version, name))
+            .find(|versioned_name| self.archive.contains(versioned_name))
+            .unwrap_or_else(|| name.to_owned())
+    }
+}
+
+/// java/multi-release.jar has `Multi-Release: true` and the following entries
+/// - `version.txt`: base, also in versions 9, 17 and 21
+/// - `only9.txt`: base, also in version 9
+/// - `com/acme/Main.class`: base, and `SimpleSum.class` as version 17
+#[test]
+fn test_read_multi_release() {
+    let jar = JarFile::open("java/multi-release.jar").unwrap();
+    assert!(jar.multi_release);
+
+    // 21 is newer than the runtime, then 17 is taken
+    assert_eq!(jar.read("version.txt").unwrap(), b"17");
+    assert_eq!(jar.read("only9.txt").unwrap(), b"9");
+    assert_eq!(jar.read("base.txt").unwrap(), b"base");
+    assert_eq!(
+        jar.versioned_name_of("com/acme/Main.class"),
+        "META-INF/versions/17/com/acme/Main.class"
+    );
+    assert_eq!(
+        jar.versioned_name_of("META-INF/MANIFEST.MF"),
+        "META-INF/MANIFEST.MF"
+    );
+}
+
+#[test]
+fn test_read_without_multi_release() {
+    let mut jar = JarFile::open("java/multi-release.jar").unwrap();
+    jar.multi_release = false;
+
+    assert_eq!(jar.read("version.txt").unwrap(), b"base");
+    assert_eq!(jar.read("only9.txt").unwrap(), b"base");
+}
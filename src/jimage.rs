@@ -0,0 +1,641 @@
+use std::fs::File;
+use std::io;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::class::package_name_of;
+use crate::inflate::inflate;
+
+/// The jimage container of the JDK run-time image, `lib/modules`.
+/// The index is kept in memory, and resources are read from the file on demand.
+/// Every value is in the byte order of the platform which built the image,
+/// which the magic number tells.
+/// https://github.com/openjdk/jdk17u/blob/master/src/java.base/share/classes/jdk/internal/jimage/BasicImageReader.java
+pub struct ImageFile {
+    pub path: PathBuf,
+    header: ImageHeader,
+    little_endian: bool,
+    /// the redirect and offsets tables, the locations and the strings, after the header
+    index: Vec<u8>,
+    content: ImageContent,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImageHeader {
+    pub magic: u32,
+    pub major_version: u16,
+    pub minor_version: u16,
+    pub flags: u32,
+    pub resource_count: u32,
+    pub table_length: u32,
+    pub locations_size: u32,
+    pub strings_size: u32,
+}
+
+enum ImageContent {
+    File(Mutex<File>),
+    #[cfg(test)]
+    Memory(Vec<u8>),
+}
+
+const MAJOR_VERSION: u16 = 1;
+const HEADER_SIZE: usize = 7 * 4;
+const HASH_MULTIPLIER: u32 = 0x0100_0193;
+
+const COMPRESSED_RESOURCE_MAGIC: u32 = 0xCAFE_FAFA;
+const COMPRESSED_HEADER_SIZE: usize = 29;
+
+/// kinds of the location attributes
+const ATTRIBUTE_END: usize = 0;
+const ATTRIBUTE_MODULE: usize = 1;
+const ATTRIBUTE_PARENT: usize = 2;
+const ATTRIBUTE_BASE: usize = 3;
+const ATTRIBUTE_EXTENSION: usize = 4;
+const ATTRIBUTE_OFFSET: usize = 5;
+const ATTRIBUTE_COMPRESSED: usize = 6;
+const ATTRIBUTE_UNCOMPRESSED: usize = 7;
+const ATTRIBUTE_COUNT: usize = 8;
+
+/// A resource in the image, such as `/java.base/java/lang/Object.class`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageLocation {
+    pub module: String,
+    pub parent: String,
+    pub base: String,
+    pub extension: String,
+    /// from the end of the index
+    pub content_offset: u64,
+    /// 0 if the resource isn't compressed
+    pub compressed_size: u64,
+    pub uncompressed_size: u64,
+}
+
+impl ImageLocation {
+    /// `/<module>/<parent>/<base>.<extension>`, without the empty parts
+    pub fn full_name(&self) -> String {
+        let mut name = String::new();
+        if !self.module.is_empty() {
+            name.push_str(&format!("/{}/", self.module));
+        }
+        if !self.parent.is_empty() {
+            name.push_str(&format!("{}/", self.parent));
+        }
+        name.push_str(&self.base);
+        if !self.extension.is_empty() {
+            name.push_str(&format!(".{}", self.extension));
+        }
+        name
+    }
+}
+
+impl ImageFile {
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<ImageFile> {
+        let mut file = File::open(path.as_ref())?;
+        let mut header = [0; HEADER_SIZE];
+        file.read_exact(&mut header)?;
+        let (header, little_endian) = parse_header(&header)?;
+
+        let mut index = vec![0; index_size(&header) - HEADER_SIZE];
+        file.read_exact(&mut index)?;
+        Ok(ImageFile {
+            path: path.as_ref().to_path_buf(),
+            header,
+            little_endian,
+            index,
+            content: ImageContent::File(Mutex::new(file)),
+        })
+    }
+
+    #[cfg(test)]
+    pub fn parse_from(path: PathBuf, data: Vec<u8>) -> io::Result<ImageFile> {
+        let (header, little_endian) = parse_header(&data)?;
+        let index_size = index_size(&header);
+        check_bounds(&data, HEADER_SIZE, index_size - HEADER_SIZE)?;
+        Ok(ImageFile {
+            path,
+            header,
+            little_endian,
+            index: data[HEADER_SIZE..index_size].to_vec(),
+            content: ImageContent::Memory(data[index_size..].to_vec()),
+        })
+    }
+
+    #[cfg(test)]
+    pub fn header(&self) -> &ImageHeader {
+        &self.header
+    }
+
+    /// find a resource by its full name through the perfect hash table
+    pub fn find_location(&self, name: &str) -> Option<ImageLocation> {
+        let length = self.header.table_length;
+        if length == 0 {
+            return None;
+        }
+        let redirect = self.u4_at(hash_code(name, HASH_MULTIPLIER) % length) as i32;
+        let index = match redirect {
+            0 => return None,
+            // the name is alone in its bucket
+            redirect if redirect < 0 => (-1 - redirect) as u32,
+            // the bucket is shared, and the redirect is the seed to hash with again
+            seed => hash_code(name, seed as u32) % length,
+        };
+        let location = self.location_at(self.u4_at(length + index))?;
+        (location.full_name() == name).then_some(location)
+    }
+
+    /// read a resource, such as `/java.base/java/lang/Object.class`
+    pub fn read(&self, name: &str) -> io::Result<Vec<u8>> {
+        let location = self.find_location(name).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{} is not found in {}", name, self.path.display()),
+            )
+        })?;
+        self.read_location(&location)
+    }
+
+    pub fn read_location(&self, location: &ImageLocation) -> io::Result<Vec<u8>> {
+        if location.compressed_size == 0 {
+            return self.read_content(location.content_offset, location.uncompressed_size);
+        }
+        let compressed = self.read_content(location.content_offset, location.compressed_size)?;
+        self.decompress(compressed)
+    }
+
+    /// the module containing the package of a class, from the `/packages/<package>` resource,
+    /// which lists `(is_empty, module name offset)` of each module having the package
+    pub fn module_of_package(&self, package: &str) -> Option<String> {
+        let name = format!("/packages/{}", package.replace('/', "."));
+        let location = self.find_location(&name)?;
+        let content = self.read_location(&location).ok()?;
+        let entries: Vec<(u32, u32)> = content
+            .chunks_exact(8)
+            .map(|entry| (self.u32_of(&entry[..4]), self.u32_of(&entry[4..])))
+            .collect();
+        entries
+            .iter()
+            .find(|(is_empty, _)| *is_empty == 0)
+            .or(entries.first())
+            .and_then(|&(_, offset)| self.string_at(offset))
+    }
+
+    /// read a class file or a resource by its `/` separated name, such as `java/lang/Object.class`,
+    /// from the module which has its package
+    pub fn read_resource(&self, file_name: &str) -> Option<Vec<u8>> {
+        let module = self.module_of_package(package_name_of(file_name))?;
+        self.read(&format!("/{}/{}", module, file_name)).ok()
+    }
+
+    /// a resource may be compressed more than once, with a header for each of them
+    fn decompress(&self, mut bytes: Vec<u8>) -> io::Result<Vec<u8>> {
+        while bytes.len() >= COMPRESSED_HEADER_SIZE
+            && self.u32_of(&bytes[..4]) == COMPRESSED_RESOURCE_MAGIC
+        {
+            let compressed_size = self.u64_of(&bytes[4..12]) as usize;
+            let uncompressed_size = self.u64_of(&bytes[12..20]) as usize;
+            let decompressor_offset = self.u32_of(&bytes[20..24]);
+            check_bounds(&bytes, COMPRESSED_HEADER_SIZE, compressed_size)?;
+            let compressed =
+                &bytes[COMPRESSED_HEADER_SIZE..COMPRESSED_HEADER_SIZE + compressed_size];
+
+            let decompressor = self.string_at(decompressor_offset).unwrap_or_default();
+            bytes = match decompressor.as_str() {
+                // java.util.zip.Inflater with the zlib header and the adler-32 trailer
+                "zip" if compressed.len() > 2 => inflate(&compressed[2..])?,
+                _ => {
+                    return Err(invalid_data(format!(
+                        "unsupported decompressor {:?}",
+                        decompressor
+                    )))
+                }
+            };
+            if bytes.len() != uncompressed_size {
+                return Err(invalid_data(format!(
+                    "decompressed to {} bytes, {} expected",
+                    bytes.len(),
+                    uncompressed_size
+                )));
+            }
+        }
+        Ok(bytes)
+    }
+
+    fn read_content(&self, offset: u64, size: u64) -> io::Result<Vec<u8>> {
+        match &self.content {
+            ImageContent::File(file) => {
+                let mut file = file.lock().unwrap();
+                let index_size = index_size(&self.header) as u64;
+                file.seek(SeekFrom::Start(index_size + offset))?;
+                let mut buffer = vec![0; size as usize];
+                file.read_exact(&mut buffer)?;
+                Ok(buffer)
+            }
+            #[cfg(test)]
+            ImageContent::Memory(data) => {
+                check_bounds(data, offset as usize, size as usize)?;
+                Ok(data[offset as usize..(offset + size) as usize].to_vec())
+            }
+        }
+    }
+
+    /// decode the attributes of a location. each one starts with a byte of its kind
+    /// in the upper 5 bits and its length - 1 in the lower 3 bits, then the value in big endian.
+    fn location_at(&self, offset: u32) -> Option<ImageLocation> {
+        let locations = self.locations();
+        let mut attributes = [0u64; ATTRIBUTE_COUNT];
+        let mut position = offset as usize;
+        while let Some(&byte) = locations.get(position) {
+            let kind = (byte >> 3) as usize;
+            if kind == ATTRIBUTE_END || kind >= ATTRIBUTE_COUNT {
+                break;
+            }
+            let length = (byte & 0x7) as usize + 1;
+            let value = locations.get(position + 1..position + 1 + length)?;
+            attributes[kind] = value
+                .iter()
+                .fold(0, |value, &byte| (value << 8) | byte as u64);
+            position += 1 + length;
+        }
+        let string = |kind: usize| self.string_at(attributes[kind] as u32);
+        Some(ImageLocation {
+            module: string(ATTRIBUTE_MODULE)?,
+            parent: string(ATTRIBUTE_PARENT)?,
+            base: string(ATTRIBUTE_BASE)?,
+            extension: string(ATTRIBUTE_EXTENSION)?,
+            content_offset: attributes[ATTRIBUTE_OFFSET],
+            compressed_size: attributes[ATTRIBUTE_COMPRESSED],
+            uncompressed_size: attributes[ATTRIBUTE_UNCOMPRESSED],
+        })
+    }
+
+    /// a NUL terminated modified UTF-8 string in the strings table
+    fn string_at(&self, offset: u32) -> Option<String> {
+        let strings = self.strings();
+        let bytes = strings.get(offset as usize..)?;
+        let end = bytes.iter().position(|&byte| byte == 0)?;
+        Some(String::from_utf8_lossy(&bytes[..end]).into_owned())
+    }
+
+    fn locations(&self) -> &[u8] {
+        let start = self.header.table_length as usize * 8;
+        &self.index[start..start + self.header.locations_size as usize]
+    }
+
+    fn strings(&self) -> &[u8] {
+        let start = self.header.table_length as usize * 8 + self.header.locations_size as usize;
+        &self.index[start..start + self.header.strings_size as usize]
+    }
+
+    /// the n-th entry of the redirect table, followed by the offsets table
+    fn u4_at(&self, index: u32) -> u32 {
+        let offset = index as usize * 4;
+        self.u32_of(&self.index[offset..offset + 4])
+    }
+
+    fn u32_of(&self, bytes: &[u8]) -> u32 {
+        let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+        if self.little_endian {
+            u32::from_le_bytes(bytes)
+        } else {
+            u32::from_be_bytes(bytes)
+        }
+    }
+
+    fn u64_of(&self, bytes: &[u8]) -> u64 {
+        let mut buffer = [0; 8];
+        buffer.copy_from_slice(&bytes[..8]);
+        if self.little_endian {
+            u64::from_le_bytes(buffer)
+        } else {
+            u64::from_be_bytes(buffer)
+        }
+    }
+}
+
+impl std::fmt::Debug for ImageFile {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("ImageFile")
+            .field("path", &self.path)
+            .field("header", &self.header)
+            .finish()
+    }
+}
+
+/// the header is 7 u4 values: magic, version, flags, resource count, table length,
+/// locations size and strings size
+fn parse_header(bytes: &[u8]) -> io::Result<(ImageHeader, bool)> {
+    check_bounds(bytes, 0, HEADER_SIZE)?;
+    let little_endian = match bytes[..4] {
+        [0xDA, 0xDA, 0xFE, 0xCA] => true,
+        [0xCA, 0xFE, 0xDA, 0xDA] => false,
+        _ => return Err(invalid_data(String::from("bad jimage magic"))),
+    };
+    let u4 = |index: usize| {
+        let value = [
+            bytes[index * 4],
+            bytes[index * 4 + 1],
+            bytes[index * 4 + 2],
+            bytes[index * 4 + 3],
+        ];
+        if little_endian {
+            u32::from_le_bytes(value)
+        } else {
+            u32::from_be_bytes(value)
+        }
+    };
+    let version = u4(1);
+    let header = ImageHeader {
+        magic: u4(0),
+        major_version: (version >> 16) as u16,
+        minor_version: version as u16,
+        flags: u4(2),
+        resource_count: u4(3),
+        table_length: u4(4),
+        locations_size: u4(5),
+        strings_size: u4(6),
+    };
+    if header.major_version != MAJOR_VERSION {
+        return Err(invalid_data(format!(
+            "unsupported jimage version {}.{}",
+            header.major_version, header.minor_version
+        )));
+    }
+    Ok((header, little_endian))
+}
+
+fn index_size(header: &ImageHeader) -> usize {
+    HEADER_SIZE
+        + header.table_length as usize * 8
+        + header.locations_size as usize
+        + header.strings_size as usize
+}
+
+/// FNV-1a like hash of the UTF-8 bytes, with 0x01000193 as the default seed.
+/// https://github.com/openjdk/jdk17u/blob/master/src/java.base/share/classes/jdk/internal/jimage/ImageStringsReader.java
+pub fn hash_code(name: &str, seed: u32) -> u32 {
+    name.bytes().fold(seed, |hash, byte| {
+        hash.wrapping_mul(HASH_MULTIPLIER) ^ byte as u32
+    }) & 0x7FFF_FFFF
+}
+
+fn check_bounds(data: &[u8], offset: usize, length: usize) -> io::Result<()> {
+    match offset.checked_add(length) {
+        Some(end) if end <= data.len() => Ok(()),
+        _ => Err(invalid_data(String::from("truncated jimage"))),
+    }
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+pub mod jimage_test {
+    use crate::jimage::{hash_code, HASH_MULTIPLIER};
+
+    const IMAGE_MAGIC: u32 = 0xCAFE_DADA;
+
+    /// build an image of `(full name, content, compressed)` resources, with `/packages` entries.
+    /// a compressed resource is wrapped in a zlib stream of stored deflate blocks.
+    pub fn image_of(resources: &[(&str, &[u8], bool)], little_endian: bool) -> Vec<u8> {
+        let u4 = |value: u32| {
+            if little_endian {
+                value.to_le_bytes()
+            } else {
+                value.to_be_bytes()
+            }
+        };
+        let mut strings = vec![0u8];
+        let mut string_offset = |string: &str| {
+            if string.is_empty() {
+                return 0;
+            }
+            let offset = strings.len() as u64;
+            strings.extend(string.as_bytes());
+            strings.push(0);
+            offset
+        };
+
+        let mut entries: Vec<(String, Vec<u8>, bool)> = resources
+            .iter()
+            .map(|&(name, bytes, compressed)| (name.to_owned(), bytes.to_vec(), compressed))
+            .collect();
+        for &(name, _, _) in resources {
+            let (module, path) = name[1..].split_once('/').unwrap();
+            let package = path.rsplit_once('/').map_or("", |(parent, _)| parent);
+            let package_entry = format!("/packages/{}", package.replace('/', "."));
+            if entries.iter().all(|(name, _, _)| *name != package_entry) {
+                let mut modules = u4(0).to_vec();
+                modules.extend(u4(string_offset(module) as u32));
+                entries.push((package_entry, modules, false));
+            }
+        }
+
+        let mut content = vec![];
+        let mut locations = vec![];
+        let mut location_offsets = vec![];
+        for (name, bytes, compressed) in &entries {
+            let (name, bytes, compressed) = (name.as_str(), bytes.as_slice(), *compressed);
+            let (module, path) = name[1..].split_once('/').unwrap();
+            let (parent, file) = path.rsplit_once('/').unwrap_or(("", path));
+            let (base, extension) = file.rsplit_once('.').unwrap_or((file, ""));
+
+            let stored = if compressed {
+                let mut resource = vec![];
+                resource.extend(u4(0xCAFE_FAFA));
+                let mut zlib = vec![0x78, 0x01, 0x01];
+                zlib.extend((bytes.len() as u16).to_le_bytes());
+                zlib.extend((!(bytes.len() as u16)).to_le_bytes());
+                zlib.extend(bytes);
+                zlib.extend([0; 4]);
+                let wide = |value: u64| {
+                    if little_endian {
+                        value.to_le_bytes()
+                    } else {
+                        value.to_be_bytes()
+                    }
+                };
+                resource.extend(wide(zlib.len() as u64));
+                resource.extend(wide(bytes.len() as u64));
+                resource.extend(u4(string_offset("zip") as u32));
+                resource.extend(u4(0));
+                resource.push(1);
+                resource.extend(zlib);
+                resource
+            } else {
+                bytes.to_vec()
+            };
+
+            let attributes = [
+                string_offset(module),
+                string_offset(parent),
+                string_offset(base),
+                string_offset(extension),
+                content.len() as u64,
+                if compressed { stored.len() as u64 } else { 0 },
+                bytes.len() as u64,
+            ];
+            location_offsets.push(locations.len() as u32);
+            for (kind, value) in attributes.into_iter().enumerate() {
+                if value == 0 {
+                    continue;
+                }
+                let value_bytes = value.to_be_bytes();
+                let length = 8 - value.leading_zeros() as usize / 8;
+                locations.push((((kind + 1) << 3) | (length - 1)) as u8);
+                locations.extend(&value_bytes[8 - length..]);
+            }
+            locations.push(0);
+            content.extend(stored);
+        }
+
+        // perfect hash, the buckets with more names first
+        let length = entries.len() as u32;
+        let mut buckets: Vec<Vec<usize>> = vec![vec![]; length as usize];
+        for (index, (name, _, _)) in entries.iter().enumerate() {
+            buckets[(hash_code(name, HASH_MULTIPLIER) % length) as usize].push(index);
+        }
+        let mut order: Vec<usize> = (0..length as usize).collect();
+        order.sort_by_key(|&bucket| std::cmp::Reverse(buckets[bucket].len()));
+        let mut redirect = vec![0i32; length as usize];
+        let mut offsets: Vec<Option<u32>> = vec![None; length as usize];
+        for bucket in order
+            .into_iter()
+            .filter(|&bucket| !buckets[bucket].is_empty())
+        {
+            let names = &buckets[bucket];
+            if names.len() == 1 {
+                let slot = offsets.iter().position(Option::is_none).unwrap();
+                offsets[slot] = Some(location_offsets[names[0]]);
+                redirect[bucket] = -1 - slot as i32;
+                continue;
+            }
+            let seed = (1..)
+                .find(|&seed| {
+                    let mut slots: Vec<usize> = names
+                        .iter()
+                        .map(|&index| (hash_code(&entries[index].0, seed) % length) as usize)
+                        .collect();
+                    slots.sort();
+                    slots.dedup();
+                    slots.len() == names.len() && slots.iter().all(|&slot| offsets[slot].is_none())
+                })
+                .unwrap();
+            for &index in names {
+                let slot = (hash_code(&entries[index].0, seed) % length) as usize;
+                offsets[slot] = Some(location_offsets[index]);
+            }
+            redirect[bucket] = seed as i32;
+        }
+
+        let mut image = vec![];
+        for value in [
+            IMAGE_MAGIC,
+            1 << 16,
+            0,
+            length,
+            length,
+            locations.len() as u32,
+            strings.len() as u32,
+        ] {
+            image.extend(u4(value));
+        }
+        for value in redirect {
+            image.extend(u4(value as u32));
+        }
+        for offset in offsets {
+            image.extend(u4(offset.unwrap_or(0)));
+        }
+        image.extend(locations);
+        image.extend(strings);
+        image.extend(content);
+        image
+    }
+}
+
+#[cfg(test)]
+fn test_image(little_endian: bool) -> ImageFile {
+    let resources: [(&str, &[u8], bool); 3] = [
+        (
+            "/java.base/java/lang/Object.class",
+            b"\xCA\xFE\xBA\xBEobject",
+            false,
+        ),
+        (
+            "/java.base/java/lang/String.class",
+            b"\xCA\xFE\xBA\xBEstring",
+            true,
+        ),
+        (
+            "/java.sql/java/sql/Driver.class",
+            b"\xCA\xFE\xBA\xBEdriver",
+            false,
+        ),
+    ];
+    let data = jimage_test::image_of(&resources, little_endian);
+    ImageFile::parse_from(PathBuf::from("modules"), data).unwrap()
+}
+
+#[test]
+fn test_find_location() {
+    for little_endian in [true, false] {
+        let image = test_image(little_endian);
+        assert_eq!(image.header().resource_count, 5);
+
+        let location = image
+            .find_location("/java.base/java/lang/Object.class")
+            .unwrap();
+        assert_eq!(location.module, "java.base");
+        assert_eq!(location.parent, "java/lang");
+        assert_eq!(location.base, "Object");
+        assert_eq!(location.extension, "class");
+        assert_eq!(
+            image.read_location(&location).unwrap(),
+            b"\xCA\xFE\xBA\xBEobject"
+        );
+
+        assert_eq!(
+            image.find_location("/java.base/java/lang/Thread.class"),
+            None
+        );
+        assert_eq!(
+            image
+                .read("/java.base/java/lang/Thread.class")
+                .unwrap_err()
+                .kind(),
+            io::ErrorKind::NotFound
+        );
+    }
+}
+
+#[test]
+fn test_read_compressed_resource() {
+    let image = test_image(true);
+
+    let location = image
+        .find_location("/java.base/java/lang/String.class")
+        .unwrap();
+
+    assert_ne!(location.compressed_size, 0);
+    assert_eq!(
+        image.read_location(&location).unwrap(),
+        b"\xCA\xFE\xBA\xBEstring"
+    );
+}
+
+#[test]
+fn test_read_resource_by_package() {
+    let image = test_image(false);
+
+    assert_eq!(
+        image.module_of_package("java/sql").as_deref(),
+        Some("java.sql")
+    );
+    assert_eq!(
+        image.read_resource("java/sql/Driver.class").unwrap(),
+        b"\xCA\xFE\xBA\xBEdriver"
+    );
+    assert!(image.read_resource("java/sql/Connection.class").is_none());
+    assert!(image.read_resource("com/acme/Main.class").is_none());
+}
@@ -1,43 +1,151 @@
 use std::cell::RefCell;
 use std::collections::HashMap;
+use std::fs;
+use std::io::{self, BufReader};
+use std::path::Path;
 use std::rc::Rc;
+use std::sync::Arc;
 
-use crate::class::Class;
+#[cfg(test)]
+use crate::binary::read_binary_file;
+use crate::class::{Class, InitState};
 use crate::class_attributes::MethodInfo;
-use crate::class_loader::ClassLoader;
+use crate::class_data::{parse_in_parallel, ClassData, ParsedClassFile};
+use crate::class_file::{ACC_PRIVATE, ACC_STATIC};
+use crate::class_loader::{
+    bootstrap_object_class, define_class_of, BootstrapClassLoader, ClassLoader, ClassPathLoader,
+    LoaderId, APPLICATION_LOADER, BOOTSTRAP_LOADER, JAVA_LANG_OBJECT,
+};
+#[cfg(test)]
+use crate::class_path::Resource;
+use crate::class_path::{ClassPath, DEFAULT_CLASS_PATH};
+use crate::debugger::Debugger;
+use crate::instrument::{transform_class_file, ClassFileTransformer};
 use crate::interpreter::interpret;
-use crate::main;
+use crate::launcher::LaunchOptions;
+use crate::loader_constraints::LoaderConstraints;
+use crate::module_graph::ModuleGraph;
+use crate::resolution::superinterfaces_of;
+use crate::runtime_constant_pool::class_names_in_descriptor;
+use crate::shared_archive::{
+    class_names_in_list, shared_paths_of, SharedArchive, SharedPath, SharingMode, SharingOptions,
+};
 use crate::thread::{Frame, Thread};
+use crate::throwable::{
+    Throwable, CLASS_CIRCULARITY_ERROR, EXCEPTION_IN_INITIALIZER_ERROR, LINKAGE_ERROR,
+    NO_CLASS_DEF_FOUND_ERROR,
+};
+#[cfg(test)]
+use crate::throwable::{
+    ARITHMETIC_EXCEPTION, CLASS_FORMAT_ERROR, CLASS_NOT_FOUND_EXCEPTION, ILLEGAL_ACCESS_ERROR,
+    UNSUPPORTED_CLASS_VERSION_ERROR, UNSUPPORTED_OPERATION_EXCEPTION,
+};
+#[cfg(test)]
+use crate::value::Value;
+
+/// the Java SE release this JVM implements
+pub const JAVA_FEATURE_VERSION: u16 = 17;
+
+pub const CLASS_INITIALIZER: &str = "<clinit>:()V";
 
 pub struct MethodArea {
-    class_area: HashMap<String, Rc<Class>>,
-    thread_area: HashMap<String, Rc<Thread>>,
+    /// classes by their defining loader and name
+    class_area: HashMap<(LoaderId, String), Rc<Class>>,
+    /// classes by the loaders initiating their loading, including the defining loader.
+    /// a loader which delegated to its parent finds the class here next time.
+    initiated_classes: HashMap<(LoaderId, String), Rc<Class>>,
+    loader_constraints: LoaderConstraints,
 }
 
 impl MethodArea {
     pub fn create() -> MethodArea {
         MethodArea {
             class_area: HashMap::new(),
-            thread_area: HashMap::new(),
+            initiated_classes: HashMap::new(),
+            loader_constraints: LoaderConstraints::default(),
         }
     }
-    pub fn register_class(&mut self, class: Class) -> Rc<Class> {
+    /// a loader defines a name only once. the class already defined is the error.
+    pub fn register_class(&mut self, class: Class) -> Result<Rc<Class>, Rc<Class>> {
+        let key = (class.defining_loader, class.descriptor.clone());
+        if let Some(defined) = self.class_area.get(&key) {
+            return Err(Rc::clone(defined));
+        }
         let class_ref = Rc::new(class);
-        let result = Rc::clone(&class_ref);
-        self.class_area
-            .insert(class_ref.descriptor.clone(), class_ref);
-        result
+        self.initiated_classes
+            .insert(key.clone(), Rc::clone(&class_ref));
+        self.class_area.insert(key, Rc::clone(&class_ref));
+        Ok(class_ref)
+    }
+    pub fn record_initiating_loader(&mut self, loader: LoaderId, class: &Rc<Class>) {
+        self.initiated_classes
+            .insert((loader, class.descriptor.clone()), Rc::clone(class));
+    }
+    /// the class loaded by `loader`, either defined by itself or by one of its parents
+    pub fn lookup_class(&self, loader: LoaderId, name: &str) -> Option<Rc<Class>> {
+        self.initiated_classes
+            .get(&(loader, name.to_owned()))
+            .map(Rc::clone)
     }
-    pub fn lookup_class(&mut self, name: String) -> Option<Rc<Class>> {
+    /// every class in the method area, by its defining loader and name
+    pub fn defined_classes(&self) -> Vec<Rc<Class>> {
+        let mut classes: Vec<_> = self.class_area.iter().collect();
+        classes.sort_by_key(|((loader, name), _)| (loader.0, name.as_str()));
+        classes
+            .into_iter()
+            .map(|(_, class)| Rc::clone(class))
+            .collect()
+    }
+
+    #[cfg(test)]
+    pub fn defined_class(&self, loader: LoaderId, name: &str) -> Option<Rc<Class>> {
         self.class_area
-            .get(name.as_str())
-            .map(|class_ref| Rc::clone(class_ref))
+            .get(&(loader, name.to_owned()))
+            .map(Rc::clone)
+    }
+    /// false if the loaders have already loaded different classes for the name
+    pub fn add_loader_constraint(
+        &mut self,
+        class_name: &str,
+        first: LoaderId,
+        second: LoaderId,
+    ) -> bool {
+        let initiated_classes = &self.initiated_classes;
+        self.loader_constraints
+            .add(class_name, first, second, |loader| {
+                initiated_classes
+                    .get(&(loader, class_name.to_owned()))
+                    .map(Rc::clone)
+            })
+    }
+    /// a loader constrained with `loader`, which has loaded another class than `class`
+    pub fn violated_loader_constraint(
+        &self,
+        class_name: &str,
+        loader: LoaderId,
+        class: Option<&Rc<Class>>,
+    ) -> Option<LoaderId> {
+        self.loader_constraints
+            .violated_by(class_name, loader, class, |other| {
+                self.lookup_class(other, class_name)
+            })
     }
 }
 
+#[allow(clippy::upper_case_acronyms)]
 pub struct JVM {
     method_area: RefCell<MethodArea>,
-    boot_loader: ClassLoader,
+    /// indexed by `LoaderId`. the bootstrap, platform and application loaders come first.
+    class_loaders: Vec<Box<dyn ClassLoader>>,
+    /// classes whose superclasses are being resolved, to detect circularity
+    classes_in_loading: RefCell<Vec<(LoaderId, String)>>,
+    module_graph: ModuleGraph,
+    /// classes of the built-in loaders defined without their class files, `-Xshare:auto`
+    shared_archive: Option<SharedArchive>,
+    /// hooks on the class file bytes before they are parsed
+    transformers: Vec<Box<dyn ClassFileTransformer>>,
+    /// commands of the user, run between instructions with `--debug-commands`
+    debugger: Option<Debugger>,
 }
 
 /// The first primitive JVM. A simple instruction interpreter.
@@ -62,61 +170,1634 @@ impl JVM {
     pub fn create() -> Self {
         JVM {
             method_area: RefCell::new(MethodArea::create()),
-            boot_loader: ClassLoader {},
+            class_loaders: vec![
+                Box::new(BootstrapClassLoader::create()),
+                Box::new(ClassPathLoader::platform()),
+                Box::new(ClassPathLoader::application(ClassPath::parse(
+                    DEFAULT_CLASS_PATH,
+                ))),
+            ],
+            classes_in_loading: RefCell::new(vec![]),
+            module_graph: ModuleGraph::default(),
+            shared_archive: None,
+            transformers: vec![],
+            debugger: None,
         }
     }
 
-    pub fn launch(&mut self, args: &[String]) {
+    #[cfg(test)]
+    pub fn create_with_class_path(class_path: &str) -> Self {
+        let mut vm = JVM::create();
+        vm.set_class_path(ClassPath::parse(class_path));
+        vm
+    }
+
+    #[cfg(test)]
+    fn set_class_path(&mut self, class_path: ClassPath) {
+        self.set_module_graph(class_path, ModuleGraph::default());
+    }
+
+    /// the resolved modules are defined to the application loader, along with the class path
+    pub fn set_module_graph(&mut self, class_path: ClassPath, module_graph: ModuleGraph) {
+        self.class_loaders[APPLICATION_LOADER.0] = Box::new(
+            ClassPathLoader::application(class_path).with_modules(module_graph.module_references()),
+        );
+        self.module_graph = module_graph;
+    }
+
+    /// the bootstrap loader loads the class library from the JDK, instead of the synthetic Object
+    pub fn set_jdk_home(&mut self, jdk_home: &Path) -> io::Result<()> {
+        self.class_loaders[BOOTSTRAP_LOADER.0] =
+            Box::new(BootstrapClassLoader::with_jdk_home(jdk_home)?);
+        Ok(())
+    }
+
+    pub fn module_graph(&self) -> &ModuleGraph {
+        &self.module_graph
+    }
+
+    #[cfg(test)]
+    pub fn shared_archive(&self) -> Option<&SharedArchive> {
+        self.shared_archive.as_ref()
+    }
+
+    /// register a transformer, as `Instrumentation.addTransformer`.
+    /// it sees the classes loaded or redefined from then on.
+    #[cfg(test)]
+    pub fn add_transformer(&mut self, transformer: Box<dyn ClassFileTransformer>) {
+        self.transformers.push(transformer);
+    }
+
+    /// the class file bytes returned by the registered transformers, `None` if left as is
+    pub fn transform_class_file(
+        &self,
+        loader: LoaderId,
+        class_name: &str,
+        class_being_redefined: Option<&Rc<Class>>,
+        class_file: &[u8],
+    ) -> Option<Vec<u8>> {
+        transform_class_file(
+            &self.transformers,
+            loader,
+            class_name,
+            class_being_redefined,
+            class_file,
+        )
+    }
+
+    /// take the commands of a debugger, such as `redefine`, while the program runs
+    pub fn attach_debugger(&mut self, debugger: Debugger) {
+        self.debugger = Some(debugger);
+    }
+
+    pub fn poll_debugger(&self) {
+        if let Some(debugger) = &self.debugger {
+            debugger.poll(self);
+        }
+    }
+
+    /// every class defined so far, as `Instrumentation.getAllLoadedClasses`
+    pub fn loaded_classes(&self) -> Vec<Rc<Class>> {
+        self.method_area.borrow().defined_classes()
+    }
+
+    /// register a user-defined class loader. its classes are kept apart from the ones
+    /// of the same name defined by any other loader. only a loader implemented in Rust can
+    /// be registered for now, see `ClassLoader`.
+    #[cfg(test)]
+    pub fn add_class_loader(&mut self, class_loader: Box<dyn ClassLoader>) -> LoaderId {
+        self.class_loaders.push(class_loader);
+        LoaderId(self.class_loaders.len() - 1)
+    }
+
+    pub fn class_loader(&self, loader: LoaderId) -> &dyn ClassLoader {
+        self.class_loaders[loader.0].as_ref()
+    }
+
+    /// `java [-cp <class path>] <main class>`, `java -jar <jar file>`
+    /// or `java -p <module path> -m <module>[/<main class>]`.
+    /// `java -Xshare:dump` writes the shared archive instead of running a main class.
+    /// returns the uncaught throwable, if the main thread ends with it.
+    pub fn launch(&mut self, args: &[String]) -> Result<(), Throwable> {
         println!("[DEBUG] -- {:?}", args);
 
-        let class = self.boot_loader.load_class(&args[0]);
+        let options = LaunchOptions::parse(args);
+        if let Some(jdk_home) = &options.jdk_home {
+            self.set_jdk_home(jdk_home).unwrap_or_else(|e| {
+                panic!(
+                    "Error: Unable to open the run-time image of {}\n Reason {}",
+                    jdk_home.display(),
+                    e
+                )
+            });
+        }
+        let main_module = options.main_module.as_deref();
+        let module_graph = ModuleGraph::resolve(&options.module_options, main_module)?;
+        // the class files of directories are read for them, so only when an archive is used
+        let shared_paths = match options.sharing {
+            SharingOptions {
+                mode: SharingMode::Dump | SharingMode::Auto,
+                archive_file: Some(_),
+                ..
+            } => shared_paths_of(
+                &options.class_path,
+                &module_graph,
+                options.jdk_home.as_deref(),
+            ),
+            _ => vec![],
+        };
+        if options.sharing.mode == SharingMode::Dump {
+            self.set_module_graph(options.class_path, module_graph);
+            return self.dump_shared_archive(&options.sharing, &shared_paths);
+        }
+        let main_class = match main_module {
+            Some(module) if options.main_class.is_empty() => module_graph
+                .descriptor(module)
+                .and_then(|descriptor| descriptor.main_class.clone())
+                .unwrap_or_else(|| {
+                    panic!(
+                        "Error: module {} does not have a ModuleMainClass attribute, use -m <module>/<main-class>",
+                        module
+                    )
+                }),
+            _ => options.main_class,
+        };
+        self.set_module_graph(options.class_path, module_graph);
+        if options.sharing.mode == SharingMode::Auto {
+            if let Some(archive_file) = &options.sharing.archive_file {
+                self.open_shared_archive(archive_file, &shared_paths);
+            }
+        }
+
+        if options.debug_commands {
+            self.attach_debugger(Debugger::attach(BufReader::new(io::stdin())));
+        }
+        if let Some(threads) = options.preload_threads {
+            for throwable in self.preload_class_path(threads) {
+                println!("[DEBUG] -- failed to preload: {}", throwable);
+            }
+        }
+
+        let class = self.resolve_class(&main_class)?;
+        if let Some(module) = main_module {
+            if class.module.as_deref() != Some(module) {
+                panic!(
+                    "Error: Could not find or load main class {} in module {}",
+                    main_class, module
+                );
+            }
+        }
+        self.initialize_class(&class)?;
 
-        self.invoke_main(class);
+        self.invoke_main(class)
     }
 
-    fn invoke_main(&mut self, class: Class) {
-        let class_ref = self.method_area.get_mut().register_class(class);
+    /// load every class on the class path of the application loader, for workloads reading
+    /// all of them anyway. the class files are parsed by `threads` workers first, then the
+    /// classes are defined on this thread in the order of the class path. the classes failing
+    /// to load, such as for a missing superclass or a class file in the directory of another
+    /// package, are returned rather than raised.
+    pub fn preload_class_path(&self, threads: usize) -> Vec<Throwable> {
+        let Some(class_path) = self.class_loader(APPLICATION_LOADER).class_path() else {
+            return vec![];
+        };
+        let class_names = class_path.class_names();
+        println!(
+            "[DEBUG] -- preload {} classes on {} threads",
+            class_names.len(),
+            threads
+        );
+        let parsed = parse_in_parallel(class_path, &class_names, threads);
+
+        let mut failed = vec![];
+        for (class_name, parsed) in class_names.iter().zip(parsed) {
+            let result = match parsed {
+                Some(Ok(parsed)) => self
+                    .load_class_by(APPLICATION_LOADER, class_name, Some(parsed))
+                    .map(|_| ()),
+                Some(Err(throwable)) => Err(throwable),
+                None => self.load_class(APPLICATION_LOADER, class_name).map(|_| ()),
+            };
+            failed.extend(result.err());
+        }
+        failed
+    }
+
+    /// load the classes of the class list, then archive every class of the built-in loaders.
+    /// a class which fails to load is left out of the archive, as HotSpot warns and goes on.
+    fn dump_shared_archive(
+        &self,
+        sharing: &SharingOptions,
+        shared_paths: &[SharedPath],
+    ) -> Result<(), Throwable> {
+        let (Some(class_list_file), Some(archive_file)) =
+            (&sharing.class_list_file, &sharing.archive_file)
+        else {
+            panic!(
+                "Error: -Xshare:dump requires -XX:SharedClassListFile and -XX:SharedArchiveFile"
+            );
+        };
+        let class_list = fs::read_to_string(class_list_file).unwrap_or_else(|e| {
+            panic!(
+                "Error: Unable to read the class list {}\n Reason {}",
+                class_list_file.display(),
+                e
+            )
+        });
+        for class_name in class_names_in_list(&class_list) {
+            if let Err(_throwable) = self.resolve_class(&class_name) {
+                println!(
+                    "[DEBUG] -- Preload Warning: Cannot find {}: {}",
+                    class_name, _throwable
+                );
+            }
+        }
+
+        let classes: Vec<Rc<Class>> = self
+            .method_area
+            .borrow()
+            .defined_classes()
+            .into_iter()
+            .filter(|class| class.defining_loader.0 <= APPLICATION_LOADER.0)
+            .collect();
+        SharedArchive::dump(archive_file, shared_paths, &classes).unwrap_or_else(|e| {
+            panic!(
+                "Error: Unable to write the shared archive {}\n Reason {}",
+                archive_file.display(),
+                e
+            )
+        });
+        println!(
+            "[DEBUG] -- dumped {} classes to {}",
+            classes.len(),
+            archive_file.display()
+        );
+        Ok(())
+    }
+
+    /// an archive which can't be used is skipped, and the classes are loaded from class files
+    fn open_shared_archive(&mut self, archive_file: &Path, shared_paths: &[SharedPath]) {
+        match SharedArchive::open(archive_file, shared_paths) {
+            Ok(archive) => {
+                println!(
+                    "[DEBUG] -- opened {} classes of the shared archive {}",
+                    archive.class_count(),
+                    archive_file.display()
+                );
+                self.shared_archive = Some(archive);
+            }
+            Err(_e) => println!(
+                "[DEBUG] -- skip the shared archive {}: {}",
+                archive_file.display(),
+                _e
+            ),
+        }
+    }
+
+    fn invoke_main(&mut self, class_ref: Rc<Class>) -> Result<(), Throwable> {
         let main_method = find_main(&class_ref);
 
         let mut thread = Thread::create();
 
-        let mut frame: Frame = Frame::create(&class_ref, &main_method);
+        let frame: Frame = Frame::create(&class_ref, &main_method);
         thread.java_virtual_machine_stack.push(frame);
 
-        interpret(&mut thread);
+        interpret(self, &mut thread)
+    }
+
+    /// the class becomes a member of the module of its package, which is the one in the
+    /// run-time image for the class library.
+    /// a second class of the same name and defining loader raises LinkageError.
+    pub fn register_class(&self, mut class: Class) -> Result<Rc<Class>, Throwable> {
+        let (loader, package) = (class.defining_loader, class.package_name());
+        class.module = self
+            .class_loader(loader)
+            .module_of_package(package)
+            .or_else(|| {
+                self.module_graph
+                    .module_of(loader, package)
+                    .map(str::to_owned)
+            });
+        self.method_area
+            .borrow_mut()
+            .register_class(class)
+            .map_err(|defined| self.duplicate_class_definition(loader, &defined.descriptor))
+    }
+
+    fn duplicate_class_definition(&self, loader: LoaderId, class_name: &str) -> Throwable {
+        Throwable::new(
+            LINKAGE_ERROR,
+            format!(
+                "loader '{}' attempted duplicate class definition for {}.",
+                self.class_loader(loader).name(),
+                class_name.replace('/', ".")
+            ),
+        )
+    }
+
+    /// load a class by the application loader, as `ClassLoader.getSystemClassLoader()`.
+    pub fn resolve_class(&self, class_name: &str) -> Result<Rc<Class>, Throwable> {
+        self.load_class(APPLICATION_LOADER, class_name)
+    }
+
+    /// `Class.forName(name, initialize, loader)`, where `name` is a binary name such as
+    /// `com.acme.Main`. a class not found by the loader raises ClassNotFoundException,
+    /// while an error loading the class found, such as a missing superclass, is raised as is.
+    /// https://docs.oracle.com/en/java/javase/17/docs/api/java.base/java/lang/Class.html#forName(java.lang.String,boolean,java.lang.ClassLoader)
+    #[cfg(test)]
+    pub fn for_name(
+        &self,
+        name: &str,
+        initialize: bool,
+        loader: LoaderId,
+    ) -> Result<Rc<Class>, Throwable> {
+        let not_found = || Throwable::new(CLASS_NOT_FOUND_EXCEPTION, name.to_owned());
+        if name.contains('/') {
+            return Err(not_found());
+        }
+        let class = self
+            .load_class_by(loader, &name.replace('.', "/"), None)?
+            .ok_or_else(not_found)?;
+        if initialize {
+            self.initialize_class(&class)?;
+        }
+        Ok(class)
+    }
+
+    /// `ClassLoader.getResource`, the first of `get_resources`
+    #[cfg(test)]
+    pub fn get_resource(&self, loader: LoaderId, name: &str) -> Option<Resource> {
+        self.get_resources(loader, name).into_iter().next()
+    }
+
+    /// `ClassLoader.getResourceAsStream`, the content of the resource
+    #[cfg(test)]
+    pub fn get_resource_as_stream(&self, loader: LoaderId, name: &str) -> Option<Vec<u8>> {
+        self.get_resource(loader, name)
+            .map(|resource| resource.bytes)
+    }
+
+    /// `ClassLoader.getResources`, the ones found by the parents come first
+    #[cfg(test)]
+    pub fn get_resources(&self, loader: LoaderId, name: &str) -> Vec<Resource> {
+        let class_loader = self.class_loader(loader);
+        let mut resources = match class_loader.parent() {
+            Some(parent) => self.get_resources(parent, name),
+            None => vec![],
+        };
+        resources.extend(class_loader.find_resources(name));
+        resources
+    }
+
+    /// resolve a class referenced from `referrer`, by the defining loader of `referrer`, JVMS 5.3.
+    pub fn resolve_class_from(
+        &self,
+        referrer: &Class,
+        class_name: &str,
+    ) -> Result<Rc<Class>, Throwable> {
+        self.load_class(referrer.defining_loader, class_name)
+    }
+
+    /// find a class loaded by `loader` from the method area, or load and register it on its first use.
+    pub fn load_class(&self, loader: LoaderId, class_name: &str) -> Result<Rc<Class>, Throwable> {
+        self.load_class_by(loader, class_name, None)?
+            .ok_or_else(|| Throwable::new(NO_CLASS_DEF_FOUND_ERROR, class_name.to_owned()))
+    }
+
+    /// parent first delegation. `None` if neither `loader` nor any of its parents find the class.
+    /// a binary name such as `com.acme.Main` is looked up by its internal form.
+    /// `parsed` is the class file `loader` itself finds, if it was parsed ahead.
+    fn load_class_by(
+        &self,
+        loader: LoaderId,
+        class_name: &str,
+        parsed: Option<ParsedClassFile>,
+    ) -> Result<Option<Rc<Class>>, Throwable> {
+        let class_name = &class_name.replace('.', "/");
+        if let Some(class) = self.method_area.borrow().lookup_class(loader, class_name) {
+            return Ok(Some(class));
+        }
+
+        let class_loader = self.class_loader(loader);
+        let parent_first = class_loader.is_parent_first(class_name);
+        let delegate = || match class_loader.parent() {
+            Some(parent) => self.load_class_by(parent, class_name, None),
+            None => Ok(None),
+        };
+
+        let mut class = if parent_first { delegate()? } else { None };
+        if class.is_none() {
+            class = self.define_found_class(loader, class_name, parsed)?;
+        }
+        if class.is_none() && !parent_first {
+            class = delegate()?;
+        }
+        let Some(class) = class else {
+            return Ok(None);
+        };
+
+        if class.defining_loader != loader {
+            self.check_loader_constraint(loader, class_name, Some(&class))?;
+        }
+        self.method_area
+            .borrow_mut()
+            .record_initiating_loader(loader, &class);
+        Ok(Some(class))
+    }
+
+    /// define the class found by `loader` itself, from the shared archive if it is there.
+    /// the archive is skipped while any transformer is registered, which must see the bytes.
+    fn define_found_class(
+        &self,
+        loader: LoaderId,
+        class_name: &str,
+        parsed: Option<ParsedClassFile>,
+    ) -> Result<Option<Rc<Class>>, Throwable> {
+        if let Some(archive) = self
+            .shared_archive
+            .as_ref()
+            .filter(|_| self.transformers.is_empty())
+        {
+            if archive.contains(loader, class_name) {
+                println!(
+                    "[DEBUG] -- load class from the shared archive: {} by {}",
+                    class_name,
+                    self.class_loader(loader).name()
+                );
+                let class = self.while_loading(loader, class_name, || {
+                    archive.define_class(loader, class_name, |name| self.load_class(loader, name))
+                })?;
+                self.check_loader_constraints_of(&class)?;
+                return self.register_class(class).map(Some);
+            }
+        }
+
+        if let Some(parsed) = parsed {
+            println!(
+                "[DEBUG] -- load class parsed ahead: {} by {}",
+                class_name,
+                self.class_loader(loader).name()
+            );
+            let class = self.link_class(loader, class_name, &parsed.binary, Some(parsed.data))?;
+            check_class_name(&class, class_name)?;
+            self.check_loader_constraints_of(&class)?;
+            return self.register_class(class).map(Some);
+        }
+
+        let Some(binary) = self.class_loader(loader).find_class(class_name)? else {
+            if loader == BOOTSTRAP_LOADER && class_name == JAVA_LANG_OBJECT {
+                return self.register_class(bootstrap_object_class()).map(Some);
+            }
+            return Ok(None);
+        };
+
+        println!(
+            "[DEBUG] -- load class on demand: {} by {}",
+            class_name,
+            self.class_loader(loader).name()
+        );
+        let class = self.link_class(loader, class_name, &binary, None)?;
+        check_class_name(&class, class_name)?;
+        self.check_loader_constraints_of(&class)?;
+        self.register_class(class).map(Some)
+    }
+
+    /// define a class from the bytes of its class file by the application loader,
+    /// as `ClassLoader.defineClass`. nothing is read from the class path for the class itself.
+    #[cfg(test)]
+    pub fn define_class(&self, class_name: &str, binary: &[u8]) -> Result<Rc<Class>, Throwable> {
+        self.define_class_by(APPLICATION_LOADER, class_name, binary)
+    }
+
+    /// define a class from bytes with `loader` as its defining loader.
+    /// `class_name` must be the name in the class file, and a loader can't define a name twice.
+    #[cfg(test)]
+    pub fn define_class_by(
+        &self,
+        loader: LoaderId,
+        class_name: &str,
+        binary: &[u8],
+    ) -> Result<Rc<Class>, Throwable> {
+        let class_name = class_name.replace('.', "/");
+        if self
+            .method_area
+            .borrow()
+            .lookup_class(loader, &class_name)
+            .is_some()
+        {
+            return Err(self.duplicate_class_definition(loader, &class_name));
+        }
+
+        println!(
+            "[DEBUG] -- define class from bytes: {} by {}",
+            class_name,
+            self.class_loader(loader).name()
+        );
+        let class = self.link_class(loader, &class_name, binary, None)?;
+        check_class_name(&class, &class_name)?;
+        self.check_loader_constraints_of(&class)?;
+        self.register_class(class)
+    }
+
+    /// parse the class file, after the transformers, and link it to its superclass and
+    /// superinterfaces, which are resolved by the same loader in turn.
+    /// `parsed` is the class data of `binary` if it was parsed ahead, used unless transformed.
+    /// a class reached again during its own loading raises ClassCircularityError.
+    fn link_class(
+        &self,
+        loader: LoaderId,
+        class_name: &str,
+        binary: &[u8],
+        parsed: Option<Arc<ClassData>>,
+    ) -> Result<Class, Throwable> {
+        let data = match (
+            self.transform_class_file(loader, class_name, None, binary),
+            parsed,
+        ) {
+            (None, Some(data)) => data,
+            (transformed, _) => ClassData::shared(transformed.as_deref().unwrap_or(binary))?,
+        };
+        self.while_loading(loader, class_name, || {
+            define_class_of(loader, data, |name| self.load_class(loader, name))
+        })
+    }
+
+    /// a class reached again while `define` resolves its super types is circular
+    fn while_loading<F>(
+        &self,
+        loader: LoaderId,
+        class_name: &str,
+        define: F,
+    ) -> Result<Class, Throwable>
+    where
+        F: FnOnce() -> Result<Class, Throwable>,
+    {
+        let key = (loader, class_name.to_owned());
+        if self.classes_in_loading.borrow().contains(&key) {
+            return Err(Throwable::new(
+                CLASS_CIRCULARITY_ERROR,
+                class_name.to_owned(),
+            ));
+        }
+
+        self.classes_in_loading.borrow_mut().push(key);
+        let class = define();
+        self.classes_in_loading.borrow_mut().pop();
+        class
+    }
+
+    /// `loader` is about to load `class`, or to define a new class if `None`.
+    /// the loaders constrained with it must not have loaded another class of the name.
+    fn check_loader_constraint(
+        &self,
+        loader: LoaderId,
+        class_name: &str,
+        class: Option<&Rc<Class>>,
+    ) -> Result<(), Throwable> {
+        let violated = self
+            .method_area
+            .borrow()
+            .violated_loader_constraint(class_name, loader, class);
+        match violated {
+            Some(other) => Err(Throwable::new(
+                LINKAGE_ERROR,
+                format!(
+                    "loader constraint violation: loader '{}' wants to load class {}. \
+                     A different class with the same name was previously loaded by '{}'.",
+                    self.class_loader(loader).name(),
+                    class_name,
+                    self.class_loader(other).name()
+                ),
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// a new class must satisfy the constraints on its name, and its methods overriding
+    /// the ones of another loader impose constraints on the types in their descriptors.
+    fn check_loader_constraints_of(&self, class: &Class) -> Result<(), Throwable> {
+        self.check_loader_constraint(class.defining_loader, &class.descriptor, None)?;
+
+        for (name_and_descriptor, method) in class.data().methods.iter() {
+            if name_and_descriptor.starts_with('<')
+                || method.access_flags & (ACC_PRIVATE | ACC_STATIC) != 0
+            {
+                continue;
+            }
+            for super_type in overridden_declaring_classes(class, name_and_descriptor) {
+                if let Err(type_name) = self.add_loader_constraints(
+                    name_and_descriptor,
+                    class.defining_loader,
+                    super_type.defining_loader,
+                ) {
+                    return Err(Throwable::new(
+                        LINKAGE_ERROR,
+                        format!(
+                            "loader constraint violation for class {}: when selecting overriding method {} \
+                             the class loader '{}' of the selected method's type {}, and the class loader '{}' \
+                             for its super type {} have different Class objects for the type {} used in the signature",
+                            class.descriptor,
+                            name_and_descriptor,
+                            self.class_loader(class.defining_loader).name(),
+                            class.descriptor,
+                            self.class_loader(super_type.defining_loader).name(),
+                            super_type.descriptor,
+                            type_name
+                        ),
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// a field or method of `declaring_class` referenced from `referrer` must mean the same
+    /// classes for the types in its descriptor, from the loaders of both classes.
+    pub fn check_member_loader_constraints(
+        &self,
+        referrer: &Class,
+        declaring_class: &Class,
+        kind: &str,
+        name_and_descriptor: &str,
+    ) -> Result<(), Throwable> {
+        self.add_loader_constraints(
+            name_and_descriptor,
+            referrer.defining_loader,
+            declaring_class.defining_loader,
+        )
+        .map_err(|type_name| {
+            Throwable::new(
+                LINKAGE_ERROR,
+                format!(
+                    "loader constraint violation: when resolving {} {}.{} the class loader '{}' of the \
+                     current class, {}, and the class loader '{}' for the {}'s defining class, {}, \
+                     have different Class objects for the type {} used in the signature",
+                    kind,
+                    declaring_class.descriptor,
+                    name_and_descriptor,
+                    self.class_loader(referrer.defining_loader).name(),
+                    referrer.descriptor,
+                    self.class_loader(declaring_class.defining_loader).name(),
+                    kind,
+                    declaring_class.descriptor,
+                    type_name
+                ),
+            )
+        })
+    }
+
+    /// impose `N^first = N^second` for each class name N in the descriptor, JVMS 5.3.4.
+    /// returns the name violating a constraint.
+    fn add_loader_constraints(
+        &self,
+        name_and_descriptor: &str,
+        first: LoaderId,
+        second: LoaderId,
+    ) -> Result<(), String> {
+        if first == second {
+            return Ok(());
+        }
+        let (_, descriptor) = name_and_descriptor
+            .split_once(':')
+            .unwrap_or_else(|| panic!("Invalid member: {}", name_and_descriptor));
+        let mut method_area = self.method_area.borrow_mut();
+        for class_name in class_names_in_descriptor(descriptor) {
+            if !method_area.add_loader_constraint(&class_name, first, second) {
+                return Err(class_name);
+            }
+        }
+        Ok(())
+    }
+
+    /// initialize the class on its first active use, JVMS 5.5.
+    /// the superclass, and superinterfaces declaring default methods, are initialized first.
+    /// a class being initialized is taken as initialized, since there is only one thread,
+    /// and a class whose initialization failed raises NoClassDefFoundError from then on.
+    pub fn initialize_class(&self, class: &Rc<Class>) -> Result<(), Throwable> {
+        let state = *class.init_state.borrow();
+        match state {
+            InitState::Initialized | InitState::InProgress => return Ok(()),
+            InitState::Erroneous => {
+                return Err(Throwable::new(
+                    NO_CLASS_DEF_FOUND_ERROR,
+                    format!(
+                        "Could not initialize class {}",
+                        class.descriptor.replace('/', ".")
+                    ),
+                ))
+            }
+            InitState::Uninitialized => {}
+        }
+        *class.init_state.borrow_mut() = InitState::InProgress;
+
+        if let Err(throwable) = self.initialize_super_types(class) {
+            *class.init_state.borrow_mut() = InitState::Erroneous;
+            return Err(throwable);
+        }
+
+        match self.run_class_initializer(class) {
+            Ok(()) => {
+                *class.init_state.borrow_mut() = InitState::Initialized;
+                Ok(())
+            }
+            Err(throwable) => {
+                *class.init_state.borrow_mut() = InitState::Erroneous;
+                if throwable.is_error() {
+                    Err(throwable)
+                } else {
+                    Err(Throwable::with_cause(
+                        EXCEPTION_IN_INITIALIZER_ERROR,
+                        String::new(),
+                        throwable,
+                    ))
+                }
+            }
+        }
+    }
+
+    /// an interface doesn't initialize its superinterfaces
+    fn initialize_super_types(&self, class: &Rc<Class>) -> Result<(), Throwable> {
+        if class.is_interface() {
+            return Ok(());
+        }
+        if let Some(super_class) = &class.super_class {
+            self.initialize_class(super_class)?;
+        }
+        for interface in &class.interfaces {
+            if interface.declares_default_methods() {
+                self.initialize_class(interface)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn run_class_initializer(&self, class: &Rc<Class>) -> Result<(), Throwable> {
+        let Some(clinit) = class.method(CLASS_INITIALIZER) else {
+            return Ok(());
+        };
+        println!("[DEBUG] -- initialize class: {}", class.descriptor);
+
+        let mut thread = Thread::create();
+        thread
+            .java_virtual_machine_stack
+            .push(Frame::create(class, &clinit));
+        interpret(self, &mut thread)
     }
 }
 
+/// the class file found or given for a name must declare the class of the name
+fn check_class_name(class: &Class, class_name: &str) -> Result<(), Throwable> {
+    if class.descriptor == class_name {
+        return Ok(());
+    }
+    Err(Throwable::new(
+        NO_CLASS_DEF_FOUND_ERROR,
+        format!("{} (wrong name: {})", class_name, class.descriptor),
+    ))
+}
+
+/// classes declaring the instance methods overridden by the method of `class`,
+/// the nearest one of the superclasses and the ones of the superinterfaces.
+fn overridden_declaring_classes(class: &Class, name_and_descriptor: &str) -> Vec<Rc<Class>> {
+    let overridable = |candidate: &Rc<Class>| {
+        candidate
+            .method(name_and_descriptor)
+            .is_some_and(|method| method.access_flags & (ACC_PRIVATE | ACC_STATIC) == 0)
+    };
+    let mut classes = vec![];
+    let mut current = class.super_class.as_ref();
+    while let Some(super_class) = current {
+        if overridable(super_class) {
+            classes.push(Rc::clone(super_class));
+            break;
+        }
+        current = super_class.super_class.as_ref();
+    }
+    for interface in &class.interfaces {
+        let mut interfaces = superinterfaces_of(interface);
+        interfaces.push(Rc::clone(interface));
+        classes.extend(interfaces.into_iter().filter(overridable));
+    }
+    classes
+}
+
 static MAIN_METHOD_NAME_DESCRIPTOR: &str = "main:([Ljava/lang/String;)V";
 /// fetch target method as main for now
 static STUB_MAIN_METHOD_NAME_DESCRIPTOR: &str = "main:()I";
 
-fn find_main(class: &Class) -> Rc<MethodInfo> {
+fn find_main(class: &Class) -> Arc<MethodInfo> {
     // TODO: keep backward compatibility by STUB_MAIN_METHOD_NAME_DESCRIPTOR. remove this later
-    let stub_method_ref = class.methods.get(STUB_MAIN_METHOD_NAME_DESCRIPTOR);
-    let main_method_ref = class.methods.get(MAIN_METHOD_NAME_DESCRIPTOR);
+    let stub_method_ref = class.method(STUB_MAIN_METHOD_NAME_DESCRIPTOR);
+    let main_method_ref = class.method(MAIN_METHOD_NAME_DESCRIPTOR);
 
-    let method_ref = stub_method_ref
+    stub_method_ref
         .or(main_method_ref)
-        .expect("Error: Can't Find Main Method. Please define Main Method as:\npublic static void main(String[] args)");
-
-    Rc::clone(method_ref)
+        .expect("Error: Can't Find Main Method. Please define Main Method as:\npublic static void main(String[] args)")
 }
 
 #[test]
 pub fn test_simplesum() {
     let mut vm = JVM::create();
-    vm.launch(&[String::from("./java/SimpleSum")]);
+    vm.launch(&[String::from("./java/SimpleSum")]).unwrap();
 }
 
 #[test]
 pub fn test_forloop() {
     let mut vm = JVM::create();
-    vm.launch(&[String::from("./java/ForLoop")]);
+    vm.launch(&[String::from("./java/ForLoop")]).unwrap();
 }
 
 #[test]
 pub fn test_fibonacci() {
     let mut vm = JVM::create();
-    vm.launch(&[String::from("./java/Fibonacci")]);
+    vm.launch(&[String::from("./java/Fibonacci")]).unwrap();
+}
+
+#[test]
+pub fn test_packaged_class_on_class_path() {
+    let mut vm = JVM::create();
+    vm.launch(&[
+        String::from("-cp"),
+        String::from("./java"),
+        String::from("com.acme.Main"),
+    ])
+    .unwrap();
+}
+
+#[test]
+pub fn test_class_in_jar_on_class_path() {
+    let mut vm = JVM::create();
+    vm.launch(&[
+        String::from("-cp"),
+        String::from("./java/acme.jar"),
+        String::from("com.acme.Main"),
+    ])
+    .unwrap();
+}
+
+#[test]
+pub fn test_executable_jar() {
+    let mut vm = JVM::create();
+    vm.launch(&[String::from("-jar"), String::from("./java/app/app.jar")])
+        .unwrap();
+}
+
+#[test]
+pub fn test_load_class_on_demand() {
+    let mut vm = JVM::create();
+    vm.launch(&[
+        String::from("-cp"),
+        String::from("./java"),
+        String::from("MultiClass"),
+    ])
+    .unwrap();
+
+    let method_area = vm.method_area.borrow();
+    assert!(method_area
+        .defined_class(APPLICATION_LOADER, "MultiClass")
+        .is_some());
+    assert!(method_area
+        .defined_class(APPLICATION_LOADER, "Calculator")
+        .is_some());
+}
+
+#[test]
+pub fn test_class_hierarchy() {
+    let vm = JVM::create_with_class_path("java");
+
+    let square = vm.resolve_class("Square").unwrap();
+    let rectangle = vm.resolve_class("Rectangle").unwrap();
+    let object = vm.resolve_class("java/lang/Object").unwrap();
+    let shape = vm.resolve_class("Shape").unwrap();
+    let polygon = vm.resolve_class("Polygon").unwrap();
+
+    assert!(Rc::ptr_eq(square.super_class.as_ref().unwrap(), &rectangle));
+    assert!(Rc::ptr_eq(&polygon.interfaces[0], &shape));
+    assert!(square.is_subclass_of(&rectangle));
+    assert!(square.is_subclass_of(&object));
+    assert!(!rectangle.is_subclass_of(&square));
+    assert!(!square.is_subclass_of(&square));
+
+    assert!(square.implements(&shape));
+    assert!(square.implements(&polygon));
+    assert!(polygon.implements(&shape));
+    assert!(!shape.implements(&polygon));
+}
+
+#[test]
+pub fn test_class_circularity_error() {
+    // CircularA extends CircularB, and CircularB extends CircularA.
+    // javac refuses them, so the class files are compiled with another superclass name
+    // of the same length, then the name is rewritten in the constant pool.
+    let vm = JVM::create_with_class_path("java");
+
+    let result = vm.resolve_class("CircularA");
+
+    assert!(result.unwrap_err().is(CLASS_CIRCULARITY_ERROR));
+    assert!(vm
+        .method_area
+        .borrow()
+        .lookup_class(APPLICATION_LOADER, "CircularA")
+        .is_none());
+    assert!(vm
+        .method_area
+        .borrow()
+        .lookup_class(APPLICATION_LOADER, "CircularB")
+        .is_none());
+}
+
+#[test]
+pub fn test_launch_main_module() {
+    // com.acme.app is a modular jar with ModuleMainClass, which requires an exploded module
+    let mut vm = JVM::create();
+    vm.launch(&[
+        String::from("--module-path"),
+        String::from("java/modules/mods"),
+        String::from("-m"),
+        String::from("com.acme.app"),
+    ])
+    .unwrap();
+
+    let main = vm
+        .method_area
+        .borrow()
+        .lookup_class(APPLICATION_LOADER, "com/acme/app/Main")
+        .unwrap();
+    assert_eq!(main.module.as_deref(), Some("com.acme.app"));
+    assert_eq!(main.static_value("greeting:I"), Some(Value::Int(42)));
+    let greeter = vm.resolve_class("com/acme/greeting/api/Greeter").unwrap();
+    assert_eq!(greeter.module.as_deref(), Some("com.acme.greeting"));
+}
+
+#[test]
+pub fn test_launch_main_class_in_module() {
+    let mut vm = JVM::create();
+    vm.launch(&[
+        String::from("-p"),
+        String::from("java/modules/mods"),
+        String::from("-m"),
+        String::from("com.acme.app/com.acme.app.Main"),
+    ])
+    .unwrap();
+}
+
+#[test]
+#[ignore = "needs a JDK 17 at JAVA_HOME, run by `cargo test -- --ignored`"]
+pub fn test_launch_with_jdk_home() {
+    let jdk_home = std::env::var_os("JAVA_HOME").expect("JAVA_HOME is not set");
+    let jdk_home = Path::new(&jdk_home);
+    let mut vm = JVM::create();
+    vm.launch(&[
+        String::from("--jdk-home"),
+        jdk_home.display().to_string(),
+        String::from("-cp"),
+        String::from("java"),
+        String::from("SimpleSum"),
+    ])
+    .unwrap();
+
+    let object = vm
+        .method_area
+        .borrow()
+        .defined_class(BOOTSTRAP_LOADER, JAVA_LANG_OBJECT)
+        .unwrap();
+    assert!(object.method("hashCode:()I").is_some());
+    let string = vm.resolve_class("java/lang/String").unwrap();
+    assert_eq!(string.defining_loader, BOOTSTRAP_LOADER);
+    assert_eq!(string.module.as_deref(), Some("java.base"));
+    let driver = vm.load_class(BOOTSTRAP_LOADER, "java/sql/Driver").unwrap();
+    assert_eq!(driver.module.as_deref(), Some("java.sql"));
+}
+
+#[test]
+pub fn test_module_of_class_in_runtime_image() {
+    use crate::jimage::jimage_test::image_of;
+
+    // a class library of one class outside java/, in a module other than java.base
+    let main = ClassPath::parse("java/acme.jar")
+        .find_class("com/acme/Main")
+        .unwrap()
+        .unwrap();
+    let image = image_of(&[("/jdk.acme/com/acme/Main.class", &main, false)], true);
+    let jdk_home = std::env::temp_dir().join(format!("jdk-home-{}", std::process::id()));
+    fs::create_dir_all(jdk_home.join("lib")).unwrap();
+    fs::write(jdk_home.join(crate::class_loader::JDK_MODULES_IMAGE), image).unwrap();
+    let mut vm = JVM::create();
+    vm.set_jdk_home(&jdk_home).unwrap();
+
+    let class = vm.load_class(BOOTSTRAP_LOADER, "com/acme/Main").unwrap();
+    assert_eq!(class.module.as_deref(), Some("jdk.acme"));
+    // not in the image, then defined by the VM in java.base
+    let object = vm.load_class(BOOTSTRAP_LOADER, JAVA_LANG_OBJECT).unwrap();
+    assert_eq!(object.module.as_deref(), Some("java.base"));
+
+    fs::remove_dir_all(jdk_home).unwrap();
+}
+
+#[test]
+pub fn test_static_field_initialized_before_main() {
+    let mut vm = JVM::create();
+    vm.launch(&[
+        String::from("-cp"),
+        String::from("./java"),
+        String::from("StaticCounter"),
+    ])
+    .unwrap();
+
+    // `count` is 1 by <clinit>, then incremented by main
+    let class = vm
+        .method_area
+        .borrow()
+        .lookup_class(APPLICATION_LOADER, "StaticCounter")
+        .unwrap();
+    assert_eq!(*class.init_state.borrow(), InitState::Initialized);
+    assert_eq!(class.static_value("count:I"), Some(Value::Int(2)));
+}
+
+#[test]
+pub fn test_static_constant_values() {
+    let vm = JVM::create_with_class_path("java");
+    let class = vm.resolve_class("StaticConstants").unwrap();
+
+    // set before <clinit>, which has no putstatic for them
+    assert_eq!(class.static_value("MAX:I"), Some(Value::Int(100)));
+    vm.initialize_class(&class).unwrap();
+    assert_eq!(class.static_value("MAX:I"), Some(Value::Int(100)));
+    assert_eq!(
+        class.static_value("BIG:J"),
+        Some(Value::Long(1234567890123))
+    );
+    assert_eq!(class.static_value("HALF:F"), Some(Value::Float(0.5)));
+    assert_eq!(class.static_value("RATE:D"), Some(Value::Double(1.25)));
+    assert_eq!(class.static_value("ENABLED:Z"), Some(Value::Int(1)));
+    assert_eq!(class.static_value("counter:I"), Some(Value::Int(101)));
+    // a String constant needs a heap, then it has no static value
+    assert_eq!(class.static_value("NAME:Ljava/lang/String;"), None);
+}
+
+#[test]
+pub fn test_load_constants_in_initializer() {
+    let vm = JVM::create_with_class_path("java");
+    let class = vm.resolve_class("LoadConstants").unwrap();
+
+    // <clinit> uses ldc and ldc2_w, see LoadConstants.jvm
+    vm.initialize_class(&class).unwrap();
+
+    assert_eq!(class.static_value("large:I"), Some(Value::Int(100000)));
+    assert_eq!(
+        class.static_value("big:J"),
+        Some(Value::Long(1234567890123))
+    );
+    assert_eq!(class.static_value("quarter:F"), Some(Value::Float(0.25)));
+    assert_eq!(class.static_value("rate:D"), Some(Value::Double(1.75)));
+}
+
+#[test]
+pub fn test_load_string_constant_unsupported() {
+    let vm = JVM::create_with_class_path("java");
+    let class = vm.resolve_class("LoadString").unwrap();
+
+    let throwable = vm.initialize_class(&class).unwrap_err();
+
+    assert_eq!(throwable.class_name, EXCEPTION_IN_INITIALIZER_ERROR);
+    assert_eq!(
+        throwable.cause,
+        Some(Box::new(Throwable::new(
+            UNSUPPORTED_OPERATION_EXCEPTION,
+            String::from("ldc of the String \"hello\" needs a heap, which isn't supported yet")
+        )))
+    );
+}
+
+#[test]
+pub fn test_super_types_initialized_first() {
+    let vm = JVM::create_with_class_path("java");
+    let child = vm.resolve_class("InitChild").unwrap();
+
+    vm.initialize_class(&child).unwrap();
+
+    let log = vm.resolve_class("InitLog").unwrap();
+    assert_eq!(log.static_value("parentBeforeChild:I"), Some(Value::Int(1)));
+    assert_eq!(
+        log.static_value("interfaceInitialized:I"),
+        Some(Value::Int(1))
+    );
+    let interface = vm.resolve_class("InitDefault").unwrap();
+    assert_eq!(interface.static_value("VALUE:I"), Some(Value::Int(1)));
+}
+
+#[test]
+pub fn test_erroneous_class_initialization() {
+    // BrokenInit calls Missing.compute() in <clinit>, and Missing.class is not in java/ on purpose
+    let vm = JVM::create_with_class_path("java");
+    let class = vm.resolve_class("BrokenInit").unwrap();
+
+    let first = vm.initialize_class(&class).unwrap_err();
+    let second = vm.initialize_class(&class).unwrap_err();
+
+    assert_eq!(
+        first,
+        Throwable::new(NO_CLASS_DEF_FOUND_ERROR, String::from("Missing"))
+    );
+    assert_eq!(
+        second,
+        Throwable::new(
+            NO_CLASS_DEF_FOUND_ERROR,
+            String::from("Could not initialize class BrokenInit")
+        )
+    );
+    assert_eq!(*class.init_state.borrow(), InitState::Erroneous);
+}
+
+#[test]
+pub fn test_int_arithmetic() {
+    let mut vm = JVM::create();
+    vm.launch(&[String::from("./java/IntArithmetic")]).unwrap();
+
+    let class = vm
+        .method_area
+        .borrow()
+        .lookup_class(APPLICATION_LOADER, "IntArithmetic")
+        .unwrap();
+    let expected = [
+        ("sum:I", -4),
+        ("difference:I", 10),
+        ("product:I", 2100),
+        ("quotient:I", -2),
+        ("remainder:I", -1),
+        ("negated:I", 7),
+        ("shiftedLeft:I", -14),
+        ("shiftedRight:I", -4),
+        ("unsignedShifted:I", 15),
+        ("and:I", 1),
+        ("or:I", -5),
+        ("xor:I", -6),
+        ("overflow:I", i32::MIN),
+        ("minQuotient:I", i32::MIN),
+        ("minRemainder:I", 0),
+        ("counter:I", 3),
+    ];
+    for (field, value) in expected {
+        assert_eq!(
+            class.static_value(field),
+            Some(Value::Int(value)),
+            "{}",
+            field
+        );
+    }
+}
+
+#[test]
+pub fn test_long_arithmetic() {
+    let mut vm = JVM::create();
+    vm.launch(&[String::from("./java/LongArithmetic")]).unwrap();
+
+    let class = vm
+        .method_area
+        .borrow()
+        .lookup_class(APPLICATION_LOADER, "LongArithmetic")
+        .unwrap();
+    let expected = [
+        ("sum:J", -6_999_999_997),
+        ("difference:J", 7_000_000_003),
+        ("product:J", -21_000_000_000),
+        ("quotient:J", -2_333_333_333),
+        ("remainder:J", -1),
+        ("negated:J", 7_000_000_000),
+        ("shiftedLeft:J", -14_000_000_000),
+        ("shiftedRight:J", -3_500_000_000),
+        ("unsignedShifted:J", 15),
+        ("and:J", 0),
+        ("or:J", -6_999_999_997),
+        ("xor:J", -6_999_999_997),
+        ("overflow:J", i64::MIN),
+        ("minQuotient:J", i64::MIN),
+        ("minRemainder:J", 0),
+        ("mixed:J", -6_999_999_996),
+    ];
+    for (field, value) in expected {
+        assert_eq!(
+            class.static_value(field),
+            Some(Value::Long(value)),
+            "{}",
+            field
+        );
+    }
+}
+
+#[test]
+pub fn test_float_arithmetic() {
+    let mut vm = JVM::create();
+    vm.launch(&[String::from("./java/FloatArithmetic")])
+        .unwrap();
+
+    let class = vm
+        .method_area
+        .borrow()
+        .lookup_class(APPLICATION_LOADER, "FloatArithmetic")
+        .unwrap();
+    let floats = [
+        ("floatSum:F", 5.5f32),
+        ("floatDifference:F", -9.5),
+        ("floatQuotient:F", -3.75),
+        ("floatRemainder:F", 1.5),
+        ("floatNegated:F", -7.5),
+        ("floatScaled:F", 16.0),
+    ];
+    for (field, value) in floats {
+        let actual = class.static_value(field).unwrap().as_float();
+        assert_eq!(actual.to_bits(), value.to_bits(), "{}", field);
+    }
+    let doubles = [
+        ("sum:D", -5.5f64),
+        ("difference:D", -9.5),
+        ("product:D", -15.0),
+        ("quotient:D", -3.75),
+        ("remainder:D", -1.5),
+        ("negated:D", 7.5),
+        ("negativeZero:D", -0.0),
+        ("infinity:D", f64::INFINITY),
+        ("scaled:D", -16.0),
+    ];
+    for (field, value) in doubles {
+        let actual = class.static_value(field).unwrap().as_double();
+        assert_eq!(actual.to_bits(), value.to_bits(), "{}", field);
+    }
+    // the sign and payload of a NaN depend on the hardware
+    assert!(class
+        .static_value("floatNaN:F")
+        .unwrap()
+        .as_float()
+        .is_nan());
+    assert!(class.static_value("nan:D").unwrap().as_double().is_nan());
+}
+
+#[test]
+pub fn test_put_final_static() {
+    let vm = JVM::create_with_class_path("java");
+
+    // set by its own <clinit>
+    let class = vm.resolve_class("FinalStatic").unwrap();
+    vm.initialize_class(&class).unwrap();
+    assert_eq!(class.static_value("LIMIT:I"), Some(Value::Int(100)));
+
+    // FinalWriter was compiled against a LIMIT which was not final, see FinalWriter.java
+    let writer = vm.resolve_class("FinalWriter").unwrap();
+    let throwable = vm.initialize_class(&writer).unwrap_err();
+
+    // an error is thrown as is, rather than wrapped in ExceptionInInitializerError
+    assert_eq!(
+        throwable,
+        Throwable::new(
+            ILLEGAL_ACCESS_ERROR,
+            String::from(
+                "Update to static final field FinalStatic.LIMIT:I attempted from a different class \
+                 (FinalWriter) than the field's declaring class"
+            )
+        )
+    );
+    assert_eq!(class.static_value("LIMIT:I"), Some(Value::Int(100)));
+}
+
+#[test]
+pub fn test_arithmetic_exception_in_initializer() {
+    // DivideInInit divides by a static field of 0 in <clinit>
+    let vm = JVM::create_with_class_path("java");
+    let class = vm.resolve_class("DivideInInit").unwrap();
+
+    let throwable = vm.initialize_class(&class).unwrap_err();
+
+    assert_eq!(throwable.class_name, EXCEPTION_IN_INITIALIZER_ERROR);
+    assert_eq!(
+        throwable.cause,
+        Some(Box::new(Throwable::new(
+            ARITHMETIC_EXCEPTION,
+            String::from("/ by zero")
+        )))
+    );
+    assert_eq!(*class.init_state.borrow(), InitState::Erroneous);
+}
+
+#[test]
+pub fn test_parent_first_delegation() {
+    let vm = JVM::create_with_class_path("java");
+
+    let square = vm.resolve_class("Square").unwrap();
+    let object = square
+        .super_class
+        .as_ref()
+        .unwrap()
+        .super_class
+        .as_ref()
+        .unwrap();
+
+    // java/lang/Object is defined by the bootstrap loader, even if asked to the application loader
+    assert_eq!(square.defining_loader, APPLICATION_LOADER);
+    assert_eq!(object.defining_loader, BOOTSTRAP_LOADER);
+    let method_area = vm.method_area.borrow();
+    assert!(method_area
+        .defined_class(APPLICATION_LOADER, JAVA_LANG_OBJECT)
+        .is_none());
+    assert!(method_area
+        .lookup_class(APPLICATION_LOADER, JAVA_LANG_OBJECT)
+        .is_some());
+}
+
+#[test]
+pub fn test_isolated_class_loaders() {
+    // java/plugin/a/Plugin and java/plugin/b/Plugin have the same name, VERSION is 1 and 2.
+    // neither is on the class path of the application loader, which is their parent.
+    let mut vm = JVM::create_with_class_path("java");
+    let loader_a = vm.add_class_loader(Box::new(ClassPathLoader::create(
+        "plugin-a",
+        APPLICATION_LOADER,
+        ClassPath::parse("java/plugin/a"),
+    )));
+    let loader_b = vm.add_class_loader(Box::new(ClassPathLoader::create(
+        "plugin-b",
+        APPLICATION_LOADER,
+        ClassPath::parse("java/plugin/b"),
+    )));
+
+    let plugin_a = vm.load_class(loader_a, "Plugin").unwrap();
+    let plugin_b = vm.load_class(loader_b, "Plugin").unwrap();
+    vm.initialize_class(&plugin_a).unwrap();
+    vm.initialize_class(&plugin_b).unwrap();
+
+    assert!(!Rc::ptr_eq(&plugin_a, &plugin_b));
+    assert_eq!(plugin_a.static_value("VERSION:I"), Some(Value::Int(1)));
+    assert_eq!(plugin_b.static_value("VERSION:I"), Some(Value::Int(2)));
+    assert!(Rc::ptr_eq(
+        plugin_a.super_class.as_ref().unwrap(),
+        plugin_b.super_class.as_ref().unwrap()
+    ));
+    assert!(vm
+        .resolve_class("Plugin")
+        .unwrap_err()
+        .is(NO_CLASS_DEF_FOUND_ERROR));
+}
+
+#[test]
+pub fn test_define_class_from_bytes() {
+    // Calculator is not on the class path, only its bytes are given
+    let vm = JVM::create_with_class_path("java/plugin");
+    let binary = read_binary_file(&"java/Calculator.class".to_owned()).unwrap();
+
+    let class = vm.define_class("Calculator", &binary).unwrap();
+
+    assert_eq!(class.defining_loader, APPLICATION_LOADER);
+    assert!(Rc::ptr_eq(&vm.resolve_class("Calculator").unwrap(), &class));
+    assert!(vm
+        .define_class("Calculator", &binary)
+        .unwrap_err()
+        .is(LINKAGE_ERROR));
+}
+
+#[test]
+pub fn test_define_class_with_wrong_name() {
+    let vm = JVM::create_with_class_path("java/plugin");
+    let binary = read_binary_file(&"java/Calculator.class".to_owned()).unwrap();
+
+    let result = vm.define_class("com.acme.Calculator", &binary);
+
+    assert_eq!(
+        result.unwrap_err().message,
+        "com/acme/Calculator (wrong name: Calculator)"
+    );
+    assert!(vm.resolve_class("Calculator").is_err());
+}
+
+#[test]
+pub fn test_load_class_by_binary_name() {
+    let vm = JVM::create_with_class_path("java/app/lib/acme.jar");
+
+    let dotted = vm.resolve_class("com.acme.Main").unwrap();
+    let internal = vm.resolve_class("com/acme/Main").unwrap();
+
+    assert!(Rc::ptr_eq(&dotted, &internal));
+    assert_eq!(vm.loaded_classes().len(), 2);
+}
+
+#[test]
+pub fn test_class_file_with_wrong_name() {
+    // java/redefine/added/Greeting.class declares Greeting, not added/Greeting
+    let vm = JVM::create_with_class_path("java/redefine");
+
+    let result = vm.resolve_class("added/Greeting").unwrap_err();
+
+    assert_eq!(
+        result,
+        Throwable::new(
+            NO_CLASS_DEF_FOUND_ERROR,
+            String::from("added/Greeting (wrong name: Greeting)")
+        )
+    );
+    assert!(vm
+        .method_area
+        .borrow()
+        .defined_class(APPLICATION_LOADER, "Greeting")
+        .is_none());
+}
+
+#[test]
+pub fn test_register_class_twice() {
+    use crate::class_loader::bootstrap_object_class;
+
+    let vm = JVM::create();
+    let object = vm.register_class(bootstrap_object_class()).unwrap();
+
+    let result = vm.register_class(bootstrap_object_class()).unwrap_err();
+
+    assert!(result.is(LINKAGE_ERROR));
+    let defined = vm
+        .method_area
+        .borrow()
+        .defined_class(BOOTSTRAP_LOADER, JAVA_LANG_OBJECT)
+        .unwrap();
+    assert!(Rc::ptr_eq(&defined, &object));
+}
+
+#[test]
+pub fn test_define_malformed_class() {
+    let vm = JVM::create_with_class_path("java/plugin");
+    let binary = read_binary_file(&"java/Calculator.class".to_owned()).unwrap();
+
+    let truncated = vm.define_class("Calculator", &binary[..5]).unwrap_err();
+    assert_eq!(
+        truncated,
+        Throwable::new(CLASS_FORMAT_ERROR, String::from("Truncated class file"))
+    );
+
+    let mut later = binary.clone();
+    later[7] = 62;
+    let result = vm.define_class("Calculator", &later).unwrap_err();
+    assert!(result.is(UNSUPPORTED_CLASS_VERSION_ERROR));
+
+    // nothing is defined by the failures
+    assert!(vm.define_class("Calculator", &binary).is_ok());
+}
+
+#[test]
+pub fn test_define_class_by_user_loader() {
+    let mut vm = JVM::create_with_class_path("java/plugin");
+    let loader = vm.add_class_loader(Box::new(ClassPathLoader::create(
+        "generated",
+        APPLICATION_LOADER,
+        ClassPath::from_paths(vec![]),
+    )));
+    let binary = read_binary_file(&"java/plugin/b/Plugin.class".to_owned()).unwrap();
+
+    let class = vm.define_class_by(loader, "Plugin", &binary).unwrap();
+
+    assert_eq!(class.defining_loader, loader);
+    assert!(Rc::ptr_eq(
+        &vm.load_class(loader, "Plugin").unwrap(),
+        &class
+    ));
+    assert!(vm.resolve_class("Plugin").is_err());
+}
+
+#[test]
+pub fn test_for_name() {
+    let vm = JVM::create_with_class_path("java");
+
+    let class = vm
+        .for_name("StaticCounter", false, APPLICATION_LOADER)
+        .unwrap();
+    assert_eq!(*class.init_state.borrow(), InitState::Uninitialized);
+    let class = vm
+        .for_name("StaticCounter", true, APPLICATION_LOADER)
+        .unwrap();
+    assert_eq!(class.static_value("count:I"), Some(Value::Int(1)));
+
+    for name in ["com.acme.Missing", "java/lang/Object"] {
+        let throwable = vm.for_name(name, false, APPLICATION_LOADER).unwrap_err();
+        assert!(throwable.is(CLASS_NOT_FOUND_EXCEPTION));
+        assert_eq!(throwable.message, name);
+    }
+}
+
+#[test]
+pub fn test_get_resources_on_class_path() {
+    let vm = JVM::create_with_class_path("java/services/cp/codecs.jar:java/services/cp/broken.jar");
+
+    let resource = vm
+        .get_resource(APPLICATION_LOADER, "com/acme/codec/codecs.properties")
+        .unwrap();
+    let jar = fs::canonicalize("java/services/cp/codecs.jar").unwrap();
+    assert_eq!(
+        resource.url,
+        format!(
+            "jar:file:{}!/com/acme/codec/codecs.properties",
+            jar.display()
+        )
+    );
+    assert_eq!(
+        vm.get_resource_as_stream(APPLICATION_LOADER, "com/acme/codec/codecs.properties"),
+        Some(b"alphabet=0123456789abcdef\n".to_vec())
+    );
+    assert_eq!(
+        vm.get_resources(APPLICATION_LOADER, "META-INF/services/com.acme.spi.Codec")
+            .len(),
+        2
+    );
+    assert!(vm
+        .get_resource(APPLICATION_LOADER, "missing.properties")
+        .is_none());
+}
+
+#[test]
+pub fn test_get_resources_in_module() {
+    use crate::module_graph::{ModuleOptions, ALL_MODULE_PATH};
+    use std::path::PathBuf;
+
+    let options = ModuleOptions {
+        module_path: vec![PathBuf::from("java/services/mods")],
+        add_modules: vec![String::from(ALL_MODULE_PATH)],
+        ..ModuleOptions::default()
+    };
+    let mut vm = JVM::create();
+    vm.set_module_graph(
+        ClassPath::from_paths(vec![]),
+        ModuleGraph::resolve(&options, None).unwrap(),
+    );
+
+    // class files are not encapsulated, other resources of a package not opened are
+    assert!(vm
+        .get_resource(APPLICATION_LOADER, "com/acme/zip/ZipCodec.class")
+        .is_some());
+    assert!(vm
+        .get_resource(APPLICATION_LOADER, "com/acme/zip/zip.properties")
+        .is_none());
+}
+
+#[test]
+pub fn test_class_data_shared_across_vms() {
+    let vm = JVM::create_with_class_path("java");
+    let other = JVM::create_with_class_path("java");
+
+    let class = vm
+        .for_name("StaticCounter", true, APPLICATION_LOADER)
+        .unwrap();
+    let other_class = other
+        .for_name("StaticCounter", false, APPLICATION_LOADER)
+        .unwrap();
+
+    assert!(Arc::ptr_eq(&class.data(), &other_class.data()));
+    // statics and the initialization state are of each JVM
+    assert_eq!(class.static_value("count:I"), Some(Value::Int(1)));
+    assert_eq!(other_class.static_value("count:I"), Some(Value::Int(0)));
+    assert_eq!(*other_class.init_state.borrow(), InitState::Uninitialized);
+    assert!(!Rc::ptr_eq(
+        &class.runtime_constant_pool(),
+        &other_class.runtime_constant_pool()
+    ));
+}
+
+#[test]
+pub fn test_preload_class_path() {
+    let vm = JVM::create_with_class_path("java/redefine:java/services/cp/codecs.jar");
+
+    let failed = vm.preload_class_path(4);
+
+    // the classes under v2 and added are named Greeting, not by their path
+    let messages: Vec<&str> = failed
+        .iter()
+        .map(|throwable| throwable.message.as_str())
+        .collect();
+    assert_eq!(
+        messages,
+        [
+            "added/Greeting (wrong name: Greeting)",
+            "v2/Greeting (wrong name: Greeting)",
+            "com/acme/spi/Codec",
+        ]
+    );
+    let method_area = vm.method_area.borrow();
+    assert!(method_area
+        .lookup_class(APPLICATION_LOADER, "Caller")
+        .is_some());
+    assert!(method_area
+        .lookup_class(APPLICATION_LOADER, "Greeting")
+        .is_some());
+}
+
+#[test]
+pub fn test_load_class_parsed_ahead() {
+    let vm = JVM::create_with_class_path("java/redefine");
+    // another version of Greeting than the one on the class path
+    let binary = read_binary_file(&"java/redefine/v2/Greeting.class".to_owned()).unwrap();
+    let data = ClassData::shared(&binary).unwrap();
+    let parsed = ParsedClassFile {
+        binary,
+        data: Arc::clone(&data),
+    };
+
+    let class = vm
+        .load_class_by(APPLICATION_LOADER, "Greeting", Some(parsed))
+        .unwrap()
+        .unwrap();
+
+    // defined from the class data parsed ahead, without reading the class path again
+    assert!(Arc::ptr_eq(&class.data(), &data));
 }
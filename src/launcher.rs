@@ -0,0 +1,395 @@
+use std::env;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::thread;
+
+use crate::class_path::{ClassPath, DEFAULT_CLASS_PATH};
+use crate::jar::JarFile;
+use crate::module_graph::ModuleOptions;
+use crate::shared_archive::{SharingMode, SharingOptions};
+
+const USAGE: &str = "Usage: java [-cp <class path>] <main class>\n   or  java -jar <jar file>\n   \
+     or  java [-p <module path>] -m <module>[/<main class>]\n   \
+     or  java -Xshare:dump -XX:SharedClassListFile=<class list> -XX:SharedArchiveFile=<archive> [-cp <class path>]\n   \
+     or  java -XX:+PreloadClassPath [-XX:PreloadThreads=<n>] [-cp <class path>] <main class>\n   \
+     or  java --debug-commands [-cp <class path>] <main class>";
+
+const SHARED_ARCHIVE_FILE: &str = "-XX:SharedArchiveFile=";
+const SHARED_CLASS_LIST_FILE: &str = "-XX:SharedClassListFile=";
+const PRELOAD_THREADS: &str = "-XX:PreloadThreads=";
+
+/// Options of the `java` launcher.
+/// https://docs.oracle.com/en/java/javase/17/docs/specs/man/java.html
+#[derive(Debug)]
+pub struct LaunchOptions {
+    pub class_path: ClassPath,
+    /// empty with `-m <module>`, where it is taken from the ModuleMainClass of the module
+    pub main_class: String,
+    pub main_module: Option<String>,
+    pub module_options: ModuleOptions,
+    /// the JDK whose class library is loaded by the bootstrap loader
+    pub jdk_home: Option<PathBuf>,
+    /// class data sharing, `-Xshare` with its archive
+    pub sharing: SharingOptions,
+    /// the threads parsing the classes on the class path, which are all loaded before the main
+    /// class with `-XX:+PreloadClassPath`, or `-XX:PreloadThreads=<n>` to give the number
+    pub preload_threads: Option<usize>,
+    /// `--debug-commands` reads debugger commands such as `redefine` from the standard input
+    pub debug_commands: bool,
+}
+
+impl LaunchOptions {
+    /// split launcher options from the main class name.
+    /// class path is taken from `-cp`, then `CLASSPATH` env, then the current directory.
+    /// with `-jar`, both the main class and the class path come from the jar manifest.
+    /// with `-m`, the main class is loaded from the module, with the module options.
+    /// with `-Xshare:dump`, no main class is needed.
+    pub fn parse(args: &[String]) -> LaunchOptions {
+        let mut class_path = None;
+        let mut module_options = ModuleOptions::default();
+        let mut jdk_home = None;
+        let mut sharing = SharingOptions::default();
+        let mut preload_threads = None;
+        let mut debug_commands = false;
+        let mut rest = args.iter();
+        loop {
+            match rest.next().map(String::as_str) {
+                Some("-cp" | "-classpath" | "--class-path") => {
+                    class_path = Some(value_of(&mut rest, "-cp requires class path specification"));
+                }
+                Some("--jdk-home") => {
+                    let path = value_of(&mut rest, "--jdk-home requires JDK home directory");
+                    jdk_home = Some(PathBuf::from(path));
+                }
+                Some("--debug-commands") => debug_commands = true,
+                Some("-Xshare:dump") => sharing.mode = SharingMode::Dump,
+                Some("-Xshare:auto") => sharing.mode = SharingMode::Auto,
+                Some("-Xshare:off") => sharing.mode = SharingMode::Off,
+                Some(option) if option.starts_with(SHARED_ARCHIVE_FILE) => {
+                    sharing.archive_file =
+                        Some(PathBuf::from(&option[SHARED_ARCHIVE_FILE.len()..]));
+                }
+                Some(option) if option.starts_with(SHARED_CLASS_LIST_FILE) => {
+                    sharing.class_list_file =
+                        Some(PathBuf::from(&option[SHARED_CLASS_LIST_FILE.len()..]));
+                }
+                Some("-XX:+PreloadClassPath") => {
+                    preload_threads = preload_threads.or_else(|| {
+                        Some(thread::available_parallelism().map_or(1, NonZeroUsize::get))
+                    });
+                }
+                Some(option) if option.starts_with(PRELOAD_THREADS) => {
+                    let threads = option[PRELOAD_THREADS.len()..]
+                        .parse()
+                        .ok()
+                        .filter(|&threads| threads > 0)
+                        .unwrap_or_else(|| panic!("Error: Invalid {}", option));
+                    preload_threads = Some(threads);
+                }
+                Some("-p" | "--module-path") => {
+                    let module_path = value_of(
+                        &mut rest,
+                        "--module-path requires module path specification",
+                    );
+                    module_options
+                        .module_path
+                        .extend(env::split_paths(&module_path));
+                }
+                Some("--add-modules") => {
+                    let modules =
+                        value_of(&mut rest, "--add-modules requires modules to be specified");
+                    module_options
+                        .add_modules
+                        .extend(modules.split(',').map(str::to_owned));
+                }
+                Some(option @ ("--add-exports" | "--add-opens" | "--add-reads")) => {
+                    let value = value_of(
+                        &mut rest,
+                        &format!("{} requires modules to be specified", option),
+                    );
+                    match option {
+                        "--add-exports" => module_options.add_exports.push(value),
+                        "--add-opens" => module_options.add_opens.push(value),
+                        _ => module_options.add_reads.push(value),
+                    }
+                }
+                Some("-m" | "--module") => {
+                    let value = value_of(&mut rest, "-m requires module name");
+                    let (module, main_class) = value.split_once('/').unwrap_or((&value, ""));
+                    return LaunchOptions {
+                        class_path: class_path_of(class_path),
+                        main_class: main_class.replace('.', "/"),
+                        main_module: Some(module.to_owned()),
+                        module_options,
+                        jdk_home,
+                        sharing,
+                        preload_threads,
+                        debug_commands,
+                    };
+                }
+                Some("-jar") => {
+                    let jar = value_of(&mut rest, "-jar requires jar file specification");
+                    let mut options = LaunchOptions::from_jar(Path::new(&jar));
+                    options.module_options = module_options;
+                    options.jdk_home = jdk_home;
+                    options.sharing = sharing;
+                    options.preload_threads = preload_threads;
+                    options.debug_commands = debug_commands;
+                    return options;
+                }
+                Some(main_class) => {
+                    let (class_path, main_class) = match class_file_path_of(main_class) {
+                        Some((directory, main_class)) => (ClassPath::parse(directory), main_class),
+                        None => (class_path_of(class_path), main_class),
+                    };
+                    return LaunchOptions {
+                        class_path,
+                        main_class: main_class.to_owned(),
+                        main_module: None,
+                        module_options,
+                        jdk_home,
+                        sharing,
+                        preload_threads,
+                        debug_commands,
+                    };
+                }
+                None if sharing.mode == SharingMode::Dump => {
+                    return LaunchOptions {
+                        class_path: class_path_of(class_path),
+                        main_class: String::new(),
+                        main_module: None,
+                        module_options,
+                        jdk_home,
+                        sharing,
+                        preload_threads,
+                        debug_commands,
+                    };
+                }
+                None => panic!("Error: Main class is not specified.\n{}", USAGE),
+            }
+        }
+    }
+
+    /// the jar itself comes first on the class path, followed by its manifest `Class-Path`,
+    /// whose entries are URLs relative to the directory containing the jar.
+    fn from_jar(jar: &Path) -> LaunchOptions {
+        let jar_file = JarFile::open(jar).unwrap_or_else(|e| {
+            panic!(
+                "Error: Unable to access jarfile {}\n Reason {}",
+                jar.display(),
+                e
+            )
+        });
+        let manifest = &jar_file.manifest;
+        let main_class = manifest
+            .main_class()
+            .unwrap_or_else(|| panic!("no main manifest attribute, in {}", jar.display()))
+            .to_owned();
+
+        let base = jar.parent().unwrap_or_else(|| Path::new(""));
+        let mut paths = vec![jar.to_path_buf()];
+        paths.extend(
+            manifest
+                .class_path()
+                .iter()
+                .map(|relative| base.join(path_of_url(relative))),
+        );
+
+        LaunchOptions {
+            class_path: ClassPath::from_paths(paths),
+            main_class,
+            main_module: None,
+            module_options: ModuleOptions::default(),
+            jdk_home: None,
+            sharing: SharingOptions::default(),
+            preload_threads: None,
+            debug_commands: false,
+        }
+    }
+}
+
+/// the argument following an option, `error` is shown without it
+fn value_of<'a, I: Iterator<Item = &'a String>>(rest: &mut I, error: &str) -> String {
+    rest.next()
+        .unwrap_or_else(|| panic!("Error: {}", error))
+        .clone()
+}
+
+/// the path of a relative `file:` URL of the manifest `Class-Path`, with its `%XX` escapes
+/// decoded as UTF-8, such as `lib/my%20lib.jar` for `lib/my lib.jar`.
+/// https://docs.oracle.com/en/java/javase/17/docs/specs/jar/jar.html#class-path-attribute
+fn path_of_url(url: &str) -> String {
+    let url = url.strip_prefix("file:").unwrap_or(url);
+    let mut bytes = Vec::with_capacity(url.len());
+    let mut rest = url.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        let escaped = tail
+            .get(..2)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(decoded) if byte == b'%' => {
+                bytes.push(decoded);
+                rest = &tail[2..];
+            }
+            _ => {
+                bytes.push(byte);
+                rest = tail;
+            }
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+/// `-cp` comes first, then `CLASSPATH` env, then the current directory
+/// the main class may be given as the path of its class file, such as `./java/SimpleSum`,
+/// then the directory of the class file is the class path. a class name such as
+/// `com/acme/Main` never starts with `.` or `/`.
+fn class_file_path_of(main_class: &str) -> Option<(&str, &str)> {
+    if !main_class.starts_with(['.', '/']) {
+        return None;
+    }
+    match main_class.rsplit_once('/') {
+        Some(("", name)) => Some(("/", name)),
+        other => other,
+    }
+}
+
+fn class_path_of(option: Option<String>) -> ClassPath {
+    let class_path = option
+        .or_else(|| env::var("CLASSPATH").ok())
+        .unwrap_or_else(|| DEFAULT_CLASS_PATH.to_owned());
+    ClassPath::parse(&class_path)
+}
+
+#[test]
+fn test_parse_class_path_option() {
+    let args = [
+        String::from("-classpath"),
+        String::from("lib:java"),
+        String::from("com/acme/Main"),
+    ];
+    let result = LaunchOptions::parse(&args);
+
+    assert_eq!(result.class_path.entries.len(), 2);
+    assert_eq!(result.main_class, "com/acme/Main");
+}
+
+#[test]
+fn test_parse_class_file_path() {
+    let args = [String::from("./java/SimpleSum")];
+    let result = LaunchOptions::parse(&args);
+
+    assert_eq!(result.main_class, "SimpleSum");
+    assert!(result.class_path.find_class("SimpleSum").unwrap().is_some());
+}
+
+#[test]
+fn test_parse_jar_option() {
+    // app.jar only has a manifest, the main class is in lib/acme.jar from its Class-Path
+    let args = [
+        String::from("-cp"),
+        String::from("ignored"),
+        String::from("-jar"),
+        String::from("java/app/app.jar"),
+    ];
+    let result = LaunchOptions::parse(&args);
+
+    assert_eq!(result.main_class, "com.acme.Main");
+    assert_eq!(result.class_path.entries.len(), 2);
+    assert!(result
+        .class_path
+        .find_class(&result.main_class)
+        .unwrap()
+        .is_some());
+}
+
+#[test]
+fn test_path_of_url() {
+    assert_eq!(path_of_url("lib/acme.jar"), "lib/acme.jar");
+    assert_eq!(path_of_url("file:lib/acme.jar"), "lib/acme.jar");
+    assert_eq!(path_of_url("lib/my%20lib.jar"), "lib/my lib.jar");
+    assert_eq!(path_of_url("caf%C3%A9/%2e%2E.jar"), "café/...jar");
+    // not an escape, kept as is
+    assert_eq!(path_of_url("100%.jar"), "100%.jar");
+    assert_eq!(path_of_url("a%zz.jar"), "a%zz.jar");
+}
+
+#[test]
+fn test_parse_module_options() {
+    let args = [
+        "-p",
+        "java/modules/mods:java/modules/lib",
+        "--add-modules",
+        "legacy.util,ALL-MODULE-PATH",
+        "--add-exports",
+        "com.acme.greeting/com.acme.greeting.internal=ALL-UNNAMED",
+        "--add-reads",
+        "com.acme.app=ALL-UNNAMED",
+        "-m",
+        "com.acme.app/com.acme.app.Main",
+    ]
+    .map(String::from);
+    let result = LaunchOptions::parse(&args);
+
+    assert_eq!(result.main_module.as_deref(), Some("com.acme.app"));
+    assert_eq!(result.main_class, "com/acme/app/Main");
+    let module_options = &result.module_options;
+    assert_eq!(module_options.module_path.len(), 2);
+    assert_eq!(
+        module_options.add_modules,
+        ["legacy.util", "ALL-MODULE-PATH"]
+    );
+    assert_eq!(module_options.add_exports.len(), 1);
+    assert_eq!(module_options.add_reads, ["com.acme.app=ALL-UNNAMED"]);
+    assert!(module_options.add_opens.is_empty());
+}
+
+#[test]
+fn test_parse_sharing_options() {
+    let args = [
+        "-Xshare:dump",
+        "-XX:SharedClassListFile=app.classlist",
+        "-XX:SharedArchiveFile=app.jsa",
+        "-cp",
+        "java",
+    ]
+    .map(String::from);
+    let result = LaunchOptions::parse(&args);
+
+    assert_eq!(result.sharing.mode, SharingMode::Dump);
+    assert_eq!(
+        result.sharing.class_list_file,
+        Some(PathBuf::from("app.classlist"))
+    );
+    assert_eq!(result.sharing.archive_file, Some(PathBuf::from("app.jsa")));
+    assert!(result.main_class.is_empty());
+
+    let args = ["-Xshare:off", "SimpleSum"].map(String::from);
+    assert_eq!(LaunchOptions::parse(&args).sharing.mode, SharingMode::Off);
+}
+
+#[test]
+fn test_parse_preload_options() {
+    let args = ["-XX:+PreloadClassPath", "SimpleSum"].map(String::from);
+    assert!(LaunchOptions::parse(&args)
+        .preload_threads
+        .is_some_and(|threads| threads > 0));
+
+    let args = ["-XX:PreloadThreads=3", "-XX:+PreloadClassPath", "SimpleSum"].map(String::from);
+    assert_eq!(LaunchOptions::parse(&args).preload_threads, Some(3));
+
+    let args = ["SimpleSum"].map(String::from);
+    assert_eq!(LaunchOptions::parse(&args).preload_threads, None);
+}
+
+#[test]
+fn test_parse_debug_commands_option() {
+    let args = ["--debug-commands", "-cp", "java", "SimpleSum"].map(String::from);
+    let options = LaunchOptions::parse(&args);
+    assert!(options.debug_commands);
+    assert_eq!(options.main_class, "SimpleSum");
+
+    let args = ["SimpleSum"].map(String::from);
+    assert!(!LaunchOptions::parse(&args).debug_commands);
+}
@@ -0,0 +1,226 @@
+use std::rc::Rc;
+
+use crate::class::Class;
+use crate::class_loader::LoaderId;
+
+/// Loading constraints, JVMS 5.3.4.
+/// Each constraint is a class name with the loaders which must load the same class for it.
+/// https://docs.oracle.com/javase/specs/jvms/se17/html/jvms-5.html#jvms-5.3.4
+#[derive(Debug, Default)]
+pub struct LoaderConstraints {
+    constraints: Vec<(String, Vec<LoaderId>)>,
+}
+
+impl LoaderConstraints {
+    /// the loaders constrained together with `loader` for `class_name`, including itself
+    pub fn loaders_of(&self, class_name: &str, loader: LoaderId) -> Vec<LoaderId> {
+        self.constraints
+            .iter()
+            .find(|(name, loaders)| name == class_name && loaders.contains(&loader))
+            .map_or_else(|| vec![loader], |(_, loaders)| loaders.clone())
+    }
+
+    /// add `N^first = N^second`, where `loaded` is the class of `class_name` already loaded by
+    /// the loader. returns false without adding it, if the loaders have different classes.
+    pub fn add<F>(&mut self, class_name: &str, first: LoaderId, second: LoaderId, loaded: F) -> bool
+    where
+        F: Fn(LoaderId) -> Option<Rc<Class>>,
+    {
+        let mut merged = self.loaders_of(class_name, first);
+        for loader in self.loaders_of(class_name, second) {
+            if !merged.contains(&loader) {
+                merged.push(loader);
+            }
+        }
+
+        let mut classes = merged.iter().filter_map(|&loader| loaded(loader));
+        if let Some(class) = classes.next() {
+            if classes.any(|other| !Rc::ptr_eq(&class, &other)) {
+                return false;
+            }
+        }
+
+        self.constraints.retain(|(name, loaders)| {
+            name != class_name || !loaders.iter().any(|l| merged.contains(l))
+        });
+        self.constraints.push((class_name.to_owned(), merged));
+        true
+    }
+
+    /// a loader constrained with `loader`, which has loaded another class than `class`
+    /// for `class_name`. `class` is `None` for a class being defined, which is always another one.
+    pub fn violated_by<F>(
+        &self,
+        class_name: &str,
+        loader: LoaderId,
+        class: Option<&Rc<Class>>,
+        loaded: F,
+    ) -> Option<LoaderId>
+    where
+        F: Fn(LoaderId) -> Option<Rc<Class>>,
+    {
+        self.loaders_of(class_name, loader)
+            .into_iter()
+            .filter(|&other| other != loader)
+            .find(|&other| {
+                loaded(other)
+                    .is_some_and(|found| class.is_none_or(|class| !Rc::ptr_eq(class, &found)))
+            })
+    }
+}
+
+#[test]
+fn test_add_loader_constraints() {
+    use crate::class_loader::{APPLICATION_LOADER, BOOTSTRAP_LOADER, PLATFORM_LOADER};
+    use crate::jvm::JVM;
+
+    let vm = JVM::create_with_class_path("java");
+    let shape = vm.resolve_class("Shape").unwrap();
+    let polygon = vm.resolve_class("Polygon").unwrap();
+    let mut constraints = LoaderConstraints::default();
+
+    // nothing loaded yet, then any constraint is consistent
+    assert!(constraints.add("Shape", APPLICATION_LOADER, PLATFORM_LOADER, |_| None));
+    assert!(constraints.add("Shape", PLATFORM_LOADER, BOOTSTRAP_LOADER, |_| None));
+    assert_eq!(constraints.loaders_of("Shape", BOOTSTRAP_LOADER).len(), 3);
+
+    // the application loader loads Shape, then the bootstrap loader must load the same one
+    let loaded_by_app = |loader| (loader == APPLICATION_LOADER).then(|| Rc::clone(&shape));
+    assert_eq!(
+        constraints.violated_by("Shape", BOOTSTRAP_LOADER, Some(&polygon), loaded_by_app),
+        Some(APPLICATION_LOADER)
+    );
+    assert_eq!(
+        constraints.violated_by("Shape", BOOTSTRAP_LOADER, Some(&shape), loaded_by_app),
+        None
+    );
+    assert!(
+        !constraints.add("Shape", APPLICATION_LOADER, BOOTSTRAP_LOADER, |loader| {
+            match loader {
+                BOOTSTRAP_LOADER => Some(Rc::clone(&polygon)),
+                _ => loaded_by_app(loader),
+            }
+        })
+    );
+}
+
+/// A plugin loader looking into its own class path first, see java/constraints.
+#[cfg(test)]
+struct ChildFirstLoader(crate::class_loader::ClassPathLoader);
+
+#[cfg(test)]
+impl crate::class_loader::ClassLoader for ChildFirstLoader {
+    fn name(&self) -> &str {
+        self.0.name()
+    }
+    fn parent(&self) -> Option<LoaderId> {
+        self.0.parent()
+    }
+    fn find_class(&self, class_name: &str) -> Result<Option<Vec<u8>>, crate::throwable::Throwable> {
+        self.0.find_class(class_name)
+    }
+    fn is_parent_first(&self, _class_name: &str) -> bool {
+        false
+    }
+}
+
+/// java/constraints/host and java/constraints/plugin both have Shared,
+/// and the plugin loader defines its own one before asking the host loader.
+#[cfg(test)]
+fn plugin_host_vm() -> (crate::jvm::JVM, LoaderId, LoaderId) {
+    use crate::class_loader::{ClassPathLoader, APPLICATION_LOADER};
+    use crate::class_path::ClassPath;
+
+    let mut vm = crate::jvm::JVM::create_with_class_path("java");
+    let host = vm.add_class_loader(Box::new(ClassPathLoader::create(
+        "host",
+        APPLICATION_LOADER,
+        ClassPath::parse("java/constraints/host"),
+    )));
+    let plugin = vm.add_class_loader(Box::new(ChildFirstLoader(ClassPathLoader::create(
+        "plugin",
+        host,
+        ClassPath::parse("java/constraints/plugin"),
+    ))));
+    (vm, host, plugin)
+}
+
+#[test]
+fn test_resolution_violates_loader_constraint() {
+    use crate::access::resolve_constant_of;
+    use crate::throwable::LINKAGE_ERROR;
+
+    let (vm, host, plugin) = plugin_host_vm();
+    let client = vm.load_class(plugin, "Client").unwrap();
+    vm.load_class(plugin, "Shared").unwrap();
+    vm.load_class(host, "Shared").unwrap();
+
+    let error = resolve_constant_of(&vm, &client, "Api.run:(LShared;)I").unwrap_err();
+
+    assert!(error.is(LINKAGE_ERROR));
+    assert_eq!(
+        error.message,
+        "loader constraint violation: when resolving method Api.run:(LShared;)I \
+         the class loader 'plugin' of the current class, Client, and the class loader 'host' \
+         for the method's defining class, Api, have different Class objects for the type Shared \
+         used in the signature"
+    );
+}
+
+#[test]
+fn test_member_with_l_in_its_name_across_loaders() {
+    use crate::access::resolve_constant_of;
+    use crate::throwable::LINKAGE_ERROR;
+
+    // only the descriptor of the member names classes, not `LOG` or `toLowerCase`
+    let (vm, host, plugin) = plugin_host_vm();
+    let client = vm.load_class(plugin, "Client").unwrap();
+    vm.load_class(plugin, "Shared").unwrap();
+    vm.load_class(host, "Shared").unwrap();
+
+    assert!(resolve_constant_of(&vm, &client, "Api.getLength:()I").is_ok());
+    for member in ["Api.LOG:LShared;", "Api.toLowerCase:()LShared;"] {
+        let error = resolve_constant_of(&vm, &client, member).unwrap_err();
+        assert!(error.is(LINKAGE_ERROR), "{}", member);
+        assert!(error
+            .message
+            .ends_with("for the type Shared used in the signature"));
+    }
+}
+
+#[test]
+fn test_loading_violates_loader_constraint() {
+    use crate::access::resolve_constant_of;
+    use crate::throwable::LINKAGE_ERROR;
+
+    // the constraint is imposed before either Shared is loaded
+    let (vm, host, plugin) = plugin_host_vm();
+    let client = vm.load_class(plugin, "Client").unwrap();
+    assert!(resolve_constant_of(&vm, &client, "Api.run:(LShared;)I").is_ok());
+    vm.load_class(host, "Shared").unwrap();
+
+    let error = vm.load_class(plugin, "Shared").unwrap_err();
+
+    assert!(error.is(LINKAGE_ERROR));
+    assert_eq!(
+        error.message,
+        "loader constraint violation: loader 'plugin' wants to load class Shared. \
+         A different class with the same name was previously loaded by 'host'."
+    );
+}
+
+#[test]
+fn test_overriding_violates_loader_constraint() {
+    use crate::throwable::LINKAGE_ERROR;
+
+    let (vm, host, plugin) = plugin_host_vm();
+    vm.load_class(plugin, "Shared").unwrap();
+    vm.load_class(host, "Shared").unwrap();
+
+    let error = vm.load_class(plugin, "MyHandler").unwrap_err();
+
+    assert!(error.is(LINKAGE_ERROR));
+    assert!(error
+        .message
+        .contains("overriding method handle:(LShared;)I"));
+}
@@ -1,21 +1,48 @@
+mod access;
 mod binary;
 mod class;
 mod class_attributes;
+mod class_data;
 mod class_file;
 mod class_loader;
+mod class_path;
 mod cp_info;
+mod debugger;
+mod field;
+mod inflate;
 mod instruction;
 mod instruction_set;
+mod instrument;
 mod interpreter;
 mod invoke;
+mod jar;
+mod jimage;
 mod jvm;
+mod launcher;
+mod loader_constraints;
+mod manifest;
+mod module;
+mod module_graph;
+mod module_path;
+mod redefinition;
+mod resolution;
+mod runtime_constant_pool;
+mod service_loader;
+mod shared_archive;
 mod thread;
+mod throwable;
+mod value;
+mod zip;
 
 use crate::jvm::JVM;
 use std::env;
+use std::process;
 
 fn main() {
     let args: Vec<String> = env::args().collect();
     let mut vm = JVM::create();
-    vm.launch(&args[1..]);
+    if let Err(throwable) = vm.launch(&args[1..]) {
+        eprintln!("Exception in thread \"main\" {}", throwable);
+        process::exit(1);
+    }
 }
@@ -0,0 +1,95 @@
+use std::collections::HashMap;
+
+pub const MANIFEST_NAME: &str = "META-INF/MANIFEST.MF";
+
+/// JAR Manifest, `META-INF/MANIFEST.MF`.
+/// https://docs.oracle.com/en/java/javase/17/docs/specs/jar/jar.html#jar-manifest
+#[derive(Debug, Default)]
+pub struct Manifest {
+    /// the main section, attribute names are lower cased since they are case-insensitive
+    pub main_attributes: HashMap<String, String>,
+    /// per-entry sections, keyed by the `Name` attribute
+    pub entries: HashMap<String, HashMap<String, String>>,
+}
+
+impl Manifest {
+    pub fn parse_from(binary: &[u8]) -> Manifest {
+        let text = String::from_utf8_lossy(binary);
+
+        // join continuation lines, which start with a single space
+        let mut lines: Vec<String> = vec![];
+        for line in text.split("\r\n").flat_map(|line| line.split(['\r', '\n'])) {
+            match (line.strip_prefix(' '), lines.last_mut()) {
+                (Some(continuation), Some(last)) if !last.is_empty() => last.push_str(continuation),
+                _ => lines.push(line.to_owned()),
+            }
+        }
+
+        let mut manifest = Manifest::default();
+        let mut sections = lines.split(|line| line.is_empty());
+        if let Some(main_section) = sections.next() {
+            manifest.main_attributes = parse_section(main_section);
+        }
+        for section in sections.filter(|section| !section.is_empty()) {
+            let mut attributes = parse_section(section);
+            if let Some(name) = attributes.remove("name") {
+                manifest.entries.insert(name, attributes);
+            }
+        }
+        manifest
+    }
+
+    pub fn main_attribute(&self, name: &str) -> Option<&str> {
+        self.main_attributes
+            .get(&name.to_ascii_lowercase())
+            .map(String::as_str)
+    }
+
+    pub fn main_class(&self) -> Option<&str> {
+        self.main_attribute("Main-Class")
+    }
+
+    /// space separated relative URLs of the dependent jars and directories
+    pub fn class_path(&self) -> Vec<&str> {
+        self.main_attribute("Class-Path")
+            .map(|class_path| class_path.split_whitespace().collect())
+            .unwrap_or_default()
+    }
+}
+
+fn parse_section(lines: &[String]) -> HashMap<String, String> {
+    lines
+        .iter()
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_ascii_lowercase(), value.trim().to_owned()))
+        .collect()
+}
+
+#[test]
+fn test_parse_manifest() {
+    let binary = b"Manifest-Version: 1.0\r\n\
+        Main-Class: com.acme.Main\r\n\
+        Class-Path: lib/first.jar lib/seco\r\n nd.jar classes/\r\n\
+        \r\n\
+        Name: com/acme/\r\n\
+        Sealed: true\r\n\
+        \r\n";
+
+    let result = Manifest::parse_from(binary);
+
+    assert_eq!(result.main_attribute("manifest-version"), Some("1.0"));
+    assert_eq!(result.main_class(), Some("com.acme.Main"));
+    assert_eq!(
+        result.class_path(),
+        ["lib/first.jar", "lib/second.jar", "classes/"]
+    );
+    assert_eq!(result.entries["com/acme/"]["sealed"], "true");
+}
+
+#[test]
+fn test_parse_manifest_without_main_class() {
+    let result = Manifest::parse_from(b"Manifest-Version: 1.0\nCreated-By: 17\n");
+
+    assert_eq!(result.main_class(), None);
+    assert!(result.class_path().is_empty());
+}
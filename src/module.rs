@@ -0,0 +1,281 @@
+use crate::class::package_name_of;
+use crate::class_attributes::{AttributeInfo, ModuleExports};
+use crate::class_file::{ClassFile, ACC_MODULE, ACC_OPEN, ACC_STATIC_PHASE, ACC_TRANSITIVE};
+use crate::cp_info::{constant_pool_value_at, CpInfo};
+
+/// the module every module reads, which java/lang/Object belongs to
+pub const JAVA_BASE: &str = "java.base";
+
+pub const MODULE_INFO: &str = "module-info";
+
+/// A module declaration, JLS 7.7, from `module-info.class` or derived for an automatic module.
+/// package names are in the internal form, such as `com/acme/app`.
+/// https://docs.oracle.com/javase/specs/jvms/se17/html/jvms-4.html#jvms-4.7.25
+#[derive(Debug, Default)]
+pub struct ModuleDescriptor {
+    pub name: String,
+    /// an open module opens all of its packages
+    pub is_open: bool,
+    /// a plain jar on the module path, which reads every module and exports all of its packages
+    pub is_automatic: bool,
+    pub requires: Vec<ModuleRequires>,
+    pub exports: Vec<PackageExports>,
+    pub opens: Vec<PackageExports>,
+    pub packages: Vec<String>,
+    pub main_class: Option<String>,
+    pub provides: Vec<Provides>,
+}
+
+#[derive(Debug)]
+pub struct ModuleRequires {
+    pub name: String,
+    /// `requires transitive`, the modules reading this one also read the required one
+    pub is_transitive: bool,
+    /// `requires static`, required at compile time only
+    pub is_static: bool,
+}
+
+/// an `exports` or `opens` directive, to every module if `targets` is empty
+#[derive(Debug)]
+pub struct PackageExports {
+    pub package: String,
+    pub targets: Vec<String>,
+}
+
+/// a `provides` directive, the classes implementing a service in the internal form
+#[derive(Debug)]
+pub struct Provides {
+    pub service: String,
+    pub providers: Vec<String>,
+}
+
+impl PackageExports {
+    pub fn is_to(&self, package: &str, module: Option<&str>) -> bool {
+        self.package == package
+            && (self.targets.is_empty()
+                || module.is_some_and(|module| self.targets.iter().any(|target| target == module)))
+    }
+}
+
+impl ModuleDescriptor {
+    /// read `module-info.class`. `packages` are the ones found in the module, in addition to
+    /// `ModulePackages`, which javac doesn't write but the jar tool does.
+    pub fn parse_from(binary: &[u8], packages: Vec<String>) -> Result<ModuleDescriptor, String> {
+        let class_file = ClassFile::parse_from(binary)?;
+        if class_file.access_flags & ACC_MODULE == 0 {
+            return Err(String::from(
+                "module-info.class is not a module declaration",
+            ));
+        }
+        let cp = &class_file.constant_pool;
+
+        let mut descriptor = ModuleDescriptor {
+            packages,
+            ..ModuleDescriptor::default()
+        };
+        let mut declared = false;
+        for attribute in &class_file.attributes {
+            match attribute {
+                AttributeInfo::ModuleAttributeInfo(info) => {
+                    declared = true;
+                    descriptor.name = constant_pool_value_at(cp, info.module_name_index);
+                    descriptor.is_open = info.module_flags & ACC_OPEN != 0;
+                    descriptor.requires = info
+                        .requires
+                        .iter()
+                        .map(|requires| ModuleRequires {
+                            name: constant_pool_value_at(cp, requires.requires_index),
+                            is_transitive: requires.requires_flags & ACC_TRANSITIVE != 0,
+                            is_static: requires.requires_flags & ACC_STATIC_PHASE != 0,
+                        })
+                        .collect();
+                    descriptor.exports = package_exports_of(cp, &info.exports);
+                    descriptor.opens = package_exports_of(cp, &info.opens);
+                    descriptor.provides = info
+                        .provides
+                        .iter()
+                        .map(|provides| Provides {
+                            service: constant_pool_value_at(cp, provides.provides_index),
+                            providers: provides
+                                .provides_with_index
+                                .iter()
+                                .map(|&index| constant_pool_value_at(cp, index))
+                                .collect(),
+                        })
+                        .collect();
+                }
+                AttributeInfo::ModulePackagesAttributeInfo(info) => {
+                    for &index in &info.package_index {
+                        descriptor.add_package(constant_pool_value_at(cp, index));
+                    }
+                }
+                AttributeInfo::ModuleMainClassAttributeInfo(info) => {
+                    descriptor.main_class = Some(constant_pool_value_at(cp, info.main_class_index));
+                }
+                _ => {}
+            }
+        }
+        if !declared {
+            return Err(String::from("module-info.class has no Module attribute"));
+        }
+        // exported and opened packages are in the module, even if they have no class file
+        let directives: Vec<String> = descriptor
+            .exports
+            .iter()
+            .chain(&descriptor.opens)
+            .map(|exports| exports.package.clone())
+            .collect();
+        for package in directives {
+            descriptor.add_package(package);
+        }
+        // a service is provided once, by classes of the module itself
+        for (index, provides) in descriptor.provides.iter().enumerate() {
+            if descriptor.provides[..index]
+                .iter()
+                .any(|other| other.service == provides.service)
+            {
+                return Err(format!(
+                    "Providers of service {} already declared",
+                    dotted_name_of(&provides.service)
+                ));
+            }
+            if let Some(provider) = provides
+                .providers
+                .iter()
+                .find(|provider| !descriptor.contains(package_name_of(provider)))
+            {
+                return Err(format!(
+                    "Provider class {} not in module",
+                    dotted_name_of(provider)
+                ));
+            }
+        }
+        Ok(descriptor)
+    }
+
+    /// the module of a plain jar, named after `Automatic-Module-Name` or the jar file name.
+    /// it provides the services listed in its `META-INF/services`.
+    pub fn automatic(
+        name: String,
+        packages: Vec<String>,
+        main_class: Option<String>,
+        provides: Vec<Provides>,
+    ) -> Self {
+        ModuleDescriptor {
+            name,
+            is_automatic: true,
+            packages,
+            main_class,
+            provides,
+            ..ModuleDescriptor::default()
+        }
+    }
+
+    pub fn contains(&self, package: &str) -> bool {
+        self.packages.iter().any(|name| name == package)
+    }
+
+    fn add_package(&mut self, package: String) {
+        if !self.contains(&package) {
+            self.packages.push(package);
+        }
+    }
+}
+
+fn package_exports_of(cp: &Vec<CpInfo>, exports: &[ModuleExports]) -> Vec<PackageExports> {
+    exports
+        .iter()
+        .map(|exports| PackageExports {
+            package: constant_pool_value_at(cp, exports.index),
+            targets: exports
+                .to_index
+                .iter()
+                .map(|&index| constant_pool_value_at(cp, index))
+                .collect(),
+        })
+        .collect()
+}
+
+/// derive the name of an automatic module from the jar file name, as `ModuleFinder.of` does.
+/// the version after the first `-` followed by a digit is dropped, such as `legacy-util-1.0.jar`
+/// to `legacy.util`, and any other non alphanumeric character is taken as a dot.
+pub fn automatic_module_name_of(file_name: &str) -> Option<String> {
+    let stem = file_name.strip_suffix(".jar").unwrap_or(file_name);
+    let stem = stem
+        .match_indices('-')
+        .find(|(index, _)| {
+            stem[index + 1..]
+                .chars()
+                .next()
+                .is_some_and(|c| c.is_ascii_digit())
+        })
+        .map_or(stem, |(index, _)| &stem[..index]);
+
+    let name = stem
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join(".");
+    (!name.is_empty()).then_some(name)
+}
+
+/// `com.acme.app` for the internal form `com/acme/app`, as it is written in the messages
+pub fn dotted_name_of(name: &str) -> String {
+    name.replace('/', ".")
+}
+
+#[test]
+fn test_parse_module_descriptor() {
+    use crate::binary::read_binary_file;
+
+    let path = "java/modules/mods/com.acme.greeting/module-info.class".to_owned();
+    let binary = read_binary_file(&path).unwrap();
+
+    let result = ModuleDescriptor::parse_from(&binary, vec![]).unwrap();
+
+    assert_eq!(result.name, "com.acme.greeting");
+    assert!(!result.is_open);
+    assert_eq!(result.requires[0].name, JAVA_BASE);
+    assert!(result.exports[0].is_to("com/acme/greeting/api", None));
+    assert!(result.opens[0].is_to("com/acme/greeting/internal", Some("com.acme.app")));
+    assert!(!result.opens[0].is_to("com/acme/greeting/internal", None));
+    assert_eq!(
+        result.packages,
+        ["com/acme/greeting/api", "com/acme/greeting/internal"]
+    );
+    assert_eq!(result.main_class, None);
+}
+
+#[test]
+fn test_provider_not_in_module() {
+    use crate::binary::read_binary_file;
+
+    let path = "java/services/mods/com.acme.zip/module-info.class".to_owned();
+    let binary = read_binary_file(&path).unwrap();
+
+    let result = ModuleDescriptor::parse_from(&binary, vec![]);
+    assert_eq!(
+        result.unwrap_err(),
+        "Provider class com.acme.zip.ZipCodec not in module"
+    );
+    let result = ModuleDescriptor::parse_from(&binary, vec!["com/acme/zip".to_owned()]).unwrap();
+    assert_eq!(result.provides[0].service, "com/acme/spi/Codec");
+    assert_eq!(result.provides[0].providers, ["com/acme/zip/ZipCodec"]);
+}
+
+#[test]
+fn test_automatic_module_name_of() {
+    assert_eq!(
+        automatic_module_name_of("legacy-util-1.0.jar").as_deref(),
+        Some("legacy.util")
+    );
+    assert_eq!(
+        automatic_module_name_of("commons_io-2.11.0-SNAPSHOT.jar").as_deref(),
+        Some("commons.io")
+    );
+    assert_eq!(
+        automatic_module_name_of("foo..bar.jar").as_deref(),
+        Some("foo.bar")
+    );
+    assert_eq!(automatic_module_name_of("-1.0.jar"), None);
+}
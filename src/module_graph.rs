@@ -0,0 +1,443 @@
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use crate::class_loader::{LoaderId, APPLICATION_LOADER, BOOTSTRAP_LOADER};
+use crate::module::{dotted_name_of, ModuleDescriptor, JAVA_BASE};
+use crate::module_path::{find_modules, ModuleReference};
+use crate::throwable::{Throwable, FIND_EXCEPTION, LAYER_INSTANTIATION_EXCEPTION};
+
+/// the target of `--add-exports`, `--add-opens` and `--add-reads` meaning every unnamed module
+pub const ALL_UNNAMED: &str = "ALL-UNNAMED";
+/// the value of `--add-modules` to resolve every module on the module path
+pub const ALL_MODULE_PATH: &str = "ALL-MODULE-PATH";
+
+/// Module options of the `java` launcher. the `--add-*` values are kept as given,
+/// such as `com.acme.greeting/com.acme.greeting.internal=com.acme.app,ALL-UNNAMED`.
+/// https://docs.oracle.com/en/java/javase/17/docs/specs/man/java.html#standard-options-for-java
+#[derive(Debug, Default)]
+pub struct ModuleOptions {
+    pub module_path: Vec<PathBuf>,
+    pub add_modules: Vec<String>,
+    pub add_exports: Vec<String>,
+    pub add_opens: Vec<String>,
+    pub add_reads: Vec<String>,
+}
+
+struct ResolvedModule {
+    reference: Rc<ModuleReference>,
+    /// the named modules this module reads, including the implied ones
+    reads: HashSet<String>,
+    reads_unnamed: bool,
+}
+
+/// The modules of the boot layer, resolved from the root modules.
+/// Every resolved module is defined to the application loader. the system modules aren't
+/// resolved, a class of the bootstrap loader is in the module of the run-time image,
+/// and without an image the classes of java/ packages are taken as java.base.
+/// A class of no named module is in the unnamed module, shown as `None` here,
+/// which reads every module and exports all of its packages.
+/// https://docs.oracle.com/en/java/javase/17/docs/api/java.base/java/lang/module/package-summary.html
+#[derive(Default)]
+pub struct ModuleGraph {
+    modules: HashMap<String, ResolvedModule>,
+    /// the module of each package
+    packages: HashMap<String, String>,
+    /// `(module, package, target)`, where target may be `ALL-UNNAMED`
+    added_exports: HashSet<(String, String, String)>,
+    added_opens: HashSet<(String, String, String)>,
+}
+
+impl ModuleGraph {
+    /// resolve `main_module` and `--add-modules` with the modules they require, transitively.
+    /// with no root module, the module path is not used at all.
+    pub fn resolve(options: &ModuleOptions, main_module: Option<&str>) -> Result<Self, Throwable> {
+        let mut graph = ModuleGraph::default();
+        let mut roots: Vec<String> = main_module.map(str::to_owned).into_iter().collect();
+        roots.extend(options.add_modules.iter().cloned());
+        if roots.is_empty() {
+            return Ok(graph);
+        }
+
+        let observable = find_modules(&options.module_path)?;
+        let find = |name: &str| observable.iter().find(|module| module.name() == name);
+        if roots.iter().any(|root| root == ALL_MODULE_PATH) {
+            roots.retain(|root| root != ALL_MODULE_PATH);
+            roots.extend(observable.iter().map(|module| module.name().to_owned()));
+        }
+
+        // breadth first from the roots, with the module requiring each one
+        let mut queue: Vec<(String, Option<String>)> =
+            roots.into_iter().map(|root| (root, None)).collect();
+        let mut resolved: Vec<Rc<ModuleReference>> = vec![];
+        while !queue.is_empty() {
+            let (name, required_by) = queue.remove(0);
+            if name == JAVA_BASE || resolved.iter().any(|module| module.name() == name) {
+                continue;
+            }
+            let Some(module) = find(&name) else {
+                let message = match required_by {
+                    Some(required_by) => {
+                        format!("Module {} not found, required by {}", name, required_by)
+                    }
+                    None => format!("Module {} not found", name),
+                };
+                return Err(Throwable::new(FIND_EXCEPTION, message));
+            };
+            let descriptor = &module.descriptor;
+            for requires in descriptor
+                .requires
+                .iter()
+                .filter(|requires| !requires.is_static)
+            {
+                queue.push((requires.name.clone(), Some(descriptor.name.clone())));
+            }
+            // an automatic module brings every other automatic module along
+            if descriptor.is_automatic {
+                for automatic in observable
+                    .iter()
+                    .filter(|other| other.descriptor.is_automatic)
+                {
+                    queue.push((automatic.name().to_owned(), Some(descriptor.name.clone())));
+                }
+            }
+            resolved.push(Rc::clone(module));
+        }
+
+        for module in &resolved {
+            for package in &module.descriptor.packages {
+                if let Some(other) = graph.packages.get(package) {
+                    return Err(Throwable::new(
+                        LAYER_INSTANTIATION_EXCEPTION,
+                        format!(
+                            "Package {} in both module {} and module {}",
+                            dotted_name_of(package),
+                            other,
+                            module.name()
+                        ),
+                    ));
+                }
+                graph
+                    .packages
+                    .insert(package.clone(), module.name().to_owned());
+            }
+        }
+        for module in &resolved {
+            let descriptor = &module.descriptor;
+            let reads = if descriptor.is_automatic {
+                resolved
+                    .iter()
+                    .map(|other| other.name().to_owned())
+                    .collect()
+            } else {
+                let mut reads = HashSet::new();
+                for requires in &descriptor.requires {
+                    if let Some(required) =
+                        resolved.iter().find(|other| other.name() == requires.name)
+                    {
+                        reads.insert(required.name().to_owned());
+                        reads.extend(implied_readability_of(&resolved, &required.descriptor));
+                    }
+                }
+                reads
+            };
+            graph.modules.insert(
+                descriptor.name.clone(),
+                ResolvedModule {
+                    reference: Rc::clone(module),
+                    reads,
+                    reads_unnamed: descriptor.is_automatic,
+                },
+            );
+        }
+
+        graph.add_reads(&options.add_reads);
+        graph.added_exports = graph.parse_added_exports("--add-exports", &options.add_exports);
+        graph.added_opens = graph.parse_added_exports("--add-opens", &options.add_opens);
+        Ok(graph)
+    }
+
+    /// the resolved modules, to be searched by the application loader
+    pub fn module_references(&self) -> Vec<Rc<ModuleReference>> {
+        self.modules
+            .values()
+            .map(|module| Rc::clone(&module.reference))
+            .collect()
+    }
+
+    pub fn descriptor(&self, module: &str) -> Option<&ModuleDescriptor> {
+        self.modules
+            .get(module)
+            .map(|module| &module.reference.descriptor)
+    }
+
+    /// the named module a class of `package` defined by `loader` belongs to,
+    /// for the bootstrap loader when it has no run-time image
+    pub fn module_of(&self, loader: LoaderId, package: &str) -> Option<&str> {
+        if loader == BOOTSTRAP_LOADER && package.starts_with("java/") {
+            return Some(JAVA_BASE);
+        }
+        if loader != APPLICATION_LOADER {
+            return None;
+        }
+        self.packages.get(package).map(String::as_str)
+    }
+
+    /// the unnamed module reads every module, and every module reads java.base
+    pub fn reads(&self, from: Option<&str>, to: Option<&str>) -> bool {
+        let Some(from) = from else {
+            return true;
+        };
+        if to == Some(from) || to == Some(JAVA_BASE) || from == JAVA_BASE {
+            return true;
+        }
+        let Some(module) = self.modules.get(from) else {
+            // a system module, whose module-info isn't read
+            return true;
+        };
+        match to {
+            Some(to) => module.reads.contains(to),
+            None => module.reads_unnamed,
+        }
+    }
+
+    /// `package` of `module` is exported to `to`, either by the module declaration or `--add-exports`
+    pub fn is_exported(&self, module: Option<&str>, package: &str, to: Option<&str>) -> bool {
+        self.is_exported_or_opened(module, package, to, false)
+    }
+
+    /// `package` of `module` is opened to `to` for deep reflection, which implies it is exported
+    pub fn is_opened(&self, module: Option<&str>, package: &str, to: Option<&str>) -> bool {
+        self.is_exported_or_opened(module, package, to, true)
+    }
+
+    fn is_exported_or_opened(
+        &self,
+        module: Option<&str>,
+        package: &str,
+        to: Option<&str>,
+        open: bool,
+    ) -> bool {
+        let Some(name) = module else {
+            return true;
+        };
+        if to == Some(name) {
+            return true;
+        }
+        let Some(descriptor) = self.descriptor(name) else {
+            // java.base, whose module-info isn't read
+            return !open;
+        };
+        if descriptor.is_automatic || (open && descriptor.is_open) {
+            return true;
+        }
+        let directives = if open {
+            &descriptor.opens
+        } else {
+            &descriptor.exports
+        };
+        let added = if open {
+            &self.added_opens
+        } else {
+            &self.added_exports
+        };
+        let target = to.unwrap_or(ALL_UNNAMED).to_owned();
+        directives
+            .iter()
+            .any(|directive| directive.is_to(package, to))
+            || added.contains(&(name.to_owned(), package.to_owned(), target))
+            || (!open && self.is_opened(module, package, to))
+    }
+
+    /// `--add-reads <module>=<target>(,<target>)*`
+    fn add_reads(&mut self, values: &[String]) {
+        for value in values {
+            let Some((source, targets)) = value.split_once('=') else {
+                eprintln!("WARNING: Unable to parse --add-reads {}", value);
+                continue;
+            };
+            let Some(module) = self.modules.get_mut(source) else {
+                eprintln!(
+                    "WARNING: Unknown module: {} specified to --add-reads",
+                    source
+                );
+                continue;
+            };
+            for target in targets.split(',') {
+                if target == ALL_UNNAMED {
+                    module.reads_unnamed = true;
+                } else {
+                    module.reads.insert(target.to_owned());
+                }
+            }
+        }
+    }
+
+    /// `<module>/<package>=<target>(,<target>)*`, for `--add-exports` and `--add-opens`
+    fn parse_added_exports(
+        &self,
+        option: &str,
+        values: &[String],
+    ) -> HashSet<(String, String, String)> {
+        let mut added = HashSet::new();
+        for value in values {
+            let parsed = value.split_once('=').and_then(|(source, targets)| {
+                let (module, package) = source.split_once('/')?;
+                Some((module, package, targets))
+            });
+            let Some((module, package, targets)) = parsed else {
+                eprintln!("WARNING: Unable to parse {} {}", option, value);
+                continue;
+            };
+            if !self.modules.contains_key(module) {
+                eprintln!(
+                    "WARNING: Unknown module: {} specified to {}",
+                    module, option
+                );
+                continue;
+            }
+            for target in targets.split(',') {
+                added.insert((
+                    module.to_owned(),
+                    package.replace('.', "/"),
+                    target.to_owned(),
+                ));
+            }
+        }
+        added
+    }
+}
+
+/// the modules required transitively, which a module reading `descriptor` also reads
+fn implied_readability_of(
+    resolved: &[Rc<ModuleReference>],
+    descriptor: &ModuleDescriptor,
+) -> HashSet<String> {
+    let mut implied = HashSet::new();
+    let mut pending = vec![descriptor];
+    while let Some(descriptor) = pending.pop() {
+        // reading an automatic module means reading all of them
+        let required: Vec<&Rc<ModuleReference>> = if descriptor.is_automatic {
+            resolved
+                .iter()
+                .filter(|module| module.descriptor.is_automatic)
+                .collect()
+        } else {
+            resolved
+                .iter()
+                .filter(|module| {
+                    descriptor
+                        .requires
+                        .iter()
+                        .any(|requires| requires.is_transitive && requires.name == module.name())
+                })
+                .collect()
+        };
+        for module in required {
+            if implied.insert(module.name().to_owned()) {
+                pending.push(&module.descriptor);
+            }
+        }
+    }
+    implied
+}
+
+/// `module com.acme.app` or `unnamed module`, as in the access error messages
+pub fn module_display_name(module: Option<&str>) -> String {
+    match module {
+        Some(name) => format!("module {}", name),
+        None => String::from("unnamed module"),
+    }
+}
+
+#[cfg(test)]
+fn test_module_options() -> ModuleOptions {
+    ModuleOptions {
+        module_path: vec![
+            PathBuf::from("java/modules/mods"),
+            PathBuf::from("java/modules/lib"),
+        ],
+        ..ModuleOptions::default()
+    }
+}
+
+#[test]
+fn test_resolve_module_graph() {
+    let graph = ModuleGraph::resolve(&test_module_options(), Some("com.acme.app")).unwrap();
+
+    let mut names: Vec<&str> = graph.modules.keys().map(String::as_str).collect();
+    names.sort();
+    assert_eq!(names, ["com.acme.app", "com.acme.greeting"]);
+    assert_eq!(
+        graph.module_of(APPLICATION_LOADER, "com/acme/greeting/api"),
+        Some("com.acme.greeting")
+    );
+    assert_eq!(
+        graph.module_of(BOOTSTRAP_LOADER, "java/lang"),
+        Some(JAVA_BASE)
+    );
+
+    let (app, greeting) = (Some("com.acme.app"), Some("com.acme.greeting"));
+    assert!(graph.reads(app, greeting));
+    assert!(!graph.reads(greeting, app));
+    assert!(!graph.reads(app, None));
+    assert!(graph.reads(None, app));
+
+    assert!(graph.is_exported(greeting, "com/acme/greeting/api", app));
+    assert!(!graph.is_exported(greeting, "com/acme/greeting/internal", None));
+    // opened to com.acme.app only, which also exports it at run time
+    assert!(graph.is_exported(greeting, "com/acme/greeting/internal", app));
+    assert!(graph.is_opened(greeting, "com/acme/greeting/internal", app));
+    assert!(!graph.is_opened(greeting, "com/acme/greeting/api", app));
+}
+
+#[test]
+fn test_resolve_automatic_module() {
+    let options = ModuleOptions {
+        add_modules: vec![String::from("legacy.util")],
+        add_reads: vec![String::from("com.acme.greeting=legacy.util")],
+        ..test_module_options()
+    };
+
+    let graph = ModuleGraph::resolve(&options, Some("com.acme.greeting")).unwrap();
+
+    let legacy = Some("legacy.util");
+    assert!(graph.reads(legacy, Some("com.acme.greeting")));
+    assert!(graph.reads(legacy, None));
+    assert!(graph.is_opened(legacy, "com/legacy", None));
+    assert!(graph.reads(Some("com.acme.greeting"), legacy));
+}
+
+#[test]
+fn test_resolve_missing_module() {
+    let options = ModuleOptions {
+        module_path: vec![PathBuf::from("java/modules/lib")],
+        ..ModuleOptions::default()
+    };
+
+    let result = ModuleGraph::resolve(&options, Some("com.acme.app"));
+
+    let error = result.err().unwrap();
+    assert!(error.is(FIND_EXCEPTION));
+    assert_eq!(error.message, "Module com.acme.app not found");
+}
+
+#[test]
+fn test_added_exports_and_opens() {
+    let options = ModuleOptions {
+        add_exports: vec![String::from(
+            "com.acme.greeting/com.acme.greeting.internal=ALL-UNNAMED",
+        )],
+        add_opens: vec![String::from(
+            "com.acme.greeting/com.acme.greeting.api=com.acme.app",
+        )],
+        ..test_module_options()
+    };
+
+    let graph = ModuleGraph::resolve(&options, Some("com.acme.app")).unwrap();
+
+    let greeting = Some("com.acme.greeting");
+    assert!(graph.is_exported(greeting, "com/acme/greeting/internal", None));
+    assert!(!graph.is_opened(greeting, "com/acme/greeting/internal", None));
+    assert!(graph.is_opened(greeting, "com/acme/greeting/api", Some("com.acme.app")));
+}
@@ -0,0 +1,221 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use crate::class::package_name_of;
+use crate::class_path::ClassPathEntry;
+use crate::jar::JarFile;
+use crate::module::{automatic_module_name_of, ModuleDescriptor, Provides, MODULE_INFO};
+use crate::service_loader::{provider_names_in, SERVICES_DIRECTORY};
+use crate::throwable::{Throwable, FIND_EXCEPTION};
+
+/// A module found on the module path, with the location of its class files.
+#[derive(Debug)]
+pub struct ModuleReference {
+    pub descriptor: ModuleDescriptor,
+    pub location: ClassPathEntry,
+}
+
+impl ModuleReference {
+    pub fn name(&self) -> &str {
+        &self.descriptor.name
+    }
+}
+
+/// Find the modules on the module path, as `ModuleFinder.of`.
+/// Each entry is a modular jar, an exploded module directory with `module-info.class`,
+/// or a directory of those. A plain jar is an automatic module.
+/// The first module of a name wins, when the same name is found more than once.
+/// https://docs.oracle.com/en/java/javase/17/docs/api/java.base/java/lang/module/ModuleFinder.html#of(java.nio.file.Path...)
+pub fn find_modules(module_path: &[PathBuf]) -> Result<Vec<Rc<ModuleReference>>, Throwable> {
+    let mut modules: Vec<Rc<ModuleReference>> = vec![];
+    for path in module_path {
+        let found = if is_jar(path) || path.join(module_info_file()).is_file() {
+            vec![open_module(path)?]
+        } else {
+            let mut children: Vec<PathBuf> = match fs::read_dir(path) {
+                Ok(entries) => entries
+                    .filter_map(|entry| Some(entry.ok()?.path()))
+                    .collect(),
+                Err(_e) => {
+                    println!(
+                        "[DEBUG] -- skip module path entry {}: {}",
+                        path.display(),
+                        _e
+                    );
+                    continue;
+                }
+            };
+            children.sort();
+            children
+                .iter()
+                .filter(|child| is_jar(child) || child.join(module_info_file()).is_file())
+                .map(|child| open_module(child))
+                .collect::<Result<_, _>>()?
+        };
+        for module in found {
+            if modules.iter().all(|other| other.name() != module.name()) {
+                modules.push(Rc::new(module));
+            }
+        }
+    }
+    Ok(modules)
+}
+
+fn open_module(path: &Path) -> Result<ModuleReference, Throwable> {
+    let find_error = |reason: String| {
+        Throwable::new(
+            FIND_EXCEPTION,
+            format!("Error reading module: {}: {}", path.display(), reason),
+        )
+    };
+
+    if !is_jar(path) {
+        let packages = packages_in_directory(path);
+        let binary =
+            fs::read(path.join(module_info_file())).map_err(|e| find_error(e.to_string()))?;
+        let descriptor = ModuleDescriptor::parse_from(&binary, packages).map_err(find_error)?;
+        return Ok(ModuleReference {
+            descriptor,
+            location: ClassPathEntry::Directory(path.to_path_buf()),
+        });
+    }
+
+    let jar = JarFile::open(path).map_err(|e| find_error(e.to_string()))?;
+    let packages = packages_in_jar(&jar);
+    let descriptor = match jar.read(&module_info_file()) {
+        Ok(binary) => ModuleDescriptor::parse_from(&binary, packages).map_err(find_error)?,
+        Err(_) => {
+            let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+            let name = match jar.manifest.main_attribute("Automatic-Module-Name") {
+                Some(name) => name.to_owned(),
+                None => automatic_module_name_of(&file_name).ok_or_else(|| {
+                    Throwable::new(
+                        FIND_EXCEPTION,
+                        format!("Unable to derive module descriptor for {}", path.display()),
+                    )
+                })?,
+            };
+            let main_class = jar
+                .manifest
+                .main_class()
+                .map(|main_class| main_class.replace('.', "/"));
+            let provides = services_in_jar(&jar, &packages).map_err(find_error)?;
+            ModuleDescriptor::automatic(name, packages, main_class, provides)
+        }
+    };
+    Ok(ModuleReference {
+        descriptor,
+        location: ClassPathEntry::Jar(jar),
+    })
+}
+
+fn module_info_file() -> String {
+    format!("{}.class", MODULE_INFO)
+}
+
+fn is_jar(path: &Path) -> bool {
+    path.is_file()
+        && path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("jar"))
+}
+
+/// packages of the class files in the jar. `META-INF` is never a package.
+fn packages_in_jar(jar: &JarFile) -> Vec<String> {
+    let mut packages = vec![];
+    for name in jar.archive.names() {
+        if name.ends_with(".class") && !name.starts_with("META-INF/") {
+            add_package_of(&mut packages, name);
+        }
+    }
+    packages
+}
+
+/// the providers listed in `META-INF/services` of an automatic module, which must be in it
+fn services_in_jar(jar: &JarFile, packages: &[String]) -> Result<Vec<Provides>, String> {
+    let mut provides = vec![];
+    for name in jar.archive.names() {
+        let Some(service) = name.strip_prefix(SERVICES_DIRECTORY) else {
+            continue;
+        };
+        if service.is_empty() || service.contains('/') {
+            continue;
+        }
+        let config = jar.read(name).map_err(|e| e.to_string())?;
+        let providers = provider_names_in(&String::from_utf8_lossy(&config));
+        if let Some(provider) = providers.iter().find(|provider| {
+            !packages
+                .iter()
+                .any(|package| package == package_name_of(provider))
+        }) {
+            return Err(format!("Provider class {} not in module", provider));
+        }
+        if !providers.is_empty() {
+            provides.push(Provides {
+                service: service.replace('.', "/"),
+                providers,
+            });
+        }
+    }
+    Ok(provides)
+}
+
+fn packages_in_directory(root: &Path) -> Vec<String> {
+    let mut packages = vec![];
+    let mut directories = vec![root.to_path_buf()];
+    while let Some(directory) = directories.pop() {
+        let Ok(entries) = fs::read_dir(&directory) else {
+            continue;
+        };
+        for path in entries.filter_map(|entry| Some(entry.ok()?.path())) {
+            if path.is_dir() {
+                directories.push(path);
+            } else if path
+                .extension()
+                .is_some_and(|extension| extension == "class")
+            {
+                if let Ok(relative) = path.strip_prefix(root) {
+                    add_package_of(&mut packages, &relative.to_string_lossy());
+                }
+            }
+        }
+    }
+    packages.sort();
+    packages
+}
+
+/// classes in the unnamed package, such as `module-info.class`, aren't in any package
+fn add_package_of(packages: &mut Vec<String>, class_file_name: &str) {
+    let package = package_name_of(class_file_name);
+    if !package.is_empty() && !packages.iter().any(|name| name == package) {
+        packages.push(package.to_owned());
+    }
+}
+
+#[test]
+fn test_find_modules() {
+    let module_path = [
+        PathBuf::from("java/modules/mods"),
+        PathBuf::from("java/modules/lib/legacy-util-1.0.jar"),
+    ];
+
+    let result = find_modules(&module_path).unwrap();
+
+    let names: Vec<&str> = result.iter().map(|module| module.name()).collect();
+    assert_eq!(names, ["com.acme.app", "com.acme.greeting", "legacy.util"]);
+
+    // a modular jar packaged by the jar tool, with ModulePackages and ModuleMainClass
+    let app = &result[0].descriptor;
+    assert_eq!(app.packages, ["com/acme/app"]);
+    assert_eq!(app.main_class.as_deref(), Some("com/acme/app/Main"));
+    assert!(result[0]
+        .location
+        .read_file("com/acme/app/Main.class")
+        .unwrap()
+        .is_some());
+
+    let legacy = &result[2].descriptor;
+    assert!(legacy.is_automatic);
+    assert_eq!(legacy.packages, ["com/legacy"]);
+}
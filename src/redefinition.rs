@@ -0,0 +1,188 @@
+use std::rc::Rc;
+#[cfg(test)]
+use std::sync::Arc;
+
+use crate::class::Class;
+use crate::class_loader::define_class;
+use crate::jvm::JVM;
+use crate::throwable::{Throwable, NO_CLASS_DEF_FOUND_ERROR, UNSUPPORTED_OPERATION_EXCEPTION};
+#[cfg(test)]
+use crate::value::Value;
+
+/// Replace the methods of a loaded class with the ones of a new version of its class file,
+/// as `Instrumentation.redefineClasses`. only the method bodies and the constant pool may change,
+/// a change of the superclass, interfaces, fields, methods or modifiers is rejected.
+/// frames running the old methods keep them with the old constant pool, while invocations
+/// resolved before are adjusted to run the new methods from then on.
+/// the values of the static fields are kept, and `<clinit>` isn't run again.
+/// the registered transformers see the new class file first.
+/// https://docs.oracle.com/en/java/javase/17/docs/specs/jvmti.html#RedefineClasses
+pub fn redefine_class(vm: &JVM, class: &Rc<Class>, binary: &[u8]) -> Result<(), Throwable> {
+    let loader = class.defining_loader;
+    let transformed = vm.transform_class_file(loader, &class.descriptor, Some(class), binary);
+    let binary = transformed.as_deref().unwrap_or(binary);
+    let new_class = define_class(loader, binary, |name| vm.load_class(loader, name))?;
+    check_redefinition(class, &new_class)?;
+
+    println!("[DEBUG] -- redefine class: {}", class.descriptor);
+    let old_constant_pool = class.runtime_constant_pool();
+    *class.data.borrow_mut() = new_class.data.into_inner();
+    *class.runtime_constant_pool.borrow_mut() = new_class.runtime_constant_pool.into_inner();
+
+    // the old constant pool is still used by the frames running the old methods
+    old_constant_pool.adjust_method_entries(class);
+    for loaded in vm.loaded_classes() {
+        loaded.runtime_constant_pool().adjust_method_entries(class);
+    }
+    Ok(())
+}
+
+/// the restrictions of RedefineClasses, with the messages of `Instrumentation.redefineClasses`
+fn check_redefinition(class: &Class, new_class: &Class) -> Result<(), Throwable> {
+    if new_class.descriptor != class.descriptor {
+        return Err(Throwable::new(
+            NO_CLASS_DEF_FOUND_ERROR,
+            format!(
+                "{} (wrong name: {})",
+                class.descriptor, new_class.descriptor
+            ),
+        ));
+    }
+
+    let names_of = |classes: &[Rc<Class>]| -> Vec<String> {
+        classes
+            .iter()
+            .map(|class| class.descriptor.clone())
+            .collect()
+    };
+    let super_class_name = |class: &Class| {
+        class
+            .super_class
+            .as_ref()
+            .map(|super_class| super_class.descriptor.clone())
+    };
+    if super_class_name(class) != super_class_name(new_class)
+        || names_of(&class.interfaces) != names_of(&new_class.interfaces)
+    {
+        return Err(unsupported("attempted to change superclass or interfaces"));
+    }
+    if class.access_flags != new_class.access_flags {
+        return Err(unsupported("attempted to change the class modifiers"));
+    }
+    let (data, new_data) = (class.data(), new_class.data());
+    if data.nest_host != new_data.nest_host || data.nest_members != new_data.nest_members {
+        return Err(unsupported(
+            "attempted to change the class NestHost, NestMembers, PermittedSubclasses, or Record attribute",
+        ));
+    }
+
+    let fields_changed = data.fields.len() != new_data.fields.len()
+        || data.fields.iter().any(|(name_and_descriptor, field)| {
+            new_data
+                .fields
+                .get(name_and_descriptor)
+                .is_none_or(|new_field| new_field.access_flags != field.access_flags)
+        });
+    if fields_changed {
+        return Err(unsupported(
+            "attempted to change the schema (add/remove fields)",
+        ));
+    }
+
+    let (methods, new_methods) = (&data.methods, &new_data.methods);
+    if new_methods.keys().any(|id| !methods.contains_key(id)) {
+        return Err(unsupported("attempted to add a method"));
+    }
+    if methods.keys().any(|id| !new_methods.contains_key(id)) {
+        return Err(unsupported("attempted to delete a method"));
+    }
+    if methods
+        .iter()
+        .any(|(id, method)| new_methods[id].access_flags != method.access_flags)
+    {
+        return Err(unsupported("attempted to change method modifiers"));
+    }
+    Ok(())
+}
+
+fn unsupported(reason: &str) -> Throwable {
+    Throwable::new(
+        UNSUPPORTED_OPERATION_EXCEPTION,
+        format!("class redefinition failed: {}", reason),
+    )
+}
+
+#[cfg(test)]
+fn run_caller(vm: &JVM, caller: &Rc<Class>, pc: u64, callee: Option<crate::thread::Frame>) {
+    use crate::interpreter::interpret;
+    use crate::thread::{Frame, Thread};
+
+    let mut frame = Frame::create(caller, &caller.method("run:()V").unwrap());
+    frame.pc = pc;
+    let mut thread = Thread::create();
+    thread.java_virtual_machine_stack.push(frame);
+    thread.java_virtual_machine_stack.extend(callee);
+    interpret(vm, &mut thread).unwrap();
+}
+
+#[test]
+fn test_redefine_method_body() {
+    use crate::binary::read_binary_file;
+    use crate::thread::Frame;
+
+    // Caller.run() is `result = Greeting.value();`, which returns 1, and 2 after the redefinition
+    let vm = JVM::create_with_class_path("java/redefine");
+    let caller = vm.resolve_class("Caller").unwrap();
+    run_caller(&vm, &caller, 0, None);
+    assert_eq!(caller.static_value("result:I"), Some(Value::Int(1)));
+
+    let greeting = vm.resolve_class("Greeting").unwrap();
+    let old_method = greeting.method("value:()I").unwrap();
+    let running = Frame::create(&greeting, &old_method);
+    let binary = read_binary_file(&"java/redefine/v2/Greeting.class".to_owned()).unwrap();
+
+    redefine_class(&vm, &greeting, &binary).unwrap();
+
+    // iconst_2, ireturn
+    let new_method = greeting.method("value:()I").unwrap();
+    assert_eq!(new_method.get_code_attribute().code, [0x05, 0xac]);
+    // the invocation resolved before runs the new code
+    run_caller(&vm, &caller, 0, None);
+    assert_eq!(caller.static_value("result:I"), Some(Value::Int(2)));
+
+    // the frame running the old method finishes it, then Caller.run() stores its value at pc 3
+    assert!(Arc::ptr_eq(&running.current_method, &old_method));
+    assert!(!Rc::ptr_eq(
+        &running.constant_pool,
+        &greeting.runtime_constant_pool()
+    ));
+    run_caller(&vm, &caller, 3, Some(running));
+    assert_eq!(caller.static_value("result:I"), Some(Value::Int(1)));
+}
+
+#[test]
+fn test_reject_redefinition_of_schema() {
+    use crate::binary::read_binary_file;
+
+    let vm = JVM::create_with_class_path("java/redefine");
+    let greeting = vm.resolve_class("Greeting").unwrap();
+    let old_method = greeting.method("value:()I").unwrap();
+
+    let added = read_binary_file(&"java/redefine/added/Greeting.class".to_owned()).unwrap();
+    let result = redefine_class(&vm, &greeting, &added).unwrap_err();
+
+    assert!(result.is(UNSUPPORTED_OPERATION_EXCEPTION));
+    assert_eq!(
+        result.message,
+        "class redefinition failed: attempted to add a method"
+    );
+    assert!(Arc::ptr_eq(
+        &greeting.method("value:()I").unwrap(),
+        &old_method
+    ));
+    assert!(greeting.method("other:()I").is_none());
+
+    let other = read_binary_file(&"java/redefine/Caller.class".to_owned()).unwrap();
+    let result = redefine_class(&vm, &greeting, &other).unwrap_err();
+    assert!(result.is(NO_CLASS_DEF_FOUND_ERROR));
+}
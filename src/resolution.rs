@@ -0,0 +1,356 @@
+use std::rc::Rc;
+use std::sync::Arc;
+
+use crate::class::Class;
+use crate::class_attributes::MethodInfo;
+use crate::class_file::{
+    ACC_ABSTRACT, ACC_NATIVE, ACC_PRIVATE, ACC_PUBLIC, ACC_STATIC, ACC_VARARGS,
+};
+#[cfg(test)]
+use crate::jvm::JVM;
+#[cfg(test)]
+use crate::throwable::ABSTRACT_METHOD_ERROR;
+use crate::throwable::{
+    Throwable, INCOMPATIBLE_CLASS_CHANGE_ERROR, NO_SUCH_FIELD_ERROR, NO_SUCH_METHOD_ERROR,
+};
+
+/// classes declaring signature polymorphic methods, JVMS 2.9.3
+const SIGNATURE_POLYMORPHIC_CLASSES: [&str; 2] = [
+    "java/lang/invoke/MethodHandle",
+    "java/lang/invoke/VarHandle",
+];
+const SIGNATURE_POLYMORPHIC_DESCRIPTOR: &str = "([Ljava/lang/Object;)Ljava/lang/Object;";
+
+/// a method found by the resolution, with the class declaring it
+pub type ResolvedMethod = (Rc<Class>, Arc<MethodInfo>);
+
+/// Field resolution, JVMS 5.4.3.2.
+/// searches the class, then its direct superinterfaces, then its superclass, recursively.
+/// returns the class declaring the field.
+/// https://docs.oracle.com/javase/specs/jvms/se17/html/jvms-5.html#jvms-5.4.3.2
+pub fn resolve_field(class: &Rc<Class>, name_and_descriptor: &str) -> Result<Rc<Class>, Throwable> {
+    find_field(class, name_and_descriptor).ok_or_else(|| {
+        let (name, _) = name_and_descriptor.split_once(':').unwrap();
+        Throwable::new(NO_SUCH_FIELD_ERROR, name.to_owned())
+    })
+}
+
+fn find_field(class: &Rc<Class>, name_and_descriptor: &str) -> Option<Rc<Class>> {
+    if class.data().fields.contains_key(name_and_descriptor) {
+        return Some(Rc::clone(class));
+    }
+    class
+        .interfaces
+        .iter()
+        .find_map(|interface| find_field(interface, name_and_descriptor))
+        .or_else(|| {
+            class
+                .super_class
+                .as_ref()
+                .and_then(|super_class| find_field(super_class, name_and_descriptor))
+        })
+}
+
+/// Method resolution, JVMS 5.4.3.3.
+/// searches the class and its superclasses, then the maximally-specific superinterface methods.
+/// https://docs.oracle.com/javase/specs/jvms/se17/html/jvms-5.html#jvms-5.4.3.3
+pub fn resolve_method(
+    class: &Rc<Class>,
+    name: &str,
+    descriptor: &str,
+) -> Result<ResolvedMethod, Throwable> {
+    if class.is_interface() {
+        return Err(Throwable::new(
+            INCOMPATIBLE_CLASS_CHANGE_ERROR,
+            format!(
+                "Found interface {}, but class was expected",
+                class.descriptor
+            ),
+        ));
+    }
+    let name_and_descriptor = format!("{}:{}", name, descriptor);
+
+    let mut current = Some(class);
+    while let Some(candidate) = current {
+        if let Some(method) = signature_polymorphic_method(candidate, name) {
+            return Ok((Rc::clone(candidate), method));
+        }
+        if let Some(method) = candidate.method(&name_and_descriptor) {
+            return Ok((Rc::clone(candidate), method));
+        }
+        current = candidate.super_class.as_ref();
+    }
+
+    find_superinterface_method(class, &name_and_descriptor)
+        .ok_or_else(|| no_such_method_error(class, &name_and_descriptor))
+}
+
+/// Interface method resolution, JVMS 5.4.3.4.
+/// searches the interface, then public instance methods of java/lang/Object,
+/// then the maximally-specific superinterface methods.
+/// https://docs.oracle.com/javase/specs/jvms/se17/html/jvms-5.html#jvms-5.4.3.4
+pub fn resolve_interface_method(
+    interface: &Rc<Class>,
+    name: &str,
+    descriptor: &str,
+) -> Result<ResolvedMethod, Throwable> {
+    if !interface.is_interface() {
+        return Err(Throwable::new(
+            INCOMPATIBLE_CLASS_CHANGE_ERROR,
+            format!(
+                "Found class {}, but interface was expected",
+                interface.descriptor
+            ),
+        ));
+    }
+    let name_and_descriptor = format!("{}:{}", name, descriptor);
+
+    if let Some(method) = interface.method(&name_and_descriptor) {
+        return Ok((Rc::clone(interface), method));
+    }
+    // the superclass of an interface is always java/lang/Object
+    if let Some(object) = &interface.super_class {
+        if let Some(method) = object.method(&name_and_descriptor) {
+            if method.access_flags & (ACC_PUBLIC | ACC_STATIC) == ACC_PUBLIC {
+                return Ok((Rc::clone(object), method));
+            }
+        }
+    }
+
+    find_superinterface_method(interface, &name_and_descriptor)
+        .ok_or_else(|| no_such_method_error(interface, &name_and_descriptor))
+}
+
+/// Method selection for invokevirtual and invokeinterface, JVMS 5.4.6.
+/// finds the method overriding `resolved` in the class of the receiver, its superclasses,
+/// or the maximally-specific superinterface methods.
+/// https://docs.oracle.com/javase/specs/jvms/se17/html/jvms-5.html#jvms-5.4.6
+#[cfg(test)]
+pub fn select_method(
+    receiver_class: &Rc<Class>,
+    resolved: &ResolvedMethod,
+    name_and_descriptor: &str,
+) -> Result<ResolvedMethod, Throwable> {
+    let (_, resolved_method) = resolved;
+    if resolved_method.access_flags & ACC_PRIVATE != 0 {
+        return Ok(resolved.clone());
+    }
+
+    let mut current = Some(receiver_class);
+    while let Some(candidate) = current {
+        if let Some(method) = candidate.method(name_and_descriptor) {
+            if method.access_flags & ACC_STATIC == 0 {
+                return ensure_implemented(receiver_class, candidate, &method, name_and_descriptor);
+            }
+        }
+        current = candidate.super_class.as_ref();
+    }
+
+    let candidates = maximally_specific_methods(receiver_class, name_and_descriptor);
+    let mut non_abstract = candidates.iter().filter(|(_, method)| !is_abstract(method));
+    match (non_abstract.next(), non_abstract.next()) {
+        (Some((class, method)), None) => Ok((Rc::clone(class), Arc::clone(method))),
+        (Some(_), Some(_)) => Err(Throwable::new(
+            INCOMPATIBLE_CLASS_CHANGE_ERROR,
+            format!(
+                "Conflicting default methods: {} in {}",
+                name_and_descriptor, receiver_class.descriptor
+            ),
+        )),
+        (None, _) => Err(abstract_method_error(receiver_class, name_and_descriptor)),
+    }
+}
+
+#[cfg(test)]
+fn ensure_implemented(
+    receiver_class: &Rc<Class>,
+    class: &Rc<Class>,
+    method: &Arc<MethodInfo>,
+    name_and_descriptor: &str,
+) -> Result<ResolvedMethod, Throwable> {
+    if is_abstract(method) {
+        return Err(abstract_method_error(receiver_class, name_and_descriptor));
+    }
+    Ok((Rc::clone(class), Arc::clone(method)))
+}
+
+/// the only non-abstract maximally-specific method if any, or else any of the superinterface
+/// methods which are neither private nor static.
+fn find_superinterface_method(
+    class: &Rc<Class>,
+    name_and_descriptor: &str,
+) -> Option<ResolvedMethod> {
+    let candidates = maximally_specific_methods(class, name_and_descriptor);
+    let mut non_abstract = candidates.iter().filter(|(_, method)| !is_abstract(method));
+    if let (Some(found), None) = (non_abstract.next(), non_abstract.next()) {
+        return Some(found.clone());
+    }
+    candidates.into_iter().next()
+}
+
+/// instance methods declared in the superinterfaces of `class`, which are not overridden by
+/// any other of them in a subinterface, JVMS 5.4.3.3.
+fn maximally_specific_methods(class: &Rc<Class>, name_and_descriptor: &str) -> Vec<ResolvedMethod> {
+    let candidates: Vec<ResolvedMethod> = superinterfaces_of(class)
+        .into_iter()
+        .filter_map(|interface| {
+            let method = interface.method(name_and_descriptor)?;
+            (method.access_flags & (ACC_PRIVATE | ACC_STATIC) == 0).then_some((interface, method))
+        })
+        .collect();
+
+    candidates
+        .iter()
+        .filter(|(interface, _)| {
+            !candidates
+                .iter()
+                .any(|(other, _)| other.implements(interface))
+        })
+        .cloned()
+        .collect()
+}
+
+/// direct and indirect superinterfaces of the class and its superclasses, without duplicates
+pub fn superinterfaces_of(class: &Rc<Class>) -> Vec<Rc<Class>> {
+    let mut superinterfaces: Vec<Rc<Class>> = vec![];
+    let mut pending: Vec<Rc<Class>> = vec![];
+    let mut current = Some(class);
+    while let Some(candidate) = current {
+        pending.extend(candidate.interfaces.iter().map(Rc::clone));
+        current = candidate.super_class.as_ref();
+    }
+    while let Some(interface) = pending.pop() {
+        if superinterfaces
+            .iter()
+            .any(|found| Rc::ptr_eq(found, &interface))
+        {
+            continue;
+        }
+        pending.extend(interface.interfaces.iter().map(Rc::clone));
+        superinterfaces.push(interface);
+    }
+    superinterfaces
+}
+
+/// the native varargs method of MethodHandle or VarHandle, which accepts any descriptor
+fn signature_polymorphic_method(class: &Class, name: &str) -> Option<Arc<MethodInfo>> {
+    if !SIGNATURE_POLYMORPHIC_CLASSES.contains(&class.descriptor.as_str()) {
+        return None;
+    }
+    let method = class.method(&format!("{}:{}", name, SIGNATURE_POLYMORPHIC_DESCRIPTOR))?;
+    let flags = ACC_VARARGS | ACC_NATIVE;
+    (method.access_flags & flags == flags).then_some(method)
+}
+
+fn is_abstract(method: &MethodInfo) -> bool {
+    method.access_flags & ACC_ABSTRACT != 0
+}
+
+fn no_such_method_error(class: &Class, name_and_descriptor: &str) -> Throwable {
+    Throwable::new(
+        NO_SUCH_METHOD_ERROR,
+        format!("'{}' in {}", name_and_descriptor, class.descriptor),
+    )
+}
+
+#[cfg(test)]
+fn abstract_method_error(receiver_class: &Class, name_and_descriptor: &str) -> Throwable {
+    Throwable::new(
+        ABSTRACT_METHOD_ERROR,
+        format!(
+            "Receiver class {} does not define or inherit an implementation of the resolved method '{}'",
+            receiver_class.descriptor, name_and_descriptor
+        ),
+    )
+}
+
+#[test]
+fn test_resolve_field() {
+    // see java/Resolution.java
+    let vm = JVM::create_with_class_path("java");
+    let derived = vm.resolve_class("Derived").unwrap();
+
+    assert_eq!(
+        resolve_field(&derived, "size:I").unwrap().descriptor,
+        "Derived"
+    );
+    assert_eq!(
+        resolve_field(&derived, "LIMIT:I").unwrap().descriptor,
+        "Counted"
+    );
+    assert_eq!(
+        resolve_field(&derived, "total:I").unwrap().descriptor,
+        "Base"
+    );
+    assert!(resolve_field(&derived, "total:J")
+        .unwrap_err()
+        .is(NO_SUCH_FIELD_ERROR));
+}
+
+#[test]
+fn test_resolve_method() {
+    let vm = JVM::create_with_class_path("java");
+    let derived = vm.resolve_class("Derived").unwrap();
+    let named = vm.resolve_class("Named").unwrap();
+
+    let (class, _) = resolve_method(&derived, "base", "()I").unwrap();
+    assert_eq!(class.descriptor, "Base");
+    // Renamed.name overrides Named.name
+    let (class, _) = resolve_method(&derived, "name", "()I").unwrap();
+    assert_eq!(class.descriptor, "Renamed");
+    let (class, method) = resolve_method(&derived, "count", "()I").unwrap();
+    assert_eq!(class.descriptor, "Counted");
+    assert!(is_abstract(&method));
+
+    assert!(resolve_method(&derived, "count", "()J")
+        .unwrap_err()
+        .is(NO_SUCH_METHOD_ERROR));
+    assert!(resolve_method(&named, "name", "()I")
+        .unwrap_err()
+        .is(INCOMPATIBLE_CLASS_CHANGE_ERROR));
+}
+
+#[test]
+fn test_resolve_signature_polymorphic_method() {
+    // java/polymorphic has a MethodHandle with `invoke` only
+    let vm = JVM::create_with_class_path("java/polymorphic");
+    let method_handle = vm.resolve_class("java/lang/invoke/MethodHandle").unwrap();
+
+    let (class, method) = resolve_method(&method_handle, "invoke", "(II)I").unwrap();
+
+    assert!(Rc::ptr_eq(&class, &method_handle));
+    assert_ne!(method.access_flags & ACC_NATIVE, 0);
+}
+
+#[test]
+fn test_resolve_interface_method() {
+    let vm = JVM::create_with_class_path("java");
+    let renamed = vm.resolve_class("Renamed").unwrap();
+    let derived = vm.resolve_class("Derived").unwrap();
+
+    let (class, _) = resolve_interface_method(&renamed, "name", "()I").unwrap();
+    assert!(Rc::ptr_eq(&class, &renamed));
+
+    assert!(resolve_interface_method(&renamed, "count", "()I")
+        .unwrap_err()
+        .is(NO_SUCH_METHOD_ERROR));
+    assert!(resolve_interface_method(&derived, "name", "()I")
+        .unwrap_err()
+        .is(INCOMPATIBLE_CLASS_CHANGE_ERROR));
+}
+
+#[test]
+fn test_select_method() {
+    let vm = JVM::create_with_class_path("java");
+    let derived = vm.resolve_class("Derived").unwrap();
+    let named = vm.resolve_class("Named").unwrap();
+
+    let resolved = resolve_interface_method(&named, "name", "()I").unwrap();
+    let (class, _) = select_method(&derived, &resolved, "name:()I").unwrap();
+    assert_eq!(class.descriptor, "Renamed");
+
+    let resolved = resolve_method(&derived, "count", "()I").unwrap();
+    assert!(select_method(&derived, &resolved, "count:()I")
+        .unwrap_err()
+        .is(ABSTRACT_METHOD_ERROR));
+}
@@ -0,0 +1,448 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use crate::access::{check_class_access, check_member_access};
+use crate::class::{Class, FieldRef, MethodRef};
+use crate::class_attributes::MethodInfo;
+use crate::cp_info::{constant_pool_value_at, long_bits_of, CpInfo};
+use crate::jvm::JVM;
+use crate::resolution::{resolve_field, resolve_interface_method, resolve_method};
+use crate::thread::Thread;
+use crate::throwable::{Throwable, UNSUPPORTED_OPERATION_EXCEPTION};
+use crate::value::Value;
+
+/// A symbolic reference or a constant value of the run-time constant pool, JVMS 5.1.
+/// https://docs.oracle.com/javase/specs/jvms/se17/html/jvms-5.html#jvms-5.1
+#[derive(Debug)]
+pub enum Constant {
+    /// the index 0, and the entry following a long or double
+    Unusable,
+    Utf8(String),
+    Integer(i32),
+    Float(f32),
+    Long(i64),
+    Double(f64),
+    Class(String),
+    String(String),
+    FieldRef(FieldRef),
+    MethodRef(MethodRef),
+    InterfaceMethodRef(MethodRef),
+    NameAndType(String),
+    MethodHandle {
+        reference_kind: u8,
+        reference_index: u16,
+    },
+    MethodType(String),
+    /// `#<bootstrap method>:<name>:<descriptor>`, bootstrap methods can't run yet
+    Dynamic(String),
+    InvokeDynamic(String),
+    Module(String),
+    Package(String),
+}
+
+/// The result of resolving an entry, which later executions use as is.
+#[derive(Debug, Clone)]
+pub enum ResolvedConstant {
+    Integer(i32),
+    Float(f32),
+    Long(i64),
+    Double(f64),
+    Class(Rc<Class>),
+    /// there is no heap yet, then a string is its value itself rather than an interned object
+    String(String),
+    /// the field with the class declaring it
+    Field {
+        class: Rc<Class>,
+        name_and_descriptor: String,
+    },
+    /// the method with the class declaring it.
+    /// `name_and_descriptor` is of the call site, which differs for signature polymorphic methods.
+    Method {
+        class: Rc<Class>,
+        method: Arc<MethodInfo>,
+        name_and_descriptor: String,
+    },
+    MethodType(String),
+    MethodHandle {
+        reference_kind: u8,
+        target: Box<ResolvedConstant>,
+    },
+}
+
+/// the constants of a class file, where `constants[0]` is never referred
+pub fn constants_of(constant_pool: &Vec<CpInfo>) -> Vec<Constant> {
+    let mut constants = vec![Constant::Unusable];
+    for (i, cp_info) in constant_pool.iter().enumerate() {
+        let index = i as u16 + 1;
+        let value = || constant_pool_value_at(constant_pool, index);
+        let constant = match cp_info {
+            CpInfo::Unusable => Constant::Unusable,
+            CpInfo::ConstantUtf8 { bytes, .. } => Constant::Utf8(bytes.clone()),
+            CpInfo::ConstantInteger { bytes, .. } => Constant::Integer(*bytes as i32),
+            CpInfo::ConstantFloat { bytes, .. } => Constant::Float(f32::from_bits(*bytes)),
+            CpInfo::ConstantLong {
+                high_bytes,
+                low_bytes,
+                ..
+            } => Constant::Long(long_bits_of(*high_bytes, *low_bytes)),
+            CpInfo::ConstantDouble {
+                high_bytes,
+                low_bytes,
+                ..
+            } => Constant::Double(f64::from_bits(long_bits_of(*high_bytes, *low_bytes) as u64)),
+            CpInfo::ConstantClassInfo { .. } => Constant::Class(value()),
+            CpInfo::ConstantString { .. } => Constant::String(value()),
+            CpInfo::ConstantFieldref { .. } => Constant::FieldRef(FieldRef::parse_from(value())),
+            CpInfo::ConstantMethodRef { .. } => Constant::MethodRef(MethodRef::parse_from(value())),
+            CpInfo::ConstantInterfaceMethodRef { .. } => {
+                Constant::InterfaceMethodRef(MethodRef::parse_from(value()))
+            }
+            CpInfo::ConstantNameAndType { .. } => Constant::NameAndType(value()),
+            CpInfo::ConstantMethodHandle {
+                reference_kind,
+                reference_index,
+                ..
+            } => Constant::MethodHandle {
+                reference_kind: *reference_kind,
+                reference_index: *reference_index,
+            },
+            CpInfo::ConstantMethodType { .. } => Constant::MethodType(value()),
+            CpInfo::ConstantDynamic { .. } => Constant::Dynamic(value()),
+            CpInfo::ConstantInvokeDynamic { .. } => Constant::InvokeDynamic(value()),
+            CpInfo::ConstantModule { .. } => Constant::Module(value()),
+            CpInfo::ConstantPackage { .. } => Constant::Package(value()),
+        };
+        constants.push(constant);
+    }
+    constants
+}
+
+/// Per-class constant pool, where each entry is resolved lazily on the first execution
+/// of an instruction referring to it.
+/// Both the resolved value and the error of a failed resolution are cached, JVMS 5.4.3.
+/// The constants are shared with the other JVMs running the same class file,
+/// while the resolved entries are of this JVM.
+#[derive(Debug)]
+pub struct RuntimeConstantPool {
+    constants: Arc<Vec<Constant>>,
+    resolved: RefCell<Vec<Option<Result<ResolvedConstant, Throwable>>>>,
+}
+
+impl RuntimeConstantPool {
+    pub fn create(constants: &Arc<Vec<Constant>>) -> RuntimeConstantPool {
+        let resolved = RefCell::new(vec![None; constants.len()]);
+        RuntimeConstantPool {
+            constants: Arc::clone(constants),
+            resolved,
+        }
+    }
+
+    pub fn constant_at(&self, index: u16) -> &Constant {
+        self.constants.get(index as usize).unwrap_or_else(|| {
+            panic!(
+                "constant_pool out of bounds: cp size {}, given index {}",
+                self.constants.len(),
+                index
+            )
+        })
+    }
+
+    pub fn resolved_at(&self, index: u16) -> Option<Result<ResolvedConstant, Throwable>> {
+        self.resolved.borrow()[index as usize].clone()
+    }
+
+    #[cfg(test)]
+    pub fn len(&self) -> usize {
+        self.constants.len()
+    }
+
+    /// point the resolved methods of the redefined `class` to their new versions,
+    /// then the next invocation through this pool runs the new code.
+    pub fn adjust_method_entries(&self, class: &Rc<Class>) {
+        for resolved in self.resolved.borrow_mut().iter_mut().flatten().flatten() {
+            adjust_method_entry(resolved, class);
+        }
+    }
+}
+
+fn adjust_method_entry(resolved: &mut ResolvedConstant, redefined: &Rc<Class>) {
+    match resolved {
+        ResolvedConstant::Method {
+            class,
+            method,
+            name_and_descriptor,
+        } if Rc::ptr_eq(class, redefined) => {
+            if let Some(new_method) = redefined.method(name_and_descriptor) {
+                *method = new_method;
+            }
+        }
+        ResolvedConstant::MethodHandle { target, .. } => adjust_method_entry(target, redefined),
+        _ => {}
+    }
+}
+
+/// resolve the entry at `index` in the run-time constant pool of `class`.
+/// the entry is resolved only once, and its result or error is returned from then on.
+#[cfg(test)]
+pub fn resolve_constant(
+    vm: &JVM,
+    class: &Rc<Class>,
+    index: u16,
+) -> Result<ResolvedConstant, Throwable> {
+    resolve_constant_in(vm, class, &class.runtime_constant_pool(), index)
+}
+
+/// resolve the entry of `pool`, which is of `class` but may be of a version before its redefinition
+pub fn resolve_constant_in(
+    vm: &JVM,
+    class: &Rc<Class>,
+    pool: &RuntimeConstantPool,
+    index: u16,
+) -> Result<ResolvedConstant, Throwable> {
+    if let Some(resolved) = pool.resolved_at(index) {
+        return resolved;
+    }
+
+    let result = resolve_symbolic_reference(vm, class, pool, index);
+    pool.resolved.borrow_mut()[index as usize] = Some(result.clone());
+    result
+}
+
+/// `ldc`, `ldc_w` and `ldc2_w` push the resolved constant at `cp_index`, JVMS 6.5.ldc.
+/// there is no heap yet, then a String, a class or a method type or handle can't be pushed,
+/// and raises UnsupportedOperationException once resolved.
+pub fn load_constant(vm: &JVM, thread: &mut Thread, cp_index: u16) -> Result<(), Throwable> {
+    let current_frame = thread.java_virtual_machine_stack.last_mut().unwrap();
+    let resolved = resolve_constant_in(
+        vm,
+        &current_frame.context,
+        &current_frame.constant_pool,
+        cp_index,
+    )?;
+    let unsupported = |constant: String| {
+        Err(Throwable::new(
+            UNSUPPORTED_OPERATION_EXCEPTION,
+            format!(
+                "ldc of {} needs a heap, which isn't supported yet",
+                constant
+            ),
+        ))
+    };
+    let value = match resolved {
+        ResolvedConstant::Integer(value) => Value::Int(value),
+        ResolvedConstant::Float(value) => Value::Float(value),
+        ResolvedConstant::Long(value) => Value::Long(value),
+        ResolvedConstant::Double(value) => Value::Double(value),
+        ResolvedConstant::String(value) => return unsupported(format!("the String {:?}", value)),
+        ResolvedConstant::Class(class) => {
+            return unsupported(format!("the class {}", class.descriptor))
+        }
+        ResolvedConstant::MethodType(descriptor) => {
+            return unsupported(format!("the method type {}", descriptor))
+        }
+        ResolvedConstant::MethodHandle { reference_kind, .. } => {
+            return unsupported(format!("a method handle of kind {}", reference_kind))
+        }
+        ResolvedConstant::Field { .. } | ResolvedConstant::Method { .. } => panic!(
+            "Error: #{} is not a loadable constant in {}",
+            cp_index, current_frame.context.descriptor
+        ),
+    };
+    current_frame.push(value);
+    Ok(())
+}
+
+fn resolve_symbolic_reference(
+    vm: &JVM,
+    class: &Rc<Class>,
+    pool: &RuntimeConstantPool,
+    index: u16,
+) -> Result<ResolvedConstant, Throwable> {
+    let constant = pool.constant_at(index);
+    match constant {
+        Constant::Integer(value) => Ok(ResolvedConstant::Integer(*value)),
+        Constant::Float(value) => Ok(ResolvedConstant::Float(*value)),
+        Constant::Long(value) => Ok(ResolvedConstant::Long(*value)),
+        Constant::Double(value) => Ok(ResolvedConstant::Double(*value)),
+        Constant::String(value) => Ok(ResolvedConstant::String(value.clone())),
+        Constant::Class(name) => Ok(ResolvedConstant::Class(resolve_class_entry(
+            vm, class, name,
+        )?)),
+        Constant::FieldRef(field_ref) => {
+            let referenced = resolve_class_entry(vm, class, &field_ref.class)?;
+            let declaring_class = resolve_field(&referenced, &field_ref.name_and_descriptor)?;
+            let access_flags = declaring_class
+                .field(&field_ref.name_and_descriptor)
+                .unwrap()
+                .access_flags;
+            check_member_access(
+                vm,
+                class,
+                &declaring_class,
+                access_flags,
+                "field",
+                &field_ref.name_and_descriptor,
+            )?;
+            vm.check_member_loader_constraints(
+                class,
+                &declaring_class,
+                "field",
+                &field_ref.name_and_descriptor,
+            )?;
+            Ok(ResolvedConstant::Field {
+                class: declaring_class,
+                name_and_descriptor: field_ref.name_and_descriptor.clone(),
+            })
+        }
+        Constant::MethodRef(method_ref) | Constant::InterfaceMethodRef(method_ref) => {
+            let referenced = resolve_class_entry(vm, class, &method_ref.class)?;
+            let (declaring_class, method) = match constant {
+                Constant::MethodRef(_) => {
+                    resolve_method(&referenced, &method_ref.name, &method_ref.descriptor)?
+                }
+                _ => {
+                    resolve_interface_method(&referenced, &method_ref.name, &method_ref.descriptor)?
+                }
+            };
+            check_member_access(
+                vm,
+                class,
+                &declaring_class,
+                method.access_flags,
+                "method",
+                &method_ref.name_and_descriptor,
+            )?;
+            vm.check_member_loader_constraints(
+                class,
+                &declaring_class,
+                "method",
+                &method_ref.name_and_descriptor,
+            )?;
+            Ok(ResolvedConstant::Method {
+                class: declaring_class,
+                method,
+                name_and_descriptor: method_ref.name_and_descriptor.clone(),
+            })
+        }
+        // the classes in the descriptor are resolved, JVMS 5.4.3.5
+        Constant::MethodType(descriptor) => {
+            for class_name in class_names_in_descriptor(descriptor) {
+                vm.resolve_class_from(class, &class_name)?;
+            }
+            Ok(ResolvedConstant::MethodType(descriptor.clone()))
+        }
+        Constant::MethodHandle {
+            reference_kind,
+            reference_index,
+        } => Ok(ResolvedConstant::MethodHandle {
+            reference_kind: *reference_kind,
+            target: Box::new(resolve_constant_in(vm, class, pool, *reference_index)?),
+        }),
+        constant => panic!(
+            "Error: #{} {:?} in {} can't be resolved",
+            index, constant, class.descriptor
+        ),
+    }
+}
+
+/// the class referred from `class`, which must be accessible from it
+fn resolve_class_entry(vm: &JVM, class: &Rc<Class>, name: &str) -> Result<Rc<Class>, Throwable> {
+    let resolved = vm.resolve_class_from(class, name)?;
+    check_class_access(vm, class, &resolved)?;
+    Ok(resolved)
+}
+
+/// class names of the reference types in a field or method descriptor, such as
+/// `java/lang/String` of `([Ljava/lang/String;)V`, JVMS 4.3
+pub fn class_names_in_descriptor(descriptor: &str) -> Vec<String> {
+    let mut class_names = vec![];
+    let mut rest = descriptor;
+    while let Some(tag) = rest.chars().next() {
+        rest = &rest[1..];
+        match tag {
+            '(' | ')' | '[' | 'B' | 'C' | 'D' | 'F' | 'I' | 'J' | 'S' | 'Z' | 'V' => {}
+            'L' => {
+                let (class_name, tail) = rest
+                    .split_once(';')
+                    .unwrap_or_else(|| panic!("Invalid descriptor: {}", descriptor));
+                class_names.push(class_name.to_owned());
+                rest = tail;
+            }
+            _ => panic!("Invalid descriptor: {}", descriptor),
+        }
+    }
+    class_names
+}
+
+#[test]
+fn test_class_names_in_descriptor() {
+    assert!(class_names_in_descriptor("(IJ)[D").is_empty());
+    assert_eq!(
+        class_names_in_descriptor("([Ljava/lang/String;ILcom/acme/Main;)Ljava/lang/Object;"),
+        ["java/lang/String", "com/acme/Main", "java/lang/Object"]
+    );
+    assert_eq!(
+        class_names_in_descriptor("[[Lorg/slf4j/Logger;"),
+        ["org/slf4j/Logger"]
+    );
+}
+
+#[test]
+fn test_resolve_constant_once() {
+    let vm = JVM::create_with_class_path("java");
+    let class = vm.resolve_class("Constants").unwrap();
+
+    // #26 Constants.large:()I and #58 REF_invokeStatic of it, see Constants.jvm
+    let first = resolve_constant(&vm, &class, 26).unwrap();
+    let second = resolve_constant(&vm, &class, 26).unwrap();
+    let (
+        ResolvedConstant::Method { method: first, .. },
+        ResolvedConstant::Method { method: second, .. },
+    ) = (first, second)
+    else {
+        panic!("#26 is not resolved to a method");
+    };
+    assert!(Arc::ptr_eq(&first, &second));
+    assert!(matches!(
+        resolve_constant(&vm, &class, 58).unwrap(),
+        ResolvedConstant::MethodHandle {
+            reference_kind: 6,
+            ..
+        }
+    ));
+
+    assert!(matches!(
+        resolve_constant(&vm, &class, 7).unwrap(),
+        ResolvedConstant::Long(1234567890123)
+    ));
+    assert!(matches!(
+        resolve_constant(&vm, &class, 13).unwrap(),
+        ResolvedConstant::String(value) if value == "hello"
+    ));
+    assert!(matches!(
+        resolve_constant(&vm, &class, 57).unwrap(),
+        ResolvedConstant::MethodType(descriptor) if descriptor == "()I"
+    ));
+}
+
+#[test]
+fn test_resolution_error_is_cached() {
+    use crate::binary::read_binary_file;
+    use crate::throwable::NO_CLASS_DEF_FOUND_ERROR;
+
+    let vm = JVM::create_with_class_path("java");
+    let class = vm.resolve_class("BrokenInit").unwrap();
+    let index = (1..class.runtime_constant_pool().len() as u16)
+        .find(|&index| class.constant_pool_value_at(index) == "Missing.compute:()I")
+        .unwrap();
+
+    let first = resolve_constant(&vm, &class, index).unwrap_err();
+    assert!(first.is(NO_CLASS_DEF_FOUND_ERROR));
+
+    // the same error, even after Missing becomes available
+    let binary = read_binary_file(&"java/init/Missing.class".to_owned()).unwrap();
+    vm.define_class("Missing", &binary).unwrap();
+    let second = resolve_constant(&vm, &class, index).unwrap_err();
+    assert_eq!(first, second);
+}
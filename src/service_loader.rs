@@ -0,0 +1,255 @@
+#[cfg(test)]
+use std::{path::PathBuf, rc::Rc};
+
+#[cfg(test)]
+use crate::{
+    class::Class,
+    class_file::{ACC_PUBLIC, ACC_STATIC},
+    class_loader::{LoaderId, APPLICATION_LOADER},
+    class_path::ClassPath,
+    jvm::JVM,
+    module::dotted_name_of,
+    module_graph::{ModuleGraph, ModuleOptions, ALL_MODULE_PATH},
+    throwable::{Throwable, CLASS_NOT_FOUND_EXCEPTION, SERVICE_CONFIGURATION_ERROR},
+};
+
+/// the provider configuration files, named after the binary name of the service
+pub const SERVICES_DIRECTORY: &str = "META-INF/services/";
+
+/// Find the providers of `service` visible to `loader`, as `ServiceLoader.load(service, loader)`.
+/// the providers of named modules come first by their `provides` directives,
+/// then the ones listed in `META-INF/services/<service>` found by the loader, which are
+/// skipped if they are in a named module. a provider listed more than once is found once.
+/// there is no heap yet, so the provider classes are returned instead of their instances.
+/// https://docs.oracle.com/en/java/javase/17/docs/api/java.base/java/util/ServiceLoader.html
+#[cfg(test)]
+pub fn load_services(
+    vm: &JVM,
+    service: &Rc<Class>,
+    loader: LoaderId,
+) -> Result<Vec<Rc<Class>>, Throwable> {
+    let mut providers: Vec<Rc<Class>> = vec![];
+    let mut add_provider = |provider: Rc<Class>| {
+        if !providers.iter().any(|found| Rc::ptr_eq(found, &provider)) {
+            providers.push(provider);
+        }
+    };
+
+    // the modules are defined to the application loader, and visible to its descendants
+    if delegates_to(vm, loader, APPLICATION_LOADER) {
+        let mut modules = vm.module_graph().module_references();
+        modules.sort_by(|module, other| module.name().cmp(other.name()));
+        for module in modules {
+            for provides in &module.descriptor.provides {
+                if provides.service != service.descriptor {
+                    continue;
+                }
+                for provider_name in &provides.providers {
+                    let provider =
+                        vm.load_class(APPLICATION_LOADER, provider_name)
+                            .map_err(|_| {
+                                fail(
+                                    service,
+                                    format!("Provider {} not found", dotted_name_of(provider_name)),
+                                )
+                            })?;
+                    check_provider(service, &provider, true)?;
+                    add_provider(provider);
+                }
+            }
+        }
+    }
+
+    let config_file = format!(
+        "{}{}",
+        SERVICES_DIRECTORY,
+        dotted_name_of(&service.descriptor)
+    );
+    for resource in vm.get_resources(loader, &config_file) {
+        for provider_name in provider_names_in(&String::from_utf8_lossy(&resource.bytes)) {
+            let provider = match vm.for_name(&dotted_name_of(&provider_name), false, loader) {
+                Ok(provider) => provider,
+                Err(throwable) if throwable.is(CLASS_NOT_FOUND_EXCEPTION) => {
+                    return Err(fail(
+                        service,
+                        format!("Provider {} not found", dotted_name_of(&provider_name)),
+                    ));
+                }
+                Err(throwable) => return Err(throwable),
+            };
+            if provider.module.is_some() {
+                continue;
+            }
+            check_provider(service, &provider, false)?;
+            add_provider(provider);
+        }
+    }
+    Ok(providers)
+}
+
+/// the class names of a provider configuration file, in the internal form.
+/// one name per line, and `#` begins a comment.
+pub fn provider_names_in(config: &str) -> Vec<String> {
+    let mut names: Vec<String> = vec![];
+    for line in config.lines() {
+        let name = line.split('#').next().unwrap_or_default().trim();
+        if !name.is_empty() {
+            let name = name.replace('.', "/");
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+    }
+    names
+}
+
+/// a provider is a public subtype of the service with a public no-arg constructor,
+/// or in a named module, with a public static `provider()` method instead.
+#[cfg(test)]
+fn check_provider(service: &Class, provider: &Class, in_module: bool) -> Result<(), Throwable> {
+    let provider_name = dotted_name_of(&provider.descriptor);
+    let is_subtype = std::ptr::eq(provider, service)
+        || provider.is_subclass_of(service)
+        || provider.implements(service);
+    if !is_subtype {
+        return Err(fail(
+            service,
+            format!("Provider {} not a subtype", provider_name),
+        ));
+    }
+    if !provider.is_public() {
+        return Err(fail(
+            service,
+            format!("Provider {} is not public", provider_name),
+        ));
+    }
+
+    let data = provider.data();
+    let methods = &data.methods;
+    let is_public = |flags: u16| flags & ACC_PUBLIC != 0;
+    let has_provider_method = in_module
+        && methods.iter().any(|(id, method)| {
+            id.starts_with("provider:()L")
+                && is_public(method.access_flags)
+                && method.access_flags & ACC_STATIC != 0
+        });
+    let has_constructor = methods
+        .get("<init>:()V")
+        .is_some_and(|constructor| is_public(constructor.access_flags));
+    if !has_provider_method && !has_constructor {
+        return Err(fail(
+            service,
+            format!("{} Unable to get public no-arg constructor", provider_name),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+fn delegates_to(vm: &JVM, loader: LoaderId, ancestor: LoaderId) -> bool {
+    let mut current = Some(loader);
+    while let Some(loader) = current {
+        if loader == ancestor {
+            return true;
+        }
+        current = vm.class_loader(loader).parent();
+    }
+    false
+}
+
+#[cfg(test)]
+fn fail(service: &Class, message: String) -> Throwable {
+    Throwable::new(
+        SERVICE_CONFIGURATION_ERROR,
+        format!("{}: {}", dotted_name_of(&service.descriptor), message),
+    )
+}
+
+#[cfg(test)]
+fn services_vm(module_path: &str, class_path: &str) -> JVM {
+    let options = ModuleOptions {
+        module_path: module_path.split(':').map(PathBuf::from).collect(),
+        add_modules: vec![String::from(ALL_MODULE_PATH)],
+        ..ModuleOptions::default()
+    };
+    let mut vm = JVM::create();
+    vm.set_module_graph(
+        ClassPath::parse(class_path),
+        ModuleGraph::resolve(&options, None).unwrap(),
+    );
+    vm
+}
+
+#[cfg(test)]
+fn provider_names(providers: &[Rc<Class>]) -> Vec<&str> {
+    providers
+        .iter()
+        .map(|provider| provider.descriptor.as_str())
+        .collect()
+}
+
+#[test]
+fn test_provider_names_in() {
+    let config = "# codecs\ncom.acme.codec.HexCodec  # hex\n\n  com.acme.codec.Base64Codec\ncom.acme.codec.HexCodec\n";
+
+    assert_eq!(
+        provider_names_in(config),
+        ["com/acme/codec/HexCodec", "com/acme/codec/Base64Codec"]
+    );
+}
+
+#[test]
+fn test_load_services() {
+    let vm = services_vm("java/services/mods", "java/services/cp/codecs.jar");
+    let codec = vm
+        .for_name("com.acme.spi.Codec", false, APPLICATION_LOADER)
+        .unwrap();
+
+    let providers = load_services(&vm, &codec, APPLICATION_LOADER).unwrap();
+
+    assert_eq!(
+        provider_names(&providers),
+        ["com/acme/zip/ZipCodec", "com/acme/codec/HexCodec"]
+    );
+    assert_eq!(providers[1].module, None);
+    assert!(
+        load_services(&vm, &codec, crate::class_loader::PLATFORM_LOADER)
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[test]
+fn test_load_services_of_automatic_module() {
+    let vm = services_vm("java/services/mods:java/services/cp/codecs.jar", "");
+    let codec = vm
+        .for_name("com.acme.spi.Codec", false, APPLICATION_LOADER)
+        .unwrap();
+
+    let providers = load_services(&vm, &codec, APPLICATION_LOADER).unwrap();
+
+    assert_eq!(
+        provider_names(&providers),
+        ["com/acme/codec/HexCodec", "com/acme/zip/ZipCodec"]
+    );
+    assert_eq!(providers[0].module.as_deref(), Some("codecs"));
+}
+
+#[test]
+fn test_provider_not_a_subtype() {
+    let vm = services_vm(
+        "java/services/mods",
+        "java/services/cp/codecs.jar:java/services/cp/broken.jar",
+    );
+    let codec = vm
+        .for_name("com.acme.spi.Codec", false, APPLICATION_LOADER)
+        .unwrap();
+
+    let throwable = load_services(&vm, &codec, APPLICATION_LOADER).unwrap_err();
+
+    assert!(throwable.is(SERVICE_CONFIGURATION_ERROR));
+    assert_eq!(
+        throwable.message,
+        "com.acme.spi.Codec: Provider com.acme.codec.NotACodec not a subtype"
+    );
+}
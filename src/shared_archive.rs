@@ -0,0 +1,1094 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::Arc;
+use std::time::UNIX_EPOCH;
+
+use crate::class::{Class, FieldRef, MethodRef};
+use crate::class_attributes::{
+    AttributeInfo, CodeAttributeInfo, ConstantValueAttributeInfo, ExceptionTable, FieldInfo,
+    GeneralAttributeInfo, MethodInfo, NestHostAttributeInfo, NestMembersAttributeInfo,
+};
+use crate::class_data::ClassData;
+use crate::class_loader::{define_class_of, LoaderId, JDK_MODULES_IMAGE};
+use crate::class_path::ClassPath;
+use crate::jvm::JAVA_FEATURE_VERSION;
+use crate::module_graph::ModuleGraph;
+use crate::runtime_constant_pool::Constant;
+use crate::throwable::{Throwable, CLASS_FORMAT_ERROR};
+#[cfg(test)]
+use crate::value::Value;
+use crate::zip::crc32;
+
+/// the magic number of the archives of HotSpot class data sharing
+pub const SHARED_ARCHIVE_MAGIC: u32 = 0xf00b_aba2;
+
+/// bumped whenever the layout of the archived classes changes
+const SHARED_ARCHIVE_VERSION: u32 = 3;
+
+/// the magic, the versions, and the length and CRC-32 of the rest of the archive
+const SHARED_ARCHIVE_HEADER_LENGTH: usize = 22;
+
+/// `-Xshare:<mode>`, auto by default as HotSpot.
+/// https://docs.oracle.com/en/java/javase/17/vm/class-data-sharing.html
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum SharingMode {
+    Off,
+    /// use the archive of `-XX:SharedArchiveFile` if it is valid, and parse class files if not
+    #[default]
+    Auto,
+    /// load the classes of `-XX:SharedClassListFile` and write them to the archive, then exit
+    Dump,
+}
+
+#[derive(Debug, Default)]
+pub struct SharingOptions {
+    pub mode: SharingMode,
+    pub archive_file: Option<PathBuf>,
+    pub class_list_file: Option<PathBuf>,
+}
+
+/// a class path, module path or run-time image entry the archived classes were read from.
+/// the archive is used only if the entries are the same as the ones at dump time.
+/// a file is compared by its size and modification time, and a directory by the names and
+/// contents of its class files, which are recompiled in place.
+#[derive(Debug, PartialEq)]
+pub struct SharedPath {
+    pub path: String,
+    size: u64,
+    /// the modification time of a file, or the CRC-32 of the class files of a directory
+    stamp: u64,
+}
+
+impl SharedPath {
+    pub fn of(path: &Path) -> SharedPath {
+        let (size, stamp) = match fs::metadata(path) {
+            Ok(metadata) if metadata.is_file() => {
+                let modified = metadata
+                    .modified()
+                    .ok()
+                    .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                    .map_or(0, |duration| duration.as_nanos() as u64);
+                (metadata.len(), modified)
+            }
+            Ok(metadata) if metadata.is_dir() => {
+                let mut class_files = vec![];
+                class_files_in(path, &mut class_files);
+                class_files.sort();
+                let mut contents = vec![];
+                for class_file in &class_files {
+                    let name = class_file.strip_prefix(path).unwrap_or(class_file);
+                    contents.extend(name.to_string_lossy().as_bytes());
+                    contents.push(0);
+                    contents.extend(fs::read(class_file).unwrap_or_default());
+                }
+                (contents.len() as u64, crc32(&contents) as u64)
+            }
+            _ => (0, 0),
+        };
+        SharedPath {
+            path: path.display().to_string(),
+            size,
+            stamp,
+        }
+    }
+}
+
+/// the class files in the directory and its subdirectories
+fn class_files_in(directory: &Path, class_files: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(directory) else {
+        return;
+    };
+    for path in entries.filter_map(|entry| Some(entry.ok()?.path())) {
+        if path.is_dir() {
+            class_files_in(&path, class_files);
+        } else if path
+            .extension()
+            .is_some_and(|extension| extension == "class")
+        {
+            class_files.push(path);
+        }
+    }
+}
+
+/// the entries searched by the built-in loaders, in the order they are searched
+pub fn shared_paths_of(
+    class_path: &ClassPath,
+    module_graph: &ModuleGraph,
+    jdk_home: Option<&Path>,
+) -> Vec<SharedPath> {
+    let mut module_paths: Vec<PathBuf> = module_graph
+        .module_references()
+        .iter()
+        .map(|module| module.location.path().to_path_buf())
+        .collect();
+    module_paths.sort();
+
+    jdk_home
+        .map(|jdk_home| jdk_home.join(JDK_MODULES_IMAGE))
+        .iter()
+        .chain(&module_paths)
+        .map(|path| SharedPath::of(path))
+        .chain(
+            class_path
+                .entries
+                .iter()
+                .map(|entry| SharedPath::of(entry.path())),
+        )
+        .collect()
+}
+
+/// Classes of the built-in loaders, archived after they were parsed and linked, CDS.
+/// A class in the archive is defined from its archived structures instead of its class file,
+/// while its superclass and superinterfaces are still loaded by the defining loader.
+/// https://docs.oracle.com/en/java/javase/17/vm/class-data-sharing.html
+pub struct SharedArchive {
+    pub path: PathBuf,
+    data: Vec<u8>,
+    /// the offset of each archived class, by its defining loader and name
+    classes: HashMap<(LoaderId, String), usize>,
+}
+
+impl SharedArchive {
+    /// read the archive, which must have been dumped from the same `shared_paths`.
+    /// only the table of the archived classes is decoded here, and each class on its loading.
+    pub fn open(path: &Path, shared_paths: &[SharedPath]) -> io::Result<SharedArchive> {
+        let data = fs::read(path)?;
+        if data.len() < SHARED_ARCHIVE_HEADER_LENGTH {
+            return Err(invalid_data(format!(
+                "{} is not a shared archive",
+                path.display()
+            )));
+        }
+        let mut reader = ArchiveReader::new(&data);
+        if reader.u32()? != SHARED_ARCHIVE_MAGIC {
+            return Err(invalid_data(format!(
+                "{} is not a shared archive",
+                path.display()
+            )));
+        }
+        let version = reader.u32()?;
+        let feature_version = reader.u16()?;
+        if version != SHARED_ARCHIVE_VERSION || feature_version != JAVA_FEATURE_VERSION {
+            return Err(invalid_data(format!(
+                "the archive version {} for Java {} doesn't match",
+                version, feature_version
+            )));
+        }
+        // a truncated or damaged archive is detected before any class is defined from it
+        let length = reader.u64()?;
+        let checksum = reader.u32()?;
+        let body = &data[SHARED_ARCHIVE_HEADER_LENGTH..];
+        if body.len() as u64 != length || crc32(body) != checksum {
+            return Err(invalid_data(format!(
+                "{} is corrupt, its length or checksum doesn't match",
+                path.display()
+            )));
+        }
+        let archived_paths = reader.list(|reader| {
+            Ok(SharedPath {
+                path: reader.string()?,
+                size: reader.u64()?,
+                stamp: reader.u64()?,
+            })
+        })?;
+        if archived_paths != shared_paths {
+            return Err(invalid_data(String::from(
+                "shared class paths mismatch, the archive was dumped with other class path entries",
+            )));
+        }
+
+        let table = reader.list(|reader| {
+            Ok((
+                LoaderId(reader.u32()? as usize),
+                reader.string()?,
+                reader.u32()?,
+            ))
+        })?;
+        let records = reader.position;
+        let classes = table
+            .into_iter()
+            .map(|(loader, name, offset)| ((loader, name), records + offset as usize))
+            .collect();
+        Ok(SharedArchive {
+            path: path.to_path_buf(),
+            data,
+            classes,
+        })
+    }
+
+    /// write the classes, which are linked to their superclasses by name.
+    pub fn dump(path: &Path, shared_paths: &[SharedPath], classes: &[Rc<Class>]) -> io::Result<()> {
+        let mut records = ArchiveWriter::default();
+        let mut table = ArchiveWriter::default();
+        table.u32(classes.len() as u32);
+        for class in classes {
+            table.u32(class.defining_loader.0 as u32);
+            table.string(&class.descriptor);
+            table.u32(records.buffer.len() as u32);
+            records.class(class);
+        }
+
+        let mut body = ArchiveWriter::default();
+        body.u32(shared_paths.len() as u32);
+        for shared_path in shared_paths {
+            body.string(&shared_path.path);
+            body.u64(shared_path.size);
+            body.u64(shared_path.stamp);
+        }
+        body.buffer.extend(table.buffer);
+        body.buffer.extend(records.buffer);
+
+        let mut writer = ArchiveWriter::default();
+        writer.u32(SHARED_ARCHIVE_MAGIC);
+        writer.u32(SHARED_ARCHIVE_VERSION);
+        writer.u16(JAVA_FEATURE_VERSION);
+        writer.u64(body.buffer.len() as u64);
+        writer.u32(crc32(&body.buffer));
+        writer.buffer.extend(body.buffer);
+
+        // the old archive may be read by running processes, so it is replaced rather than
+        // rewritten in place, which they would see half written
+        let mut temp_name = path.file_name().unwrap_or_default().to_os_string();
+        temp_name.push(format!(".{}.tmp", std::process::id()));
+        let temp_path = path.with_file_name(temp_name);
+        let result =
+            fs::write(&temp_path, writer.buffer).and_then(|_| fs::rename(&temp_path, path));
+        if result.is_err() {
+            let _ = fs::remove_file(&temp_path);
+        }
+        result
+    }
+
+    pub fn contains(&self, loader: LoaderId, class_name: &str) -> bool {
+        self.classes.contains_key(&(loader, class_name.to_owned()))
+    }
+
+    pub fn class_count(&self) -> usize {
+        self.classes.len()
+    }
+
+    /// define the archived class with `loader` as its defining loader, as `define_class` does
+    /// from a class file. the superclass and superinterfaces are checked as for a class file,
+    /// since they may have changed since the dump.
+    pub fn define_class<F>(
+        &self,
+        loader: LoaderId,
+        class_name: &str,
+        resolve_class: F,
+    ) -> Result<Class, Throwable>
+    where
+        F: FnMut(&str) -> Result<Rc<Class>, Throwable>,
+    {
+        let offset = self.classes[&(loader, class_name.to_owned())];
+        let mut reader = ArchiveReader::new(&self.data);
+        reader.position = offset;
+        let data = reader.class_data().map_err(|error| {
+            Throwable::new(
+                CLASS_FORMAT_ERROR,
+                format!(
+                    "the shared archive {} is corrupt: {}",
+                    self.path.display(),
+                    error
+                ),
+            )
+        })?;
+        define_class_of(loader, Arc::new(data), resolve_class)
+    }
+}
+
+/// the names of a class list, one per line as `-XX:DumpLoadedClassList` writes.
+/// comments and the lines of lambda proxies, `@lambda-proxy`, are skipped.
+pub fn class_names_in_list(class_list: &str) -> Vec<String> {
+    class_list
+        .lines()
+        .filter_map(|line| line.split_whitespace().next())
+        .filter(|name| !name.starts_with('#') && !name.starts_with('@'))
+        .map(|name| name.replace('.', "/"))
+        .collect()
+}
+
+/// big-endian, and a string is its length followed by its UTF-8 bytes
+#[derive(Default)]
+struct ArchiveWriter {
+    buffer: Vec<u8>,
+}
+
+impl ArchiveWriter {
+    fn u8(&mut self, value: u8) {
+        self.buffer.push(value);
+    }
+
+    fn u16(&mut self, value: u16) {
+        self.buffer.extend(value.to_be_bytes());
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.buffer.extend(value.to_be_bytes());
+    }
+
+    fn u64(&mut self, value: u64) {
+        self.buffer.extend(value.to_be_bytes());
+    }
+
+    fn bytes(&mut self, bytes: &[u8]) {
+        self.u32(bytes.len() as u32);
+        self.buffer.extend(bytes);
+    }
+
+    fn string(&mut self, value: &str) {
+        self.bytes(value.as_bytes());
+    }
+
+    fn optional_string(&mut self, value: Option<&str>) {
+        match value {
+            Some(value) => {
+                self.u8(1);
+                self.string(value);
+            }
+            None => self.u8(0),
+        }
+    }
+
+    fn strings(&mut self, values: &[String]) {
+        self.u32(values.len() as u32);
+        for value in values {
+            self.string(value);
+        }
+    }
+
+    fn class(&mut self, class: &Class) {
+        self.string(&class.descriptor);
+        self.u16(class.access_flags);
+        self.optional_string(
+            class
+                .super_class
+                .as_ref()
+                .map(|class| class.descriptor.as_str()),
+        );
+        let interfaces: Vec<String> = class
+            .interfaces
+            .iter()
+            .map(|interface| interface.descriptor.clone())
+            .collect();
+        self.strings(&interfaces);
+        let data = class.data();
+        self.strings(&data.constant_pool);
+        self.u32(data.constants.len() as u32);
+        for constant in data.constants.iter() {
+            self.constant(constant);
+        }
+        self.optional_string(data.nest_host.as_deref());
+        self.strings(&data.nest_members);
+
+        // sorted to dump the same archive from the same classes
+        let mut methods: Vec<_> = data.methods.iter().collect();
+        methods.sort_by_key(|(id, _)| id.as_str());
+        self.u32(methods.len() as u32);
+        for (id, method) in methods {
+            self.string(id);
+            self.member(
+                method.access_flags,
+                method.name_index,
+                method.descriptor_index,
+                &method.attributes,
+            );
+        }
+        let mut fields: Vec<_> = data.fields.iter().collect();
+        fields.sort_by_key(|(id, _)| id.as_str());
+        self.u32(fields.len() as u32);
+        for (id, field) in fields {
+            self.string(id);
+            self.member(
+                field.access_flags,
+                field.name_index,
+                field.descriptor_index,
+                &field.attributes,
+            );
+        }
+    }
+
+    fn constant(&mut self, constant: &Constant) {
+        match constant {
+            Constant::Unusable => self.u8(0),
+            Constant::Utf8(value) => {
+                self.u8(1);
+                self.string(value);
+            }
+            Constant::Integer(value) => {
+                self.u8(3);
+                self.u32(*value as u32);
+            }
+            Constant::Float(value) => {
+                self.u8(4);
+                self.u32(value.to_bits());
+            }
+            Constant::Long(value) => {
+                self.u8(5);
+                self.u64(*value as u64);
+            }
+            Constant::Double(value) => {
+                self.u8(6);
+                self.u64(value.to_bits());
+            }
+            Constant::Class(name) => {
+                self.u8(7);
+                self.string(name);
+            }
+            Constant::String(value) => {
+                self.u8(8);
+                self.string(value);
+            }
+            Constant::FieldRef(field_ref) => {
+                self.u8(9);
+                self.strings(&[
+                    field_ref.class.clone(),
+                    field_ref.name.clone(),
+                    field_ref.descriptor.clone(),
+                    field_ref.name_and_descriptor.clone(),
+                ]);
+            }
+            Constant::MethodRef(method_ref) | Constant::InterfaceMethodRef(method_ref) => {
+                self.u8(if matches!(constant, Constant::MethodRef(_)) {
+                    10
+                } else {
+                    11
+                });
+                self.strings(&[
+                    method_ref.class.clone(),
+                    method_ref.name.clone(),
+                    method_ref.descriptor.clone(),
+                    method_ref.name_and_descriptor.clone(),
+                ]);
+            }
+            Constant::NameAndType(value) => {
+                self.u8(12);
+                self.string(value);
+            }
+            Constant::MethodHandle {
+                reference_kind,
+                reference_index,
+            } => {
+                self.u8(15);
+                self.u8(*reference_kind);
+                self.u16(*reference_index);
+            }
+            Constant::MethodType(value) => {
+                self.u8(16);
+                self.string(value);
+            }
+            Constant::Dynamic(value) => {
+                self.u8(17);
+                self.string(value);
+            }
+            Constant::InvokeDynamic(value) => {
+                self.u8(18);
+                self.string(value);
+            }
+            Constant::Module(value) => {
+                self.u8(19);
+                self.string(value);
+            }
+            Constant::Package(value) => {
+                self.u8(20);
+                self.string(value);
+            }
+        }
+    }
+
+    fn member(
+        &mut self,
+        access_flags: u16,
+        name_index: u16,
+        descriptor_index: u16,
+        attributes: &[AttributeInfo],
+    ) {
+        self.u16(access_flags);
+        self.u16(name_index);
+        self.u16(descriptor_index);
+        self.attributes(attributes);
+    }
+
+    /// the module attributes are only in `module-info`, which is never loaded as a class
+    fn attributes(&mut self, attributes: &[AttributeInfo]) {
+        let archived: Vec<&AttributeInfo> = attributes
+            .iter()
+            .filter(|attribute| {
+                matches!(
+                    attribute,
+                    AttributeInfo::ConstantValueAttributeInfo(_)
+                        | AttributeInfo::CodeAttributeInfo(_)
+                        | AttributeInfo::NestHostAttributeInfo(_)
+                        | AttributeInfo::NestMembersAttributeInfo(_)
+                        | AttributeInfo::GeneralAttributeInfo(_)
+                )
+            })
+            .collect();
+        self.u16(archived.len() as u16);
+        for attribute in archived {
+            match attribute {
+                AttributeInfo::CodeAttributeInfo(code) => {
+                    self.u8(1);
+                    self.u16(code.attribute_name_index);
+                    self.u32(code.attribute_length);
+                    self.u16(code.max_stack);
+                    self.u16(code.max_locals);
+                    self.bytes(&code.code);
+                    self.u16(code.exception_table.len() as u16);
+                    for entry in &code.exception_table {
+                        self.u16(entry.start_pc);
+                        self.u16(entry.end_pc);
+                        self.u16(entry.handler_pc);
+                        self.u16(entry.catch_type);
+                    }
+                    self.attributes(&code.attributes);
+                }
+                AttributeInfo::NestHostAttributeInfo(info) => {
+                    self.u8(2);
+                    self.u16(info.attribute_name_index);
+                    self.u32(info.attribute_length);
+                    self.u16(info.host_class_index);
+                }
+                AttributeInfo::NestMembersAttributeInfo(info) => {
+                    self.u8(3);
+                    self.u16(info.attribute_name_index);
+                    self.u32(info.attribute_length);
+                    self.u16(info.classes.len() as u16);
+                    for &index in &info.classes {
+                        self.u16(index);
+                    }
+                }
+                AttributeInfo::ConstantValueAttributeInfo(info) => {
+                    self.u8(5);
+                    self.u16(info.attribute_name_index);
+                    self.u32(info.attribute_length);
+                    self.u16(info.constantvalue_index);
+                }
+                AttributeInfo::GeneralAttributeInfo(info) => {
+                    self.u8(4);
+                    self.u16(info.attribute_name_index);
+                    self.u32(info.attribute_length);
+                    self.bytes(&info.info);
+                }
+                _ => unreachable!(),
+            }
+        }
+    }
+}
+
+/// every read fails on a truncated or corrupt archive rather than panicking,
+/// then the archive is skipped and the classes are loaded from their class files.
+struct ArchiveReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> ArchiveReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        ArchiveReader { data, position: 0 }
+    }
+
+    fn take(&mut self, length: usize) -> io::Result<&'a [u8]> {
+        let bytes = self
+            .position
+            .checked_add(length)
+            .and_then(|end| self.data.get(self.position..end))
+            .ok_or_else(|| {
+                invalid_data(format!(
+                    "the shared archive is truncated at {}",
+                    self.position
+                ))
+            })?;
+        self.position += length;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> io::Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> io::Result<u16> {
+        Ok(u16::from_be_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> io::Result<u64> {
+        Ok(u64::from_be_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn bytes(&mut self) -> io::Result<&'a [u8]> {
+        let length = self.u32()? as usize;
+        self.take(length)
+    }
+
+    fn string(&mut self) -> io::Result<String> {
+        Ok(String::from_utf8_lossy(self.bytes()?).into_owned())
+    }
+
+    fn optional_string(&mut self) -> io::Result<Option<String>> {
+        match self.u8()? {
+            0 => Ok(None),
+            _ => Ok(Some(self.string()?)),
+        }
+    }
+
+    fn strings(&mut self) -> io::Result<Vec<String>> {
+        self.list(ArchiveReader::string)
+    }
+
+    fn list<T>(&mut self, mut read: impl FnMut(&mut Self) -> io::Result<T>) -> io::Result<Vec<T>> {
+        let count = self.u32()?;
+        (0..count).map(|_| read(self)).collect()
+    }
+
+    fn class_data(&mut self) -> io::Result<ClassData> {
+        let descriptor = self.string()?;
+        let access_flags = self.u16()?;
+        let super_class_name = self.optional_string()?;
+        let interface_names = self.strings()?;
+        let constant_pool = self.strings()?;
+        let constants = self.list(ArchiveReader::constant)?;
+        let nest_host = self.optional_string()?;
+        let nest_members = self.strings()?;
+
+        let methods = self
+            .list(|reader| {
+                let id = reader.string()?;
+                let (access_flags, name_index, descriptor_index, attributes) = reader.member()?;
+                let method = MethodInfo {
+                    access_flags,
+                    name_index,
+                    descriptor_index,
+                    attributes,
+                };
+                Ok((id, Arc::new(method)))
+            })?
+            .into_iter()
+            .collect();
+
+        let fields = self
+            .list(|reader| {
+                let id = reader.string()?;
+                let (access_flags, name_index, descriptor_index, attributes) = reader.member()?;
+                let field = FieldInfo {
+                    access_flags,
+                    name_index,
+                    descriptor_index,
+                    attributes,
+                };
+                Ok((id, Arc::new(field)))
+            })?
+            .into_iter()
+            .collect();
+
+        Ok(ClassData {
+            descriptor,
+            access_flags,
+            super_class_name,
+            interface_names,
+            constant_pool,
+            constants: Arc::new(constants),
+            methods,
+            fields,
+            nest_host,
+            nest_members,
+        })
+    }
+
+    fn constant(&mut self) -> io::Result<Constant> {
+        let constant = match self.u8()? {
+            0 => Constant::Unusable,
+            1 => Constant::Utf8(self.string()?),
+            3 => Constant::Integer(self.u32()? as i32),
+            4 => Constant::Float(f32::from_bits(self.u32()?)),
+            5 => Constant::Long(self.u64()? as i64),
+            6 => Constant::Double(f64::from_bits(self.u64()?)),
+            7 => Constant::Class(self.string()?),
+            8 => Constant::String(self.string()?),
+            9 => {
+                let [class, name, descriptor, name_and_descriptor] = self.member_ref()?;
+                Constant::FieldRef(FieldRef {
+                    class,
+                    name,
+                    descriptor,
+                    name_and_descriptor,
+                })
+            }
+            tag @ (10 | 11) => {
+                let [class, name, descriptor, name_and_descriptor] = self.member_ref()?;
+                let method_ref = MethodRef {
+                    class,
+                    name,
+                    descriptor,
+                    name_and_descriptor,
+                };
+                if tag == 10 {
+                    Constant::MethodRef(method_ref)
+                } else {
+                    Constant::InterfaceMethodRef(method_ref)
+                }
+            }
+            12 => Constant::NameAndType(self.string()?),
+            15 => Constant::MethodHandle {
+                reference_kind: self.u8()?,
+                reference_index: self.u16()?,
+            },
+            16 => Constant::MethodType(self.string()?),
+            17 => Constant::Dynamic(self.string()?),
+            18 => Constant::InvokeDynamic(self.string()?),
+            19 => Constant::Module(self.string()?),
+            20 => Constant::Package(self.string()?),
+            tag => {
+                return Err(invalid_data(format!(
+                    "unknown constant tag {} in the shared archive",
+                    tag
+                )))
+            }
+        };
+        Ok(constant)
+    }
+
+    fn member_ref(&mut self) -> io::Result<[String; 4]> {
+        self.strings()?.try_into().map_err(|_| {
+            invalid_data(String::from(
+                "a broken member reference in the shared archive",
+            ))
+        })
+    }
+
+    fn member(&mut self) -> io::Result<(u16, u16, u16, Vec<AttributeInfo>)> {
+        Ok((self.u16()?, self.u16()?, self.u16()?, self.attributes()?))
+    }
+
+    fn attributes(&mut self) -> io::Result<Vec<AttributeInfo>> {
+        let count = self.u16()?;
+        (0..count).map(|_| self.attribute()).collect()
+    }
+
+    fn attribute(&mut self) -> io::Result<AttributeInfo> {
+        let tag = self.u8()?;
+        let attribute_name_index = self.u16()?;
+        let attribute_length = self.u32()?;
+        let attribute = match tag {
+            1 => {
+                let max_stack = self.u16()?;
+                let max_locals = self.u16()?;
+                let code = self.bytes()?.to_vec();
+                let exception_table_length = self.u16()?;
+                let mut exception_table = vec![];
+                for _ in 0..exception_table_length {
+                    exception_table.push(ExceptionTable {
+                        start_pc: self.u16()?,
+                        end_pc: self.u16()?,
+                        handler_pc: self.u16()?,
+                        catch_type: self.u16()?,
+                    });
+                }
+                let attributes = self.attributes()?;
+                AttributeInfo::CodeAttributeInfo(CodeAttributeInfo {
+                    attribute_name_index,
+                    attribute_length,
+                    max_stack,
+                    max_locals,
+                    code,
+                    exception_table,
+                    attributes,
+                })
+            }
+            2 => AttributeInfo::NestHostAttributeInfo(NestHostAttributeInfo {
+                attribute_name_index,
+                attribute_length,
+                host_class_index: self.u16()?,
+            }),
+            3 => {
+                let number_of_classes = self.u16()?;
+                let mut classes = vec![];
+                for _ in 0..number_of_classes {
+                    classes.push(self.u16()?);
+                }
+                AttributeInfo::NestMembersAttributeInfo(NestMembersAttributeInfo {
+                    attribute_name_index,
+                    attribute_length,
+                    classes,
+                })
+            }
+            4 => AttributeInfo::GeneralAttributeInfo(GeneralAttributeInfo {
+                attribute_name_index,
+                attribute_length,
+                info: self.bytes()?.to_vec(),
+            }),
+            5 => AttributeInfo::ConstantValueAttributeInfo(ConstantValueAttributeInfo {
+                attribute_name_index,
+                attribute_length,
+                constantvalue_index: self.u16()?,
+            }),
+            tag => {
+                return Err(invalid_data(format!(
+                    "unknown attribute tag {} in the shared archive",
+                    tag
+                )))
+            }
+        };
+        Ok(attribute)
+    }
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+fn temp_file_of(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("shared-archive-{}-{}", std::process::id(), name))
+}
+
+#[test]
+fn test_class_names_in_list() {
+    let class_list = "# NOTE: Do not modify this file.\n\
+        java/lang/Object id: 0\n\
+        com.acme.Main\n\
+        @lambda-proxy java/lang/Runnable run\n\n";
+
+    assert_eq!(
+        class_names_in_list(class_list),
+        ["java/lang/Object", "com/acme/Main"]
+    );
+}
+
+#[test]
+fn test_dump_and_open_shared_archive() {
+    use crate::class_loader::{APPLICATION_LOADER, BOOTSTRAP_LOADER};
+    use crate::jvm::JVM;
+
+    let class_list = temp_file_of("dump.classlist");
+    let archive_file = temp_file_of("dump.jsa");
+    // Missing is not on the class path, and left out of the archive
+    fs::write(&class_list, "StaticCounter\nSquare\nMissing\n").unwrap();
+    let mut vm = JVM::create();
+    vm.launch(&[
+        String::from("-Xshare:dump"),
+        format!("-XX:SharedClassListFile={}", class_list.display()),
+        format!("-XX:SharedArchiveFile={}", archive_file.display()),
+        String::from("-cp"),
+        String::from("java"),
+    ])
+    .unwrap();
+
+    let mut vm = JVM::create();
+    vm.launch(&[
+        format!("-XX:SharedArchiveFile={}", archive_file.display()),
+        String::from("-cp"),
+        String::from("java"),
+        String::from("StaticCounter"),
+    ])
+    .unwrap();
+
+    let archive = vm.shared_archive().unwrap();
+    assert!(archive.contains(BOOTSTRAP_LOADER, "java/lang/Object"));
+    assert!(archive.contains(APPLICATION_LOADER, "Square"));
+    // the super types of Square are archived along with it
+    assert!(archive.contains(APPLICATION_LOADER, "Rectangle"));
+    assert!(archive.contains(APPLICATION_LOADER, "Shape"));
+    assert!(!archive.contains(APPLICATION_LOADER, "Missing"));
+
+    let counter = vm.resolve_class("StaticCounter").unwrap();
+    assert_eq!(counter.static_value("count:I"), Some(Value::Int(2)));
+
+    let square = vm.resolve_class("Square").unwrap();
+    let parsed = JVM::create_with_class_path("java")
+        .resolve_class("Square")
+        .unwrap();
+    assert_eq!(square.data().constant_pool, parsed.data().constant_pool);
+    assert_eq!(
+        square.runtime_constant_pool().len(),
+        parsed.runtime_constant_pool().len()
+    );
+    assert_eq!(square.super_class.as_ref().unwrap().descriptor, "Rectangle");
+    let (data, parsed_data) = (square.data(), parsed.data());
+    let (methods, parsed_methods) = (&data.methods, &parsed_data.methods);
+    let mut ids: Vec<&String> = methods.keys().collect();
+    let mut parsed_ids: Vec<&String> = parsed_methods.keys().collect();
+    ids.sort();
+    parsed_ids.sort();
+    assert_eq!(ids, parsed_ids);
+    for (id, method) in methods.iter() {
+        let code = method.get_code_attribute();
+        let parsed_code = parsed_methods[id].get_code_attribute();
+        assert_eq!(code.code, parsed_code.code);
+        assert_eq!(code.max_locals, parsed_code.max_locals);
+    }
+
+    fs::remove_file(class_list).unwrap();
+    fs::remove_file(archive_file).unwrap();
+}
+
+#[test]
+fn test_shared_archive_of_other_class_path() {
+    use crate::jvm::JVM;
+
+    let class_list = temp_file_of("other.classlist");
+    let archive_file = temp_file_of("other.jsa");
+    fs::write(&class_list, "com/acme/Main\n").unwrap();
+    let mut vm = JVM::create();
+    vm.launch(&[
+        String::from("-Xshare:dump"),
+        format!("-XX:SharedClassListFile={}", class_list.display()),
+        format!("-XX:SharedArchiveFile={}", archive_file.display()),
+        String::from("-cp"),
+        String::from("java/acme.jar"),
+    ])
+    .unwrap();
+
+    let module_graph = ModuleGraph::default();
+    let jar_paths = shared_paths_of(&ClassPath::parse("java/acme.jar"), &module_graph, None);
+    let other_paths = shared_paths_of(&ClassPath::parse("java"), &module_graph, None);
+    assert!(SharedArchive::open(&archive_file, &jar_paths).is_ok());
+    let result = SharedArchive::open(&archive_file, &other_paths);
+    assert_eq!(result.err().unwrap().kind(), io::ErrorKind::InvalidData);
+
+    // the classes are loaded from the class path instead
+    let mut vm = JVM::create();
+    vm.launch(&[
+        format!("-XX:SharedArchiveFile={}", archive_file.display()),
+        String::from("-cp"),
+        String::from("java"),
+        String::from("com.acme.Main"),
+    ])
+    .unwrap();
+    assert!(vm.shared_archive().is_none());
+
+    fs::remove_file(class_list).unwrap();
+    fs::remove_file(archive_file).unwrap();
+}
+
+#[test]
+fn test_shared_path_of_directory() {
+    let directory = temp_file_of("classes");
+    fs::create_dir_all(directory.join("com/acme")).unwrap();
+    fs::write(
+        directory.join("com/acme/Main.class"),
+        [0xca, 0xfe, 0xba, 0xbe, 1],
+    )
+    .unwrap();
+    fs::write(directory.join("README"), "not a class file").unwrap();
+    let shared_path = SharedPath::of(&directory);
+
+    // a class file recompiled in place, with the same length
+    fs::write(
+        directory.join("com/acme/Main.class"),
+        [0xca, 0xfe, 0xba, 0xbe, 2],
+    )
+    .unwrap();
+    assert_ne!(SharedPath::of(&directory), shared_path);
+    fs::write(
+        directory.join("com/acme/Main.class"),
+        [0xca, 0xfe, 0xba, 0xbe, 1],
+    )
+    .unwrap();
+    assert_eq!(SharedPath::of(&directory), shared_path);
+    // other files are not read by the class loaders
+    fs::write(directory.join("README"), "changed").unwrap();
+    assert_eq!(SharedPath::of(&directory), shared_path);
+    fs::write(directory.join("Added.class"), [0xca, 0xfe, 0xba, 0xbe]).unwrap();
+    assert_ne!(SharedPath::of(&directory), shared_path);
+
+    fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn test_truncated_or_damaged_shared_archive() {
+    use crate::jvm::JVM;
+
+    let class_list = temp_file_of("damaged.classlist");
+    let archive_file = temp_file_of("damaged.jsa");
+    fs::write(&class_list, "com/acme/Main\n").unwrap();
+    let mut vm = JVM::create();
+    vm.launch(&[
+        String::from("-Xshare:dump"),
+        format!("-XX:SharedClassListFile={}", class_list.display()),
+        format!("-XX:SharedArchiveFile={}", archive_file.display()),
+        String::from("-cp"),
+        String::from("java/acme.jar"),
+    ])
+    .unwrap();
+    let archive = fs::read(&archive_file).unwrap();
+    let shared_paths = shared_paths_of(
+        &ClassPath::parse("java/acme.jar"),
+        &ModuleGraph::default(),
+        None,
+    );
+
+    let truncated = &archive[..archive.len() - 1];
+    let mut damaged = archive.clone();
+    *damaged.last_mut().unwrap() ^= 0xff;
+    for bytes in [&archive[..12], truncated, &damaged] {
+        fs::write(&archive_file, bytes).unwrap();
+        let result = SharedArchive::open(&archive_file, &shared_paths);
+        assert_eq!(result.err().unwrap().kind(), io::ErrorKind::InvalidData);
+
+        // the archive is skipped, and the classes are loaded from the jar
+        let mut vm = JVM::create();
+        vm.launch(&[
+            format!("-XX:SharedArchiveFile={}", archive_file.display()),
+            String::from("-cp"),
+            String::from("java/acme.jar"),
+            String::from("com.acme.Main"),
+        ])
+        .unwrap();
+        assert!(vm.shared_archive().is_none());
+    }
+
+    fs::remove_file(class_list).unwrap();
+    fs::remove_file(archive_file).unwrap();
+}
+
+#[test]
+fn test_dump_over_open_shared_archive() {
+    use crate::class_loader::APPLICATION_LOADER;
+    use crate::jvm::JVM;
+
+    let class_list = temp_file_of("redump.classlist");
+    let archive_file = temp_file_of("redump.jsa");
+    fs::write(&class_list, "com/acme/Main\n").unwrap();
+    let dump = || {
+        let mut vm = JVM::create();
+        vm.launch(&[
+            String::from("-Xshare:dump"),
+            format!("-XX:SharedClassListFile={}", class_list.display()),
+            format!("-XX:SharedArchiveFile={}", archive_file.display()),
+            String::from("-cp"),
+            String::from("java/acme.jar"),
+        ])
+        .unwrap();
+    };
+    dump();
+    let shared_paths = shared_paths_of(
+        &ClassPath::parse("java/acme.jar"),
+        &ModuleGraph::default(),
+        None,
+    );
+    let archive = SharedArchive::open(&archive_file, &shared_paths).unwrap();
+
+    // the archive is replaced by a new file, while the open one keeps its classes
+    dump();
+    let vm = JVM::create_with_class_path("java/acme.jar");
+    let class = archive
+        .define_class(APPLICATION_LOADER, "com/acme/Main", |name| {
+            vm.resolve_class(name)
+        })
+        .unwrap();
+    assert_eq!(class.descriptor, "com/acme/Main");
+    let temp_prefix = format!("{}.", archive_file.file_name().unwrap().to_string_lossy());
+    let directory = fs::read_dir(archive_file.parent().unwrap()).unwrap();
+    assert!(!directory
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .any(|name| name.starts_with(&temp_prefix)));
+
+    fs::remove_file(class_list).unwrap();
+    fs::remove_file(archive_file).unwrap();
+}
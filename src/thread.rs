@@ -1,10 +1,9 @@
-use crate::binary::debug_bytes;
 use crate::class::Class;
 use crate::class_attributes::MethodInfo;
-use crate::interpreter::interpret;
-use crate::JVM;
-use std::cell::{RefCell, RefMut};
+use crate::runtime_constant_pool::RuntimeConstantPool;
+use crate::value::Value;
 use std::rc::Rc;
+use std::sync::Arc;
 
 pub struct Thread {
     pub java_virtual_machine_stack: Vec<Frame>,
@@ -21,21 +20,73 @@ impl Thread {
 #[derive(Debug)]
 pub struct Frame {
     pub pc: u64,
-    pub local_variable: Vec<u64>,
-    pub operand_stack: Vec<u64>,
+    pub local_variable: Vec<Value>,
+    pub operand_stack: Vec<Value>,
     pub context: Rc<Class>,
-    pub current_method: Rc<MethodInfo>,
+    pub current_method: Arc<MethodInfo>,
+    /// the constant pool the code of `current_method` refers to,
+    /// which stays with the frame when the class is redefined
+    pub constant_pool: Rc<RuntimeConstantPool>,
 }
 
 impl Frame {
-    pub fn create(context: &Rc<Class>, current_method: &Rc<MethodInfo>) -> Self {
+    pub fn create(context: &Rc<Class>, current_method: &Arc<MethodInfo>) -> Self {
         let max_locals = current_method.get_code_attribute().max_locals;
         Frame {
             pc: 0,
-            local_variable: vec![0; max_locals as usize],
+            local_variable: vec![Value::Top; max_locals as usize],
             operand_stack: vec![],
             context: Rc::clone(context),
-            current_method: Rc::clone(current_method),
+            current_method: Arc::clone(current_method),
+            constant_pool: context.runtime_constant_pool(),
         }
     }
+
+    /// the local variable at `index`. a `long` or `double` is at its first index.
+    pub fn load(&self, index: usize) -> Value {
+        self.local_variable[index]
+    }
+
+    /// a `long` or `double` takes `index + 1` as well. storing into the second half of one
+    /// makes its first half unusable, JVMS 2.6.1.
+    pub fn store(&mut self, index: usize, value: Value) {
+        if index > 0 && self.local_variable[index - 1].category() == 2 {
+            self.local_variable[index - 1] = Value::Top;
+        }
+        self.local_variable[index] = value;
+        if value.category() == 2 {
+            self.local_variable[index + 1] = Value::Top;
+        }
+    }
+
+    pub fn push(&mut self, value: Value) {
+        self.operand_stack.push(value);
+    }
+
+    pub fn pop(&mut self) -> Value {
+        self.operand_stack
+            .pop()
+            .unwrap_or_else(|| panic!("Error: the operand stack is empty"))
+    }
+}
+
+#[test]
+fn test_store_category_2_value() {
+    use crate::instruction::frame_test::{dummy_class, dummy_method};
+
+    let method = Arc::new(dummy_method(vec![]));
+    let mut frame = Frame::create(&Rc::new(dummy_class()), &method);
+
+    frame.store(0, Value::Long(-1));
+    assert_eq!(frame.load(0), Value::Long(-1));
+    assert_eq!(frame.load(1), Value::Top);
+
+    // storing into the second half of the long makes its first half unusable
+    frame.store(1, Value::Int(7));
+    assert_eq!(frame.load(0), Value::Top);
+    assert_eq!(frame.load(1), Value::Int(7));
+
+    frame.push(Value::Double(0.5));
+    assert_eq!(frame.operand_stack.len(), 1);
+    assert_eq!(frame.pop().as_double(), 0.5);
 }
@@ -0,0 +1,113 @@
+use std::fmt;
+
+pub const ABSTRACT_METHOD_ERROR: &str = "java/lang/AbstractMethodError";
+pub const ARITHMETIC_EXCEPTION: &str = "java/lang/ArithmeticException";
+pub const CLASS_CIRCULARITY_ERROR: &str = "java/lang/ClassCircularityError";
+pub const CLASS_FORMAT_ERROR: &str = "java/lang/ClassFormatError";
+#[cfg(test)]
+pub const CLASS_NOT_FOUND_EXCEPTION: &str = "java/lang/ClassNotFoundException";
+pub const EXCEPTION_IN_INITIALIZER_ERROR: &str = "java/lang/ExceptionInInitializerError";
+pub const FIND_EXCEPTION: &str = "java/lang/module/FindException";
+pub const ILLEGAL_ACCESS_ERROR: &str = "java/lang/IllegalAccessError";
+#[cfg(test)]
+pub const INACCESSIBLE_OBJECT_EXCEPTION: &str = "java/lang/reflect/InaccessibleObjectException";
+pub const INCOMPATIBLE_CLASS_CHANGE_ERROR: &str = "java/lang/IncompatibleClassChangeError";
+pub const LAYER_INSTANTIATION_EXCEPTION: &str = "java/lang/LayerInstantiationException";
+pub const LINKAGE_ERROR: &str = "java/lang/LinkageError";
+pub const NO_CLASS_DEF_FOUND_ERROR: &str = "java/lang/NoClassDefFoundError";
+pub const NO_SUCH_FIELD_ERROR: &str = "java/lang/NoSuchFieldError";
+pub const NO_SUCH_METHOD_ERROR: &str = "java/lang/NoSuchMethodError";
+pub const SERVICE_CONFIGURATION_ERROR: &str = "java/util/ServiceConfigurationError";
+pub const UNSUPPORTED_CLASS_VERSION_ERROR: &str = "java/lang/UnsupportedClassVersionError";
+pub const UNSUPPORTED_OPERATION_EXCEPTION: &str = "java/lang/UnsupportedOperationException";
+
+/// the throwables above which are subclasses of java.lang.Error
+const ERRORS: [&str; 14] = [
+    ABSTRACT_METHOD_ERROR,
+    CLASS_CIRCULARITY_ERROR,
+    CLASS_FORMAT_ERROR,
+    EXCEPTION_IN_INITIALIZER_ERROR,
+    ILLEGAL_ACCESS_ERROR,
+    INCOMPATIBLE_CLASS_CHANGE_ERROR,
+    LINKAGE_ERROR,
+    NO_CLASS_DEF_FOUND_ERROR,
+    NO_SUCH_FIELD_ERROR,
+    NO_SUCH_METHOD_ERROR,
+    SERVICE_CONFIGURATION_ERROR,
+    UNSUPPORTED_CLASS_VERSION_ERROR,
+    "java/lang/Error",
+    "java/lang/VirtualMachineError",
+];
+
+/// A Java throwable raised by the VM itself, such as linkage errors.
+/// There is no heap yet, so it is carried as the name of the throwable class and its message.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Throwable {
+    pub class_name: String,
+    pub message: String,
+    pub cause: Option<Box<Throwable>>,
+}
+
+impl Throwable {
+    pub fn new(class_name: &str, message: String) -> Throwable {
+        Throwable {
+            class_name: class_name.to_owned(),
+            message,
+            cause: None,
+        }
+    }
+
+    pub fn with_cause(class_name: &str, message: String, cause: Throwable) -> Throwable {
+        Throwable {
+            class_name: class_name.to_owned(),
+            message,
+            cause: Some(Box::new(cause)),
+        }
+    }
+
+    #[cfg(test)]
+    pub fn is(&self, class_name: &str) -> bool {
+        self.class_name == class_name
+    }
+
+    /// true for java.lang.Error and its subclasses.
+    /// the VM raises only the throwables it knows, so they are checked against a fixed list
+    /// rather than by loading the class hierarchy of the throwable.
+    pub fn is_error(&self) -> bool {
+        ERRORS.contains(&self.class_name.as_str())
+    }
+}
+
+/// printed in the same form as `Throwable.printStackTrace`, without the stack trace
+impl fmt::Display for Throwable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.class_name.replace('/', "."))?;
+        if !self.message.is_empty() {
+            write!(f, ": {}", self.message)?;
+        }
+        if let Some(cause) = &self.cause {
+            write!(f, "\nCaused by: {}", cause)?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_display_throwable() {
+    let cause = Throwable::new(CLASS_CIRCULARITY_ERROR, String::from("A"));
+    let result = Throwable::with_cause(NO_CLASS_DEF_FOUND_ERROR, String::from("B"), cause);
+
+    assert_eq!(
+        result.to_string(),
+        "java.lang.NoClassDefFoundError: B\nCaused by: java.lang.ClassCircularityError: A"
+    );
+}
+
+#[test]
+fn test_is_error() {
+    assert!(Throwable::new(NO_CLASS_DEF_FOUND_ERROR, String::new()).is_error());
+    assert!(Throwable::new(SERVICE_CONFIGURATION_ERROR, String::new()).is_error());
+    assert!(!Throwable::new(ARITHMETIC_EXCEPTION, String::new()).is_error());
+    // the name alone doesn't make an error
+    assert!(!Throwable::new("com/acme/MirrorError", String::new()).is_error());
+}
@@ -0,0 +1,113 @@
+use std::fmt;
+
+/// A reference to an object on the heap, by its index rather than a pointer, so that a
+/// collector can move the object by updating the table. no object is allocated yet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ObjectId(pub u32);
+
+/// A value of a local variable, an operand stack entry, or a static field, JVMS 2.2-2.5.
+/// Values are tagged by their type, so a collector can tell references from numbers.
+/// https://docs.oracle.com/javase/specs/jvms/se17/html/jvms-2.html#jvms-2.3
+///
+/// `long` and `double` are of category 2. They take two local variables, where the second
+/// is `Top`, and count two units of `max_stack`, while they are one entry of the stack.
+/// https://docs.oracle.com/javase/specs/jvms/se17/html/jvms-2.html#jvms-2.6.1
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Value {
+    /// `boolean`, `byte`, `char` and `short` are computed as `int` as well
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    /// `None` for `null`
+    Reference(Option<ObjectId>),
+    /// a local variable not assigned yet, or the second half of a `long` or `double`
+    Top,
+}
+
+impl Value {
+    /// the default value of a field of the type in the descriptor, JVMS 2.3, 2.4.
+    /// `B`, `C`, `I`, `S` and `Z` are all `int`.
+    pub fn default_of(descriptor: &str) -> Value {
+        match descriptor.as_bytes().first() {
+            Some(b'J') => Value::Long(0),
+            Some(b'F') => Value::Float(0.0),
+            Some(b'D') => Value::Double(0.0),
+            Some(b'L' | b'[') => Value::Reference(None),
+            _ => Value::Int(0),
+        }
+    }
+
+    /// 2 for `long` and `double`, 1 for the others
+    pub fn category(&self) -> usize {
+        match self {
+            Value::Long(_) | Value::Double(_) => 2,
+            _ => 1,
+        }
+    }
+
+    /// the instruction is only given a value of its type by the verified code.
+    /// anything else is a bug of the interpreter.
+    pub fn as_int(self) -> i32 {
+        match self {
+            Value::Int(value) => value,
+            other => panic!("Error: int is expected, but {}", other),
+        }
+    }
+
+    pub fn as_long(self) -> i64 {
+        match self {
+            Value::Long(value) => value,
+            other => panic!("Error: long is expected, but {}", other),
+        }
+    }
+
+    pub fn as_float(self) -> f32 {
+        match self {
+            Value::Float(value) => value,
+            other => panic!("Error: float is expected, but {}", other),
+        }
+    }
+
+    pub fn as_double(self) -> f64 {
+        match self {
+            Value::Double(value) => value,
+            other => panic!("Error: double is expected, but {}", other),
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Int(value) => write!(f, "int {}", value),
+            Value::Long(value) => write!(f, "long {}", value),
+            Value::Float(value) => write!(f, "float {}", value),
+            Value::Double(value) => write!(f, "double {}", value),
+            Value::Reference(None) => write!(f, "null"),
+            Value::Reference(Some(ObjectId(id))) => write!(f, "reference @{}", id),
+            Value::Top => write!(f, "top"),
+        }
+    }
+}
+
+#[test]
+fn test_default_of() {
+    assert_eq!(Value::default_of("I"), Value::Int(0));
+    assert_eq!(Value::default_of("Z"), Value::Int(0));
+    assert_eq!(Value::default_of("J"), Value::Long(0));
+    assert_eq!(Value::default_of("D"), Value::Double(0.0));
+    assert_eq!(
+        Value::default_of("Ljava/lang/String;"),
+        Value::Reference(None)
+    );
+    assert_eq!(Value::default_of("[I"), Value::Reference(None));
+}
+
+#[test]
+fn test_value_size() {
+    // one tag and the largest payload, so the stack and the locals stay compact
+    assert_eq!(std::mem::size_of::<Value>(), 16);
+    assert_eq!(Value::Long(0).category(), 2);
+    assert_eq!(Value::Reference(None).category(), 1);
+}
@@ -0,0 +1,546 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+
+use crate::binary::{read_to, read_u16_le, read_u32_le, read_u64_le};
+use crate::inflate::inflate;
+
+/// ZIP archive reader for JAR files, driven by the central directory.
+/// https://pkware.cachefly.net/webdocs/casestudies/APPNOTE.TXT
+///
+/// Entry sizes always come from the central directory, so entries written with a
+/// data descriptor (general purpose flag bit 3, zero sizes in the local header) are
+/// read the same way as the others.
+pub struct ZipArchive {
+    pub path: PathBuf,
+    data: Vec<u8>,
+    entries: HashMap<String, ZipEntry>,
+    /// entry names in the central directory order
+    names: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct ZipEntry {
+    pub name: String,
+    pub flags: u16,
+    pub compression_method: u16,
+    pub crc32: u32,
+    pub compressed_size: u64,
+    pub uncompressed_size: u64,
+    pub local_header_offset: u64,
+}
+
+const LOCAL_FILE_HEADER_SIGNATURE: u32 = 0x04034b50;
+const CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x02014b50;
+const END_OF_CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x06054b50;
+const ZIP64_END_OF_CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x06064b50;
+const ZIP64_END_OF_CENTRAL_DIRECTORY_LOCATOR_SIGNATURE: u32 = 0x07064b50;
+const ZIP64_EXTRA_FIELD_ID: u16 = 0x0001;
+
+const LOCAL_FILE_HEADER_SIZE: usize = 30;
+const CENTRAL_DIRECTORY_HEADER_SIZE: usize = 46;
+const END_OF_CENTRAL_DIRECTORY_SIZE: usize = 22;
+const ZIP64_END_OF_CENTRAL_DIRECTORY_SIZE: usize = 56;
+const ZIP64_END_OF_CENTRAL_DIRECTORY_LOCATOR_SIZE: usize = 20;
+const MAX_COMMENT_LENGTH: usize = 0xFFFF;
+
+/// general purpose flag bit 0, the entry is encrypted
+const FLAG_ENCRYPTED: u16 = 0x0001;
+/// general purpose flag bit 11, the name is encoded in UTF-8
+const FLAG_UTF8: u16 = 0x0800;
+
+/// code page 437, the encoding of the names without the UTF-8 flag, for the bytes above ASCII
+#[rustfmt::skip]
+const CP437: [char; 128] = [
+    'Ç', 'ü', 'é', 'â', 'ä', 'à', 'å', 'ç', 'ê', 'ë', 'è', 'ï', 'î', 'ì', 'Ä', 'Å',
+    'É', 'æ', 'Æ', 'ô', 'ö', 'ò', 'û', 'ù', 'ÿ', 'Ö', 'Ü', '¢', '£', '¥', '₧', 'ƒ',
+    'á', 'í', 'ó', 'ú', 'ñ', 'Ñ', 'ª', 'º', '¿', '⌐', '¬', '½', '¼', '¡', '«', '»',
+    '░', '▒', '▓', '│', '┤', '╡', '╢', '╖', '╕', '╣', '║', '╗', '╝', '╜', '╛', '┐',
+    '└', '┴', '┬', '├', '─', '┼', '╞', '╟', '╚', '╔', '╩', '╦', '╠', '═', '╬', '╧',
+    '╨', '╤', '╥', '╙', '╘', '╒', '╓', '╫', '╪', '┘', '┌', '█', '▄', '▌', '▐', '▀',
+    'α', 'ß', 'Γ', 'π', 'Σ', 'σ', 'µ', 'τ', 'Φ', 'Θ', 'Ω', 'δ', '∞', 'φ', 'ε', '∩',
+    '≡', '±', '≥', '≤', '⌠', '⌡', '÷', '≈', '°', '∙', '·', '√', 'ⁿ', '²', '■', '\u{a0}',
+];
+
+const METHOD_STORED: u16 = 0;
+const METHOD_DEFLATED: u16 = 8;
+
+impl ZipArchive {
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<ZipArchive> {
+        let data = fs::read(path.as_ref())?;
+        ZipArchive::parse_from(path.as_ref().to_path_buf(), data)
+    }
+
+    pub fn parse_from(path: PathBuf, data: Vec<u8>) -> io::Result<ZipArchive> {
+        let (entry_count, central_directory_offset) = find_central_directory(&data)?;
+
+        let mut entries = HashMap::new();
+        let mut names = vec![];
+        let mut offset = central_directory_offset;
+        for _ in 0..entry_count {
+            let (entry, next_offset) = parse_central_directory_header(&data, offset)?;
+            offset = next_offset;
+            names.push(entry.name.clone());
+            entries.insert(entry.name.clone(), entry);
+        }
+
+        Ok(ZipArchive {
+            path,
+            data,
+            entries,
+            names,
+        })
+    }
+
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.entries.contains_key(name)
+    }
+
+    /// read and decompress an entry. the crc-32 of the result is verified.
+    pub fn read(&self, name: &str) -> io::Result<Vec<u8>> {
+        let entry = self.entries.get(name).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{} is not found in {}", name, self.path.display()),
+            )
+        })?;
+
+        if entry.flags & FLAG_ENCRYPTED != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("encrypted entry {}", name),
+            ));
+        }
+
+        let offset = entry.local_header_offset as usize;
+        check_bounds(&self.data, offset, LOCAL_FILE_HEADER_SIZE)?;
+        let mut cursor = Cursor::new(self.data.as_slice());
+        cursor.set_position(offset as u64);
+        if read_u32_le(&mut cursor) != LOCAL_FILE_HEADER_SIGNATURE {
+            return Err(invalid_data(format!("bad local file header for {}", name)));
+        }
+        // the local name and extra field lengths may differ from the central directory ones
+        cursor.set_position((offset + 26) as u64);
+        let name_length = read_u16_le(&mut cursor) as usize;
+        let extra_length = read_u16_le(&mut cursor) as usize;
+
+        let data_offset = offset + LOCAL_FILE_HEADER_SIZE + name_length + extra_length;
+        check_bounds(&self.data, data_offset, entry.compressed_size as usize)?;
+        let compressed = &self.data[data_offset..data_offset + entry.compressed_size as usize];
+
+        let content = match entry.compression_method {
+            METHOD_STORED => compressed.to_vec(),
+            METHOD_DEFLATED => inflate(compressed)?,
+            method => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    format!("unsupported compression method {} for {}", method, name),
+                ))
+            }
+        };
+
+        if content.len() as u64 != entry.uncompressed_size {
+            return Err(invalid_data(format!(
+                "invalid entry size for {}: expected {} but got {}",
+                name,
+                entry.uncompressed_size,
+                content.len()
+            )));
+        }
+        if crc32(&content) != entry.crc32 {
+            return Err(invalid_data(format!("invalid entry crc-32 for {}", name)));
+        }
+        Ok(content)
+    }
+}
+
+impl fmt::Debug for ZipArchive {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ZipArchive")
+            .field("path", &self.path)
+            .field("entries", &self.names.len())
+            .finish()
+    }
+}
+
+/// returns (total entry count, central directory offset), reading the zip64 record when needed.
+fn find_central_directory(data: &[u8]) -> io::Result<(u64, u64)> {
+    let eocd_offset = find_end_of_central_directory(data)?;
+    let mut cursor = Cursor::new(data);
+    cursor.set_position((eocd_offset + 10) as u64);
+    let entry_count = read_u16_le(&mut cursor);
+    let _central_directory_size = read_u32_le(&mut cursor);
+    let central_directory_offset = read_u32_le(&mut cursor);
+
+    if entry_count != 0xFFFF && central_directory_offset != 0xFFFF_FFFF {
+        return Ok((entry_count as u64, central_directory_offset as u64));
+    }
+
+    // ZIP64: the locator sits right before the end of central directory record
+    let locator_offset = eocd_offset
+        .checked_sub(ZIP64_END_OF_CENTRAL_DIRECTORY_LOCATOR_SIZE)
+        .ok_or_else(|| invalid_data("missing zip64 end of central directory locator".to_owned()))?;
+    cursor.set_position(locator_offset as u64);
+    if read_u32_le(&mut cursor) != ZIP64_END_OF_CENTRAL_DIRECTORY_LOCATOR_SIGNATURE {
+        return Err(invalid_data(
+            "missing zip64 end of central directory locator".to_owned(),
+        ));
+    }
+    let _disk_number = read_u32_le(&mut cursor);
+    let zip64_eocd_offset = read_u64_le(&mut cursor) as usize;
+
+    check_bounds(data, zip64_eocd_offset, ZIP64_END_OF_CENTRAL_DIRECTORY_SIZE)?;
+    cursor.set_position(zip64_eocd_offset as u64);
+    if read_u32_le(&mut cursor) != ZIP64_END_OF_CENTRAL_DIRECTORY_SIGNATURE {
+        return Err(invalid_data(
+            "bad zip64 end of central directory record".to_owned(),
+        ));
+    }
+    cursor.set_position((zip64_eocd_offset + 32) as u64);
+    let entry_count = read_u64_le(&mut cursor);
+    let _central_directory_size = read_u64_le(&mut cursor);
+    let central_directory_offset = read_u64_le(&mut cursor);
+    Ok((entry_count, central_directory_offset))
+}
+
+/// the record is at the end of the file, followed only by a variable length comment.
+fn find_end_of_central_directory(data: &[u8]) -> io::Result<usize> {
+    let not_a_zip = || invalid_data("end of central directory record is not found".to_owned());
+    let last = data
+        .len()
+        .checked_sub(END_OF_CENTRAL_DIRECTORY_SIZE)
+        .ok_or_else(not_a_zip)?;
+    let first = last.saturating_sub(MAX_COMMENT_LENGTH);
+    (first..=last)
+        .rev()
+        .find(|&offset| {
+            data[offset..offset + 4] == END_OF_CENTRAL_DIRECTORY_SIGNATURE.to_le_bytes()
+        })
+        .ok_or_else(not_a_zip)
+}
+
+fn parse_central_directory_header(data: &[u8], offset: u64) -> io::Result<(ZipEntry, u64)> {
+    let offset = offset as usize;
+    check_bounds(data, offset, CENTRAL_DIRECTORY_HEADER_SIZE)?;
+    let mut cursor = Cursor::new(data);
+    cursor.set_position(offset as u64);
+
+    if read_u32_le(&mut cursor) != CENTRAL_DIRECTORY_SIGNATURE {
+        return Err(invalid_data(format!(
+            "bad central directory header at {}",
+            offset
+        )));
+    }
+    let _version_made_by = read_u16_le(&mut cursor);
+    let _version_needed = read_u16_le(&mut cursor);
+    let flags = read_u16_le(&mut cursor);
+    let compression_method = read_u16_le(&mut cursor);
+    let _last_modified_time = read_u16_le(&mut cursor);
+    let _last_modified_date = read_u16_le(&mut cursor);
+    let crc32 = read_u32_le(&mut cursor);
+    let compressed_size = read_u32_le(&mut cursor);
+    let uncompressed_size = read_u32_le(&mut cursor);
+    let name_length = read_u16_le(&mut cursor) as usize;
+    let extra_length = read_u16_le(&mut cursor) as usize;
+    let comment_length = read_u16_le(&mut cursor) as usize;
+    let _disk_number_start = read_u16_le(&mut cursor);
+    let _internal_attributes = read_u16_le(&mut cursor);
+    let _external_attributes = read_u32_le(&mut cursor);
+    let local_header_offset = read_u32_le(&mut cursor);
+
+    check_bounds(
+        data,
+        offset + CENTRAL_DIRECTORY_HEADER_SIZE,
+        name_length + extra_length + comment_length,
+    )?;
+    let name = decode_name(&read_to(&mut cursor, name_length), flags)
+        .ok_or_else(|| invalid_data(format!("invalid entry name at {}", offset)))?;
+    let extra = read_to(&mut cursor, extra_length);
+
+    let mut entry = ZipEntry {
+        name,
+        flags,
+        compression_method,
+        crc32,
+        compressed_size: compressed_size as u64,
+        uncompressed_size: uncompressed_size as u64,
+        local_header_offset: local_header_offset as u64,
+    };
+    apply_zip64_extra_field(&mut entry, &extra)?;
+
+    let next_offset =
+        offset + CENTRAL_DIRECTORY_HEADER_SIZE + name_length + extra_length + comment_length;
+    Ok((entry, next_offset as u64))
+}
+
+/// a name is in UTF-8 with the flag bit 11, and in code page 437 without it, APPNOTE appendix D
+fn decode_name(bytes: &[u8], flags: u16) -> Option<String> {
+    if flags & FLAG_UTF8 != 0 {
+        return String::from_utf8(bytes.to_vec()).ok();
+    }
+    let name = bytes
+        .iter()
+        .map(|&byte| match byte {
+            0x00..=0x7F => byte as char,
+            _ => CP437[(byte - 0x80) as usize],
+        })
+        .collect();
+    Some(name)
+}
+
+/// the zip64 extra field holds, in this order, only the values saturated in the header.
+fn apply_zip64_extra_field(entry: &mut ZipEntry, extra: &[u8]) -> io::Result<()> {
+    let mut cursor = Cursor::new(extra);
+    while (cursor.position() as usize) + 4 <= extra.len() {
+        let id = read_u16_le(&mut cursor);
+        let size = read_u16_le(&mut cursor) as usize;
+        let start = cursor.position() as usize;
+        check_bounds(extra, start, size)?;
+        if id != ZIP64_EXTRA_FIELD_ID {
+            cursor.set_position((start + size) as u64);
+            continue;
+        }
+
+        let mut field = Cursor::new(&extra[start..start + size]);
+        let mut next_u64 = || -> io::Result<u64> {
+            if (field.position() as usize) + 8 > size {
+                return Err(invalid_data(format!(
+                    "truncated zip64 extra field for {}",
+                    entry.name
+                )));
+            }
+            Ok(read_u64_le(&mut field))
+        };
+        if entry.uncompressed_size == 0xFFFF_FFFF {
+            entry.uncompressed_size = next_u64()?;
+        }
+        if entry.compressed_size == 0xFFFF_FFFF {
+            entry.compressed_size = next_u64()?;
+        }
+        if entry.local_header_offset == 0xFFFF_FFFF {
+            entry.local_header_offset = next_u64()?;
+        }
+        break;
+    }
+    Ok(())
+}
+
+fn check_bounds(data: &[u8], offset: usize, length: usize) -> io::Result<()> {
+    match offset.checked_add(length) {
+        Some(end) if end <= data.len() => Ok(()),
+        _ => Err(invalid_data(format!(
+            "zip record out of bounds: offset {} length {} file size {}",
+            offset,
+            length,
+            data.len()
+        ))),
+    }
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+const CRC32_TABLE: [u32; 256] = crc32_table();
+
+const fn crc32_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut n = 0;
+    while n < 256 {
+        let mut c = n as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 != 0 {
+                0xEDB88320 ^ (c >> 1)
+            } else {
+                c >> 1
+            };
+            k += 1;
+        }
+        table[n] = c;
+        n += 1;
+    }
+    table
+}
+
+pub fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in bytes {
+        crc = CRC32_TABLE[((crc ^ byte as u32) & 0xFF) as usize] ^ (crc >> 8);
+    }
+    crc ^ 0xFFFF_FFFF
+}
+
+#[test]
+fn test_crc32() {
+    assert_eq!(crc32(b""), 0);
+    assert_eq!(crc32(b"123456789"), 0xCBF43926);
+}
+
+#[test]
+fn test_read_deflated_with_data_descriptor() {
+    use crate::binary::read_binary_file;
+
+    let expected = read_binary_file(&"java/com/acme/Main.class".to_owned()).unwrap();
+    let archive = ZipArchive::open("java/acme.jar").unwrap();
+
+    // the jar tool writes deflated entries followed by a data descriptor
+    let entry = &archive.entries["com/acme/Main.class"];
+    assert_eq!(entry.compression_method, METHOD_DEFLATED);
+    assert_ne!(entry.flags & 0x08, 0);
+
+    assert_eq!(archive.read("com/acme/Main.class").unwrap(), expected);
+    assert!(archive.contains("META-INF/MANIFEST.MF"));
+    assert_eq!(
+        archive.read("NotExists.class").unwrap_err().kind(),
+        io::ErrorKind::NotFound
+    );
+}
+
+#[test]
+fn test_read_stored() {
+    use crate::binary::read_binary_file;
+
+    let expected = read_binary_file(&"java/com/acme/Main.class".to_owned()).unwrap();
+    let archive = ZipArchive::open("java/acme-stored.jar").unwrap();
+
+    assert_eq!(
+        archive.entries["com/acme/Main.class"].compression_method,
+        METHOD_STORED
+    );
+    assert_eq!(archive.read("com/acme/Main.class").unwrap(), expected);
+}
+
+#[test]
+fn test_read_zip64() {
+    let content = b"zip64 entry";
+    let data = zip_test::zip64_archive("Zip64.class", content);
+
+    let archive = ZipArchive::parse_from(PathBuf::from("zip64.jar"), data).unwrap();
+
+    assert_eq!(archive.names(), ["Zip64.class"]);
+    assert_eq!(archive.read("Zip64.class").unwrap(), content);
+}
+
+#[test]
+fn test_decode_entry_names() {
+    let content = b"named in code page 437";
+    let mut data = zip_test::zip64_archive("X.class", content);
+    for position in 0..data.len() - 6 {
+        if &data[position..position + 7] == b"X.class" {
+            data[position] = 0x80;
+        }
+    }
+
+    let archive = ZipArchive::parse_from(PathBuf::from("cp437.jar"), data.clone()).unwrap();
+    assert_eq!(archive.names(), ["Ç.class"]);
+    assert_eq!(archive.read("Ç.class").unwrap(), content);
+
+    // 0x80 alone is not UTF-8
+    let central_directory = data
+        .windows(4)
+        .position(|bytes| bytes == CENTRAL_DIRECTORY_SIGNATURE.to_le_bytes())
+        .unwrap();
+    data[central_directory + 8..central_directory + 10].copy_from_slice(&FLAG_UTF8.to_le_bytes());
+    let result = ZipArchive::parse_from(PathBuf::from("utf8.jar"), data);
+    assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn test_parse_not_a_zip() {
+    let result = ZipArchive::parse_from(PathBuf::from("broken.jar"), vec![0xCA, 0xFE, 0xBA, 0xBE]);
+
+    assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidData);
+}
+
+#[cfg(test)]
+mod zip_test {
+    use crate::zip::crc32;
+
+    /// a single stored entry, with every size and offset moved into zip64 records
+    pub fn zip64_archive(name: &str, content: &[u8]) -> Vec<u8> {
+        let mut data = vec![];
+        let crc = crc32(content);
+
+        // local file header
+        data.extend(0x04034b50u32.to_le_bytes());
+        data.extend(45u16.to_le_bytes());
+        data.extend(0u16.to_le_bytes());
+        data.extend(0u16.to_le_bytes());
+        data.extend([0u8; 4]);
+        data.extend(crc.to_le_bytes());
+        data.extend(0xFFFF_FFFFu32.to_le_bytes());
+        data.extend(0xFFFF_FFFFu32.to_le_bytes());
+        data.extend((name.len() as u16).to_le_bytes());
+        data.extend(20u16.to_le_bytes());
+        data.extend(name.as_bytes());
+        data.extend(0x0001u16.to_le_bytes());
+        data.extend(16u16.to_le_bytes());
+        data.extend((content.len() as u64).to_le_bytes());
+        data.extend((content.len() as u64).to_le_bytes());
+        data.extend(content);
+
+        // central directory
+        let central_directory_offset = data.len() as u64;
+        data.extend(0x02014b50u32.to_le_bytes());
+        data.extend(45u16.to_le_bytes());
+        data.extend(45u16.to_le_bytes());
+        data.extend(0u16.to_le_bytes());
+        data.extend(0u16.to_le_bytes());
+        data.extend([0u8; 4]);
+        data.extend(crc.to_le_bytes());
+        data.extend(0xFFFF_FFFFu32.to_le_bytes());
+        data.extend(0xFFFF_FFFFu32.to_le_bytes());
+        data.extend((name.len() as u16).to_le_bytes());
+        data.extend(28u16.to_le_bytes());
+        data.extend(0u16.to_le_bytes());
+        data.extend(0u16.to_le_bytes());
+        data.extend(0u16.to_le_bytes());
+        data.extend(0u32.to_le_bytes());
+        data.extend(0xFFFF_FFFFu32.to_le_bytes());
+        data.extend(name.as_bytes());
+        data.extend(0x0001u16.to_le_bytes());
+        data.extend(24u16.to_le_bytes());
+        data.extend((content.len() as u64).to_le_bytes());
+        data.extend((content.len() as u64).to_le_bytes());
+        data.extend(0u64.to_le_bytes());
+        let central_directory_size = data.len() as u64 - central_directory_offset;
+
+        // zip64 end of central directory record
+        let zip64_eocd_offset = data.len() as u64;
+        data.extend(0x06064b50u32.to_le_bytes());
+        data.extend(44u64.to_le_bytes());
+        data.extend(45u16.to_le_bytes());
+        data.extend(45u16.to_le_bytes());
+        data.extend(0u32.to_le_bytes());
+        data.extend(0u32.to_le_bytes());
+        data.extend(1u64.to_le_bytes());
+        data.extend(1u64.to_le_bytes());
+        data.extend(central_directory_size.to_le_bytes());
+        data.extend(central_directory_offset.to_le_bytes());
+
+        // zip64 end of central directory locator
+        data.extend(0x07064b50u32.to_le_bytes());
+        data.extend(0u32.to_le_bytes());
+        data.extend(zip64_eocd_offset.to_le_bytes());
+        data.extend(1u32.to_le_bytes());
+
+        // end of central directory record
+        data.extend(0x06054b50u32.to_le_bytes());
+        data.extend(0u16.to_le_bytes());
+        data.extend(0u16.to_le_bytes());
+        data.extend(0xFFFFu16.to_le_bytes());
+        data.extend(0xFFFFu16.to_le_bytes());
+        data.extend(0xFFFF_FFFFu32.to_le_bytes());
+        data.extend(0xFFFF_FFFFu32.to_le_bytes());
+        data.extend(0u16.to_le_bytes());
+        data
+    }
+}